@@ -0,0 +1,61 @@
+//! CRC-32 (IEEE 802.3), as used by zlib, PNG, and others.
+
+const fn make_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 {
+                0xEDB88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+const TABLE: [u32; 256] = make_table();
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Crc32(u32);
+
+impl Crc32 {
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self(0xFFFFFFFF)
+    }
+
+    pub(crate) fn update(&mut self, buf: &[u8]) {
+        let mut c = self.0;
+        for b in buf.iter().copied() {
+            c = TABLE[((c ^ b as u32) & 0xFF) as usize] ^ (c >> 8);
+        }
+        self.0 = c;
+    }
+
+    #[inline]
+    pub(crate) const fn finish(&self) -> u32 {
+        self.0 ^ 0xFFFFFFFF
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn crc32(buf: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(buf);
+    crc.finish()
+}
+
+#[cfg(test)]
+mod tests;
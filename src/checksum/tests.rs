@@ -0,0 +1,19 @@
+use super::{crc32, Crc32};
+
+#[test]
+fn crc32_empty() {
+    assert_eq!(crc32(b""), 0);
+}
+
+#[test]
+fn crc32_check_value() {
+    assert_eq!(crc32(b"123456789"), 0xCBF43926);
+}
+
+#[test]
+fn crc32_incremental() {
+    let mut crc = Crc32::new();
+    crc.update(b"1234");
+    crc.update(b"56789");
+    assert_eq!(crc.finish(), 0xCBF43926);
+}
@@ -1,5 +1,11 @@
+use crate::output::OutputFormat;
 use camino::Utf8PathBuf;
 use clap::{Args, Parser, Subcommand};
+use zcue::cue_file::CueFormat;
+use zcue::database::Game;
+use zcue::render::ImageFormat;
+use zcue::subtitles::SubtitleFormat;
+use zcue::{Codec, CuePosition, Profile};
 
 #[derive(Debug, Parser)]
 #[command(
@@ -11,27 +17,256 @@ use clap::{Args, Parser, Subcommand};
 pub(crate) struct MainArgs {
     #[command(subcommand)]
     pub(crate) command: Commands,
+    /// How to print the files read and written, warnings, and errors
+    #[clap(long, value_enum, global = true, default_value_t)]
+    pub(crate) output: OutputFormat,
+    /// Print nothing but errors
+    #[clap(long, short, action, global = true, conflicts_with = "output")]
+    pub(crate) quiet: bool,
 }
 
 #[derive(Debug, Args)]
 pub(crate) struct ExtractArgs {
-    /// The wave file to read from
+    /// The wave file to read from, or an AIFF or FLAC file to convert (`-`
+    /// for standard input)
     pub(crate) input_file: Utf8PathBuf,
-    /// The cue file to write cue points to
+    /// The cue file to write cue points to (`-` for standard output)
     pub(crate) cue_file: Utf8PathBuf,
     /// Specify to write sample information
     #[clap(long, action)]
     pub(crate) samples: bool,
+    /// Offset the timestamps by the `bext` time reference, so they are the
+    /// time of day
+    #[clap(long, action)]
+    pub(crate) bext_offset: bool,
+    /// The format of the cue file [default: from the file extension]
+    #[clap(long, value_enum)]
+    pub(crate) format: Option<CueFormat>,
 }
 
 #[derive(Debug, Args)]
 pub(crate) struct ApplyArgs {
-    /// The wave file to read from
+    /// The wave file to read from, or an AIFF or FLAC file to convert (`-`
+    /// for standard input)
+    pub(crate) input_file: Utf8PathBuf,
+    /// The cue file to read cue points from (`-` for standard input)
+    pub(crate) cue_file: Utf8PathBuf,
+    /// The wave file to write to, or a FLAC file to encode to (`-` for
+    /// standard output)
+    #[clap(required_unless_present = "in_place")]
+    pub(crate) output_file: Option<Utf8PathBuf>,
+    /// Update the input file in place, instead of writing to an output file
+    #[clap(long, action, conflicts_with = "output_file")]
+    pub(crate) in_place: bool,
+    /// When updating in place, keep the original file as `<input file>.bak`
+    #[clap(long, action, requires = "in_place")]
+    pub(crate) backup: bool,
+    /// Where to write the cue chunk, relative to the data chunk
+    #[clap(long, value_enum, default_value_t)]
+    pub(crate) cue_position: CuePosition,
+    /// Replace the sample loops with loops between pairs of cue points
+    #[clap(long, action)]
+    pub(crate) loops_from_cues: bool,
+    /// Remove the `INFO` metadata, instead of keeping it
+    #[clap(long, action)]
+    pub(crate) strip_info: bool,
+    /// The timestamps are the time of day, so offset them by the `bext`
+    /// time reference
+    #[clap(long, action)]
+    pub(crate) bext_offset: bool,
+    /// The format of the cue file [default: from the file extension]
+    #[clap(long, value_enum)]
+    pub(crate) format: Option<CueFormat>,
+    /// Check that the output can be loaded by the games
+    #[clap(long, value_enum, default_value_t)]
+    pub(crate) profile: Profile,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ConvertArgs {
+    /// The wave file to read from, or an AIFF or FLAC file to convert (`-`
+    /// for standard input)
     pub(crate) input_file: Utf8PathBuf,
-    /// The cue file to read cue points from
+    /// The wave file to write to, or a FLAC file to encode to (`-` for
+    /// standard output)
+    pub(crate) output_file: Utf8PathBuf,
+    /// The codec to convert the audio data to
+    #[clap(long, value_enum, default_value_t = Codec::Pcm)]
+    pub(crate) codec: Codec,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SubtitlesExportArgs {
+    /// The wave file or cue file to read cue points from
+    pub(crate) input_file: Utf8PathBuf,
+    /// The text file to read subtitle lines from, one per cue point
+    pub(crate) lines_file: Utf8PathBuf,
+    /// The subtitle file to write to
+    pub(crate) output_file: Utf8PathBuf,
+    /// The format of the cue file, if the input is not a wave file [default: from the file extension]
+    #[clap(long, value_enum)]
+    pub(crate) format: Option<CueFormat>,
+    /// The duration of the audio in seconds, if the input is not a wave file
+    #[clap(long)]
+    pub(crate) duration: Option<f64>,
+    /// The sample rate in Hz, if the input is a cue file with times in samples
+    #[clap(long)]
+    pub(crate) samples_per_sec: Option<u32>,
+    /// The format of the subtitle file [default: from the file extension]
+    #[clap(long, value_enum)]
+    pub(crate) subtitle_format: Option<SubtitleFormat>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SubtitlesImportArgs {
+    /// The subtitle file to read from
+    pub(crate) subtitle_file: Utf8PathBuf,
+    /// The cue file to write cue points to (`-` for standard output)
     pub(crate) cue_file: Utf8PathBuf,
+    /// The format of the cue file [default: from the file extension]
+    #[clap(long, value_enum)]
+    pub(crate) format: Option<CueFormat>,
+    /// The text file to write subtitle lines to, one per cue point
+    #[clap(long)]
+    pub(crate) lines: Option<Utf8PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum SubtitlesCommands {
+    /// Create subtitles from cue points and subtitle lines
+    #[command(arg_required_else_help(true))]
+    Export(SubtitlesExportArgs),
+    /// Create cue points from subtitles
+    #[command(arg_required_else_help(true))]
+    Import(SubtitlesImportArgs),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SubtitlesArgs {
+    #[command(subcommand)]
+    pub(crate) command: SubtitlesCommands,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct InfoArgs {
+    /// The wave files, or directories of wave files, to read from
+    #[clap(required = true)]
+    pub(crate) input_files: Vec<Utf8PathBuf>,
+    /// The database of known files to identify the wave files with
+    #[clap(long)]
+    pub(crate) database: Option<Utf8PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CatalogArgs {
+    /// The database of known files to add to, which is created if missing
+    pub(crate) database_file: Utf8PathBuf,
+    /// The wave files, or directories of wave files, to add
+    #[clap(required = true)]
+    pub(crate) input_files: Vec<Utf8PathBuf>,
+    /// The game the wave files were shipped with
+    #[clap(long, value_enum)]
+    pub(crate) game: Game,
+    /// The archive the wave files were shipped in, e.g. `soundsH`
+    #[clap(long)]
+    pub(crate) archive: String,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct RestoreArgs {
+    /// The database of known files to look up the original cue points in
+    pub(crate) database_file: Utf8PathBuf,
+    /// The wave file to read from
+    pub(crate) input_file: Utf8PathBuf,
     /// The wave file to write to
+    #[clap(required_unless_present = "in_place")]
+    pub(crate) output_file: Option<Utf8PathBuf>,
+    /// Update the input file in place, instead of writing to an output file
+    #[clap(long, action, conflicts_with = "output_file")]
+    pub(crate) in_place: bool,
+    /// When updating in place, keep the original file as `<input file>.bak`
+    #[clap(long, action, requires = "in_place")]
+    pub(crate) backup: bool,
+    /// Where to write the cue chunk, relative to the data chunk
+    #[clap(long, value_enum, default_value_t)]
+    pub(crate) cue_position: CuePosition,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ValidateArgs {
+    /// The wave files, or directories of wave files, to validate
+    #[clap(required = true)]
+    pub(crate) input_files: Vec<Utf8PathBuf>,
+    /// Check that the wave files can be loaded by the games
+    #[clap(long, value_enum, default_value_t)]
+    pub(crate) profile: Profile,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct RoundtripArgs {
+    /// The wave files, or directories of wave files, to read and write again
+    #[clap(required = true)]
+    pub(crate) input_files: Vec<Utf8PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct BuildArgs {
+    /// The manifest file to build
+    #[clap(default_value = "zcue.toml")]
+    pub(crate) manifest_file: Utf8PathBuf,
+    /// Build every entry, even if its inputs are unchanged
+    #[clap(long, action)]
+    pub(crate) force: bool,
+    /// The JSON file to write a report of every output to
+    #[clap(long)]
+    pub(crate) report: Option<Utf8PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct RenderArgs {
+    /// The wave file to read from, or an AIFF or FLAC file to convert (`-`
+    /// for standard input)
+    pub(crate) input_file: Utf8PathBuf,
+    /// The PNG or SVG image to write to
     pub(crate) output_file: Utf8PathBuf,
+    /// The cue file to draw cue points from, instead of the wave file
+    #[clap(long)]
+    pub(crate) cue_file: Option<Utf8PathBuf>,
+    /// The format of the cue file [default: from the file extension]
+    #[clap(long, value_enum, requires = "cue_file")]
+    pub(crate) format: Option<CueFormat>,
+    /// The format of the image [default: from the file extension]
+    #[clap(long, value_enum)]
+    pub(crate) image_format: Option<ImageFormat>,
+    /// The width of the image in pixels
+    #[clap(long, default_value_t = 1200)]
+    pub(crate) width: u32,
+    /// The height of the image in pixels
+    #[clap(long, default_value_t = 300)]
+    pub(crate) height: u32,
+    /// Zoom in, starting at this time in seconds
+    #[clap(long, default_value_t = 0.0)]
+    pub(crate) start: f32,
+    /// Zoom in, ending at this time in seconds [default: the end of the audio]
+    #[clap(long)]
+    pub(crate) end: Option<f32>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct EditArgs {
+    /// The wave file to edit, or an AIFF or FLAC file to convert
+    pub(crate) input_file: Utf8PathBuf,
+    /// The wave file to save to [default: the input file]
+    pub(crate) output_file: Option<Utf8PathBuf>,
+    /// Where to write the cue chunk, relative to the data chunk
+    #[clap(long, value_enum, default_value_t)]
+    pub(crate) cue_position: CuePosition,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SchemaArgs {
+    /// The file to write the JSON Schema to [default: standard output]
+    pub(crate) output_file: Option<Utf8PathBuf>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -42,4 +277,59 @@ pub(crate) enum Commands {
     /// Apply cue points to a wave file
     #[command(arg_required_else_help(true))]
     Apply(ApplyArgs),
+    /// Convert the audio data of a wave file, e.g. decode ADPCM to PCM
+    #[command(arg_required_else_help(true))]
+    Convert(ConvertArgs),
+    /// Convert between cue points and subtitles
+    #[command(arg_required_else_help(true))]
+    Subtitles(SubtitlesArgs),
+    /// Print information about wave files
+    #[command(arg_required_else_help(true))]
+    Info(InfoArgs),
+    /// Check that wave files can be read
+    #[command(arg_required_else_help(true))]
+    Validate(ValidateArgs),
+    /// Check that wave files are unchanged by reading and writing them again
+    #[command(arg_required_else_help(true))]
+    Roundtrip(RoundtripArgs),
+    /// Build the outputs of a manifest whose inputs changed
+    Build(BuildArgs),
+    /// Add wave files and their cue points to a database of known files
+    #[command(arg_required_else_help(true))]
+    Catalog(CatalogArgs),
+    /// Restore the original cue points of a known wave file
+    #[command(arg_required_else_help(true))]
+    Restore(RestoreArgs),
+    /// Draw the waveform and cue points of a wave file as an image
+    #[command(arg_required_else_help(true))]
+    Render(RenderArgs),
+    /// Edit the cue points of a wave file interactively in the terminal
+    #[command(arg_required_else_help(true))]
+    Edit(EditArgs),
+    /// Print the JSON Schema of JSON cue files
+    Schema(SchemaArgs),
+}
+
+impl Commands {
+    /// The name of the command, e.g. for the JSON result object.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Extract(_) => "extract",
+            Self::Apply(_) => "apply",
+            Self::Convert(_) => "convert",
+            Self::Subtitles(args) => match args.command {
+                SubtitlesCommands::Export(_) => "subtitles export",
+                SubtitlesCommands::Import(_) => "subtitles import",
+            },
+            Self::Info(_) => "info",
+            Self::Validate(_) => "validate",
+            Self::Roundtrip(_) => "roundtrip",
+            Self::Build(_) => "build",
+            Self::Catalog(_) => "catalog",
+            Self::Restore(_) => "restore",
+            Self::Render(_) => "render",
+            Self::Edit(_) => "edit",
+            Self::Schema(_) => "schema",
+        }
+    }
 }
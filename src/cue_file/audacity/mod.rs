@@ -0,0 +1,103 @@
+//! Audacity label tracks, as exported via "Export Labels...".
+//!
+//! Each line is `start<TAB>end<TAB>label`, with the times in seconds.
+//! Spectral selections are written as an additional line starting with a
+//! backslash, which is ignored. Tabs and line breaks in labels are written as
+//! spaces, since the format has no way to escape them.
+use crate::error::bail;
+use crate::wave::{CuePoints, CUE_VERSION};
+use eyre::{eyre, Context as _, Result};
+use std::fmt::Write as _;
+
+const SEPARATORS: &[char] = &['\t', '\r', '\n'];
+
+pub(crate) fn read(buf: &[u8]) -> Result<CuePoints> {
+    let contents = std::str::from_utf8(buf).wrap_err("Label file is not valid UTF-8")?;
+
+    let mut timestamps = Vec::new();
+    let mut labels = Vec::new();
+    let mut lengths = Vec::new();
+    for (line, line_no) in contents.lines().zip(1usize..) {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('\\') {
+            continue;
+        }
+
+        let mut fields = line.split('\t');
+        let start = fields
+            .next()
+            .ok_or_else(|| eyre!("Expected start time (line {})", line_no))?;
+        let end = fields
+            .next()
+            .ok_or_else(|| eyre!("Expected end time (line {})", line_no))?;
+        // the label may contain tabs
+        let label = fields.collect::<Vec<_>>().join("\t");
+
+        let start: f64 = start
+            .trim()
+            .parse()
+            .wrap_err_with(|| format!("Invalid start time `{}` (line {})", start, line_no))?;
+        let end: f64 = end
+            .trim()
+            .parse()
+            .wrap_err_with(|| format!("Invalid end time `{}` (line {})", end, line_no))?;
+        if end < start {
+            bail!(
+                "Expected end time >= {}, but was {} (line {})",
+                start,
+                end,
+                line_no
+            );
+        }
+
+        timestamps.push(start);
+        labels.push(label);
+        lengths.push(end - start);
+    }
+
+    Ok(CuePoints {
+        version: CUE_VERSION,
+        metadata: None,
+        sample_points: None,
+        timestamps,
+        labels: super::non_empty(labels, |label| !label.is_empty()),
+        lengths: super::non_empty(lengths, |length| *length > 0.0),
+        sampler: None,
+        info: None,
+    })
+}
+
+pub(crate) fn write(cue: &CuePoints) -> Vec<u8> {
+    let mut contents = String::new();
+    let mut replaced = false;
+    for (ts, index) in cue.timestamps.iter().copied().zip(0usize..) {
+        let length = cue
+            .lengths
+            .as_ref()
+            .and_then(|lengths| lengths.get(index))
+            .copied()
+            .unwrap_or_default();
+        let label = cue.labels.as_ref().and_then(|labels| labels.get(index));
+        // Tabs and line breaks would split the label into more fields or lines
+        let label = label.map(|label| {
+            if label.contains(SEPARATORS) {
+                replaced = true;
+                label.replace(SEPARATORS, " ")
+            } else {
+                label.clone()
+            }
+        });
+        // Infallible: writing to a string cannot fail
+        let _ = match label {
+            Some(label) => writeln!(contents, "{:.6}\t{:.6}\t{}", ts, ts + length, label),
+            None => writeln!(contents, "{:.6}\t{:.6}\t{}", ts, ts + length, index + 1),
+        };
+    }
+    if replaced {
+        tracing::warn!("Replacing tabs and line breaks in labels with spaces...");
+    }
+    contents.into_bytes()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,73 @@
+use super::{read, write};
+use crate::wave::CuePoints;
+
+#[test]
+fn audacity_read_points_and_regions() {
+    let contents = b"0.000000\t0.000000\t1\n1.500000\t2.000000\tregion\n";
+    let cue = read(contents).unwrap();
+    assert_eq!(cue.timestamps, vec![0.0, 1.5]);
+    assert_eq!(
+        cue.labels,
+        Some(vec!["1".to_string(), "region".to_string()])
+    );
+    assert_eq!(cue.lengths, Some(vec![0.0, 0.5]));
+}
+
+#[test]
+fn audacity_read_skips_spectral_and_empty_lines() {
+    let contents = b"0.250000\t0.250000\t1\r\n\\\t100.0\t2000.0\r\n\r\n";
+    let cue = read(contents).unwrap();
+    assert_eq!(cue.timestamps, vec![0.25]);
+}
+
+#[test]
+fn audacity_read_invalid_time() {
+    let err = read(b"abc\t0.0\t1\n").unwrap_err();
+    assert!(err.to_string().contains("line 1"), "{}", err);
+}
+
+#[test]
+fn audacity_read_end_before_start() {
+    assert!(read(b"1.0\t0.5\t1\n").is_err());
+}
+
+#[test]
+fn audacity_write() {
+    let cue = CuePoints::new(vec![0.0, 0.5]);
+    let contents = write(&cue);
+    assert_eq!(contents, b"0.000000\t0.000000\t1\n0.500000\t0.500000\t2\n");
+}
+
+#[test]
+fn audacity_write_labels_and_lengths() {
+    let cue = CuePoints {
+        labels: Some(vec!["start".to_string(), "loop".to_string()]),
+        lengths: Some(vec![0.0, 0.25]),
+        ..CuePoints::new(vec![0.0, 0.5])
+    };
+    let contents = write(&cue);
+    assert_eq!(
+        contents,
+        b"0.000000\t0.000000\tstart\n0.500000\t0.750000\tloop\n"
+    );
+}
+
+#[test]
+fn audacity_write_multi_line_label_round_trip() {
+    let cue = CuePoints {
+        labels: Some(vec!["first\nsecond".to_string(), "a\tb\r\nc".to_string()]),
+        ..CuePoints::new(vec![0.0, 0.5])
+    };
+    let contents = write(&cue);
+    assert_eq!(
+        contents,
+        b"0.000000\t0.000000\tfirst second\n0.500000\t0.500000\ta b  c\n"
+    );
+
+    let read_back = read(&contents).unwrap();
+    assert_eq!(read_back.timestamps, vec![0.0, 0.5]);
+    assert_eq!(
+        read_back.labels,
+        Some(vec!["first second".to_string(), "a b  c".to_string()])
+    );
+}
@@ -0,0 +1,196 @@
+//! Comma-separated cue files for spreadsheets.
+//!
+//! The header is `index,sample,seconds,label`. Either the sample or the
+//! seconds may be left empty. The sample rate isn't stored, but is taken from
+//! the wave file, or inferred from the samples and seconds when both are
+//! given and only one sample rate fits them.
+use super::record;
+use crate::error::bail;
+use crate::wave::{CuePoints, SamplePoints, SamplesPerSec, CUE_VERSION};
+use eyre::{eyre, Context as _, Result};
+use std::fmt::Write as _;
+
+const HEADER: &str = "index,sample,seconds,label";
+
+struct Row {
+    index: u32,
+    sample: Option<u32>,
+    seconds: Option<f64>,
+    label: String,
+}
+
+fn parse_row(fields: Vec<String>, line_no: usize) -> Result<Row> {
+    if fields.len() < 3 || fields.len() > 4 {
+        bail!(
+            "Expected {} or {} fields, but was {} (line {})",
+            3,
+            4,
+            fields.len(),
+            line_no
+        );
+    }
+
+    let index = fields[0].trim();
+    let index = index
+        .parse()
+        .wrap_err_with(|| format!("Invalid index `{}` (line {})", index, line_no))?;
+
+    let sample = fields[1].trim();
+    let sample = if sample.is_empty() {
+        None
+    } else {
+        let sample = sample
+            .parse()
+            .wrap_err_with(|| format!("Invalid sample `{}` (line {})", sample, line_no))?;
+        Some(sample)
+    };
+
+    let seconds = fields[2].trim();
+    let seconds = if seconds.is_empty() {
+        None
+    } else {
+        let value: f64 = seconds
+            .parse()
+            .wrap_err_with(|| format!("Invalid seconds `{}` (line {})", seconds, line_no))?;
+        if !value.is_finite() || value < 0.0 {
+            bail!("Invalid seconds `{}` (line {})", seconds, line_no);
+        }
+        Some(value)
+    };
+
+    if sample.is_none() && seconds.is_none() {
+        bail!("Expected sample or seconds (line {})", line_no);
+    }
+
+    let label = fields.get(3).cloned().unwrap_or_default();
+    Ok(Row {
+        index,
+        sample,
+        seconds,
+        label,
+    })
+}
+
+/// Find the sample rate that converts every sample to its seconds, if there
+/// is exactly one. Rows at zero fit every sample rate, so at least one row
+/// must be after the start.
+fn infer_samples_per_sec(rows: &[Row]) -> Option<SamplesPerSec> {
+    if !rows
+        .iter()
+        .any(|row| row.sample.is_some_and(|sample| sample > 0))
+    {
+        return None;
+    }
+    let mut matching = SamplesPerSec::ALL
+        .iter()
+        .copied()
+        .filter(|samples_per_sec| {
+            let hz = samples_per_sec.as_u32() as f64;
+            rows.iter().all(|row| match (row.sample, row.seconds) {
+                (Some(sample), Some(seconds)) => (seconds * hz - sample as f64).abs() <= 0.5,
+                _ => false,
+            })
+        });
+    match (matching.next(), matching.next()) {
+        (Some(samples_per_sec), None) => Some(samples_per_sec),
+        _ => None,
+    }
+}
+
+pub(crate) fn read(buf: &[u8], samples_per_sec: Option<SamplesPerSec>) -> Result<CuePoints> {
+    let contents = std::str::from_utf8(buf).wrap_err("CSV file is not valid UTF-8")?;
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+
+    let contents = record::strip_header(contents, HEADER)?;
+
+    let mut rows = record::records(contents, 2)?
+        .into_iter()
+        .map(|(fields, line_no)| parse_row(fields, line_no))
+        .collect::<Result<Vec<_>>>()?;
+
+    // spreadsheets may re-order rows
+    rows.sort_by_key(|row| row.index);
+    for (row, expected) in rows.iter().zip(1u32..) {
+        if row.index != expected {
+            bail!(
+                "Expected `index` == {}, but was {} (indices must be unique and start at 1)",
+                expected,
+                row.index
+            );
+        }
+    }
+
+    let samples_per_sec = samples_per_sec.or_else(|| infer_samples_per_sec(&rows));
+
+    let timestamps = rows
+        .iter()
+        .map(|row| match (row.seconds, row.sample, samples_per_sec) {
+            (Some(seconds), _, _) => Ok(seconds),
+            (None, Some(sample), Some(hz)) => Ok(sample as f64 / hz.as_u32() as f64),
+            (None, _, _) => Err(eyre!(
+                "Cue point {} has no seconds, and the sample rate is unknown, specify `--samples-per-sec`",
+                row.index
+            )),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let sample_starts = rows
+        .iter()
+        .map(|row| row.sample)
+        .collect::<Option<Vec<_>>>();
+    let sample_points = match (sample_starts, samples_per_sec) {
+        (Some(sample_starts), Some(samples_per_sec)) if !sample_starts.is_empty() => {
+            Some(SamplePoints {
+                samples_per_sec,
+                sample_starts,
+            })
+        }
+        _ => None,
+    };
+
+    let labels = rows.into_iter().map(|row| row.label).collect();
+
+    Ok(CuePoints {
+        version: CUE_VERSION,
+        metadata: None,
+        sample_points,
+        timestamps,
+        labels: super::non_empty(labels, |label| !label.is_empty()),
+        lengths: None,
+        sampler: None,
+        info: None,
+    })
+}
+
+pub(crate) fn write(cue: &CuePoints) -> Vec<u8> {
+    let mut contents = String::new();
+    // Infallible: writing to a string cannot fail
+    let _ = writeln!(contents, "{}", HEADER);
+
+    for (ts, index) in cue.timestamps.iter().copied().zip(0usize..) {
+        let sample = cue
+            .sample_points
+            .as_ref()
+            .and_then(|sample_points| sample_points.sample_starts.get(index))
+            .map(u32::to_string)
+            .unwrap_or_default();
+        let label = cue
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get(index))
+            .map(String::as_str)
+            .unwrap_or_default();
+        let _ = writeln!(
+            contents,
+            "{},{},{:.6},{}",
+            index + 1,
+            sample,
+            ts,
+            record::escape(label)
+        );
+    }
+    contents.into_bytes()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,82 @@
+use super::{read, write};
+use crate::wave::{CuePoints, SamplesPerSec};
+
+#[test]
+fn csv_read_sorts_by_index() {
+    let contents = b"index,sample,seconds,label\n2,,1.5,b\n1,,0.5,a\n";
+    let cue = read(contents, None).unwrap();
+    assert_eq!(cue.timestamps, vec![0.5, 1.5]);
+    assert_eq!(cue.labels, Some(vec!["a".to_string(), "b".to_string()]));
+    assert!(cue.sample_points.is_none());
+}
+
+#[test]
+fn csv_read_samples_only() {
+    let contents = b"index,sample,seconds,label\n1,11025,,\n";
+    let cue = read(contents, Some(SamplesPerSec::Hz11025)).unwrap();
+    assert_eq!(cue.timestamps, vec![1.0]);
+    assert_eq!(cue.sample_points.unwrap().sample_starts, vec![11025]);
+}
+
+#[test]
+fn csv_read_samples_only_without_rate() {
+    let contents = b"index,sample,seconds,label\n1,11025,,\n";
+    assert!(read(contents, None).is_err());
+}
+
+#[test]
+fn csv_read_infers_rate() {
+    let contents = b"index,sample,seconds,label\n1,22040,1.000000,\n";
+    let cue = read(contents, None).unwrap();
+    let sample_points = cue.sample_points.unwrap();
+    assert_eq!(sample_points.samples_per_sec, SamplesPerSec::Hz22040);
+}
+
+#[test]
+fn csv_read_ambiguous_rate() {
+    // 22040 and 22050 Hz both fit a sample this close to the start
+    let contents = b"index,sample,seconds,label\n1,22,0.001,\n";
+    assert!(read(contents, None).unwrap().sample_points.is_none());
+
+    // every sample rate fits the start
+    let contents = b"index,sample,seconds,label\n1,0,0.0,\n";
+    assert!(read(contents, None).unwrap().sample_points.is_none());
+
+    // a row with only a sample can't be converted without the sample rate
+    let contents = b"index,sample,seconds,label\n1,0,0.0,\n2,100,,\n";
+    let err = read(contents, None).unwrap_err();
+    assert!(err.to_string().contains("--samples-per-sec"));
+}
+
+#[test]
+fn csv_read_duplicate_index() {
+    let contents = b"index,sample,seconds,label\n1,,0.5,\n1,,1.5,\n";
+    assert!(read(contents, None).is_err());
+}
+
+#[test]
+fn csv_read_invalid_header() {
+    assert!(read(b"sample,seconds\n", None).is_err());
+}
+
+#[test]
+fn csv_read_unterminated_quote() {
+    let contents = b"index,sample,seconds,label\n1,,0.5,\"a\n2,,1.5,b\n";
+    let err = read(contents, None).unwrap_err();
+    assert!(err.to_string().contains("line 2"), "{}", err);
+}
+
+#[test]
+fn csv_write_multi_line_label_round_trip() {
+    let cue = CuePoints {
+        labels: Some(vec![
+            "first\nsecond".to_string(),
+            "a, \"b\"\r\nc".to_string(),
+        ]),
+        ..CuePoints::new(vec![0.5, 1.5])
+    };
+    let contents = write(&cue);
+    let read_back = read(&contents, None).unwrap();
+    assert_eq!(read_back.timestamps, cue.timestamps);
+    assert_eq!(read_back.labels, cue.labels);
+}
@@ -0,0 +1,148 @@
+//! Reading and writing cue points in various cue file formats.
+mod audacity;
+mod csv;
+mod reaper;
+mod record;
+mod schema;
+
+use crate::error::bail;
+use crate::wave::{CuePoints, SamplesPerSec, CUE_VERSION, CUE_VERSION_LEGACY};
+use camino::Utf8Path;
+use eyre::{Context as _, Result};
+pub use schema::schema;
+use serde::{Deserialize, Serialize};
+
+/// The supported cue file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum CueFormat {
+    /// JSON cue file (`.json`)
+    Json,
+    /// TOML cue file (`.toml`)
+    Toml,
+    /// Comma-separated cue file (`.csv`)
+    Csv,
+    /// Audacity label track (`.txt`)
+    Audacity,
+    /// Reaper marker/region list (comma-separated)
+    Reaper,
+}
+
+impl CueFormat {
+    /// The format implied by a file extension, if any.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            "csv" => Some(Self::Csv),
+            "txt" => Some(Self::Audacity),
+            _ => None,
+        }
+    }
+
+    /// Use the given format, or the format implied by the file extension.
+    ///
+    /// Unknown extensions fall back to JSON, which was the only format
+    /// supported previously.
+    pub fn resolve(format: Option<Self>, path: &Utf8Path) -> Self {
+        format
+            .or_else(|| path.extension().and_then(Self::from_extension))
+            .unwrap_or(Self::Json)
+    }
+}
+
+/// Read cue points from a cue file.
+///
+/// Some formats may specify times in samples, in which case the sample rate
+/// of the wave file is required to convert them.
+pub fn read(
+    format: CueFormat,
+    buf: &[u8],
+    samples_per_sec: Option<SamplesPerSec>,
+) -> crate::Result<CuePoints> {
+    let cue = match format {
+        CueFormat::Json => migrate(serde_json::from_slice(buf).map_err(eyre::Report::new)?),
+        CueFormat::Toml => {
+            let contents = std::str::from_utf8(buf).wrap_err("TOML file is not valid UTF-8")?;
+            migrate(toml::from_str(contents).map_err(eyre::Report::new)?)
+        }
+        CueFormat::Csv => csv::read(buf, samples_per_sec),
+        CueFormat::Audacity => audacity::read(buf),
+        CueFormat::Reaper => reaper::read(buf, samples_per_sec),
+    }?;
+    Ok(cue)
+}
+
+/// Write cue points to a cue file.
+pub fn write(format: CueFormat, cue: &CuePoints) -> crate::Result<Vec<u8>> {
+    match format {
+        CueFormat::Json => {
+            let mut contents = serde_json::to_vec_pretty(cue).wrap_err("Internal error")?;
+            contents.push(b'\n');
+            Ok(contents)
+        }
+        CueFormat::Toml => {
+            let contents = toml::to_string_pretty(cue).wrap_err("Internal error")?;
+            Ok(contents.into_bytes())
+        }
+        CueFormat::Csv | CueFormat::Audacity | CueFormat::Reaper
+            if cue.sampler.is_some() || cue.info.is_some() =>
+        {
+            if cue.sampler.is_some() {
+                tracing::warn!("The cue file format has no sample loops, ignoring them...");
+            }
+            if cue.info.is_some() {
+                tracing::warn!("The cue file format has no INFO metadata, ignoring it...");
+            }
+            write(
+                format,
+                &CuePoints {
+                    sampler: None,
+                    info: None,
+                    ..cue.clone()
+                },
+            )
+        }
+        CueFormat::Csv => Ok(csv::write(cue)),
+        CueFormat::Audacity => Ok(audacity::write(cue)),
+        CueFormat::Reaper => Ok(reaper::write(cue)),
+    }
+}
+
+/// Upgrade cue points read from an older version of the cue file.
+fn migrate(mut cue: CuePoints) -> Result<CuePoints> {
+    if cue.version > CUE_VERSION {
+        bail!(
+            "Cue file version {} is newer than the supported version {}, please upgrade zcue",
+            cue.version,
+            CUE_VERSION
+        );
+    }
+    if cue.version < CUE_VERSION_LEGACY {
+        bail!("Invalid cue file version {}", cue.version);
+    }
+    if cue.version == CUE_VERSION_LEGACY {
+        // version 1 is a strict subset of version 2
+        tracing::debug!("migrating cue file from version {}", cue.version);
+        cue.version = 2;
+    }
+    if cue.version == 2 {
+        // version 2 is a strict subset of version 3
+        tracing::debug!("migrating cue file from version {}", cue.version);
+        cue.version = 3;
+    }
+    Ok(cue)
+}
+
+/// Labels and lengths are optional, so only keep them if any are set.
+fn non_empty<T>(values: Vec<T>, is_set: impl Fn(&T) -> bool) -> Option<Vec<T>> {
+    if values.iter().any(is_set) {
+        Some(values)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,227 @@
+//! Reaper marker/region CSV, as exported via the Region/Marker Manager.
+//!
+//! The header is `#,Name,Start,End,Length`. Markers have an ID starting with
+//! `M` and no end or length, regions have an ID starting with `R`. Times may
+//! be written in seconds (`1.5`, `0:01.500`, `0:00:01.500`), or in samples
+//! (`33075`), which requires the sample rate of the wave file.
+//!
+//! Reaper writes times in seconds with a fractional part, so a file is read
+//! in samples if every time is a whole number, and in seconds otherwise,
+//! where whole numbers are whole seconds.
+use super::record;
+use crate::error::bail;
+use crate::wave::{CuePoints, SamplePoints, SamplesPerSec, CUE_VERSION};
+use eyre::{eyre, Context as _, Result};
+use std::fmt::Write as _;
+
+const HEADER: &str = "#,Name,Start,End,Length";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Time {
+    Seconds(f64),
+    Samples(u32),
+}
+
+fn parse_seconds(value: &str) -> Option<f64> {
+    let mut seconds = 0.0f64;
+    let mut parts = value.split(':').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_some() {
+            // hours or minutes
+            let v: u32 = part.parse().ok()?;
+            seconds = (seconds + v as f64) * 60.0;
+        } else {
+            let v: f64 = part.parse().ok()?;
+            if !v.is_finite() || v < 0.0 {
+                return None;
+            }
+            seconds += v;
+        }
+    }
+    Some(seconds)
+}
+
+fn is_whole(value: &str) -> bool {
+    let value = value.trim();
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn parse_time(value: &str, in_samples: bool, line_no: usize, name: &str) -> Result<Time> {
+    let value = value.trim();
+    if in_samples {
+        let samples = value
+            .parse()
+            .wrap_err_with(|| format!("Invalid {} `{}` (line {})", name, value, line_no))?;
+        return Ok(Time::Samples(samples));
+    }
+    parse_seconds(value)
+        .map(Time::Seconds)
+        .ok_or_else(|| eyre!("Invalid {} `{}` (line {})", name, value, line_no))
+}
+
+fn to_seconds(time: Time, samples_per_sec: Option<SamplesPerSec>, line_no: usize) -> Result<f64> {
+    match time {
+        Time::Seconds(seconds) => Ok(seconds),
+        Time::Samples(samples) => {
+            let hz = samples_per_sec.ok_or_else(|| {
+                eyre!(
+                    "Times in samples (every time is a whole number) require the sample rate of the wave file (line {})",
+                    line_no
+                )
+            })?;
+            Ok(samples as f64 / hz.as_u32() as f64)
+        }
+    }
+}
+
+pub(crate) fn read(buf: &[u8], samples_per_sec: Option<SamplesPerSec>) -> Result<CuePoints> {
+    let contents = std::str::from_utf8(buf).wrap_err("Marker file is not valid UTF-8")?;
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+
+    let contents = record::strip_header(contents, HEADER)?;
+
+    let records = record::records(contents, 2)?;
+    for (fields, line_no) in &records {
+        if fields.len() < 3 {
+            bail!(
+                "Expected at least {} fields, but was {} (line {})",
+                3,
+                fields.len(),
+                line_no
+            );
+        }
+    }
+    // the start, end, and length, where given
+    let in_samples = !records.is_empty()
+        && records.iter().all(|(fields, _)| {
+            fields[2..]
+                .iter()
+                .take(3)
+                .filter(|time| !time.trim().is_empty())
+                .all(|time| is_whole(time))
+        });
+
+    let mut timestamps = Vec::new();
+    let mut labels = Vec::new();
+    let mut lengths = Vec::new();
+    // only keep sample positions if every time is given in samples
+    let mut sample_starts = Some(Vec::new());
+
+    for (fields, line_no) in records {
+        let id = fields[0].trim();
+        let is_region = match id.chars().next() {
+            Some('M' | 'm') => false,
+            Some('R' | 'r') => true,
+            _ => bail!(
+                "Expected marker or region ID, but was `{}` (line {})",
+                id,
+                line_no
+            ),
+        };
+
+        let start = parse_time(&fields[2], in_samples, line_no, "start")?;
+        let length = if is_region {
+            let end = fields.get(3).map(String::as_str).unwrap_or_default();
+            let length = fields.get(4).map(String::as_str).unwrap_or_default();
+            if !end.trim().is_empty() {
+                let start = to_seconds(start, samples_per_sec, line_no)?;
+                let end = parse_time(end, in_samples, line_no, "end")?;
+                let end = to_seconds(end, samples_per_sec, line_no)?;
+                if end < start {
+                    bail!(
+                        "Expected end >= {}, but was {} (line {})",
+                        start,
+                        end,
+                        line_no
+                    );
+                }
+                end - start
+            } else if !length.trim().is_empty() {
+                let length = parse_time(length, in_samples, line_no, "length")?;
+                to_seconds(length, samples_per_sec, line_no)?
+            } else {
+                0.0
+            }
+        } else {
+            0.0
+        };
+
+        match (start, sample_starts.as_mut()) {
+            (Time::Samples(samples), Some(starts)) => starts.push(samples),
+            _ => sample_starts = None,
+        }
+        timestamps.push(to_seconds(start, samples_per_sec, line_no)?);
+        labels.push(fields[1].clone());
+        lengths.push(length);
+    }
+
+    let sample_points = match (sample_starts, samples_per_sec) {
+        (Some(sample_starts), Some(samples_per_sec)) if !sample_starts.is_empty() => {
+            Some(SamplePoints {
+                samples_per_sec,
+                sample_starts,
+            })
+        }
+        _ => None,
+    };
+
+    Ok(CuePoints {
+        version: CUE_VERSION,
+        metadata: None,
+        sample_points,
+        timestamps,
+        labels: super::non_empty(labels, |label| !label.is_empty()),
+        lengths: super::non_empty(lengths, |length| *length > 0.0),
+        sampler: None,
+        info: None,
+    })
+}
+
+pub(crate) fn write(cue: &CuePoints) -> Vec<u8> {
+    let mut contents = String::new();
+    // Infallible: writing to a string cannot fail
+    let _ = writeln!(contents, "{}", HEADER);
+
+    let mut marker = 1u32;
+    let mut region = 1u32;
+    for (index, ts) in cue.timestamps.iter().copied().enumerate() {
+        let label = cue
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get(index))
+            .map(String::as_str)
+            .unwrap_or_default();
+        let length = cue
+            .lengths
+            .as_ref()
+            .and_then(|lengths| lengths.get(index))
+            .copied()
+            .unwrap_or_default();
+
+        if length > 0.0 {
+            let _ = writeln!(
+                contents,
+                "R{},{},{:.6},{:.6},{:.6}",
+                region,
+                record::escape(label),
+                ts,
+                ts + length,
+                length,
+            );
+            region += 1;
+        } else {
+            let _ = writeln!(
+                contents,
+                "M{},{},{:.6},,",
+                marker,
+                record::escape(label),
+                ts
+            );
+            marker += 1;
+        }
+    }
+    contents.into_bytes()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,101 @@
+use super::{read, write};
+use crate::wave::{CuePoints, SamplesPerSec};
+
+#[test]
+fn reaper_read_seconds() {
+    let contents =
+        b"#,Name,Start,End,Length\nM1,intro,0.500,,\nR1,\"hum, loop\",1:01.000,1:02.500,1.500\n";
+    let cue = read(contents, None).unwrap();
+    assert_eq!(cue.timestamps, vec![0.5, 61.0]);
+    assert_eq!(
+        cue.labels,
+        Some(vec!["intro".to_string(), "hum, loop".to_string()])
+    );
+    assert_eq!(cue.lengths, Some(vec![0.0, 1.5]));
+    assert!(cue.sample_points.is_none());
+}
+
+#[test]
+fn reaper_read_samples() {
+    let contents = b"#,Name,Start,End,Length\r\nM1,,0,,\r\nR1,,22050,44100,22050\r\n";
+    let cue = read(contents, Some(SamplesPerSec::Hz22050)).unwrap();
+    assert_eq!(cue.timestamps, vec![0.0, 1.0]);
+    assert_eq!(cue.labels, None);
+    assert_eq!(cue.lengths, Some(vec![0.0, 1.0]));
+    let sample_points = cue.sample_points.unwrap();
+    assert_eq!(sample_points.samples_per_sec, SamplesPerSec::Hz22050);
+    assert_eq!(sample_points.sample_starts, vec![0, 22050]);
+}
+
+#[test]
+fn reaper_read_whole_seconds() {
+    // whole numbers are seconds if any time has a fractional part
+    let contents = b"#,Name,Start,End,Length\nM1,,5,,\nR1,,1.500,3,1.5\n";
+    let cue = read(contents, Some(SamplesPerSec::Hz22050)).unwrap();
+    assert_eq!(cue.timestamps, vec![5.0, 1.5]);
+    assert_eq!(cue.lengths, Some(vec![0.0, 1.5]));
+    assert!(cue.sample_points.is_none());
+
+    let contents = b"#,Name,Start,End,Length\nM1,,5,,\nM2,,0:07,,\n";
+    let cue = read(contents, None).unwrap();
+    assert_eq!(cue.timestamps, vec![5.0, 7.0]);
+}
+
+#[test]
+fn reaper_read_samples_without_rate() {
+    let contents = b"#,Name,Start,End,Length\nM1,,100,,\n";
+    assert!(read(contents, None).is_err());
+}
+
+#[test]
+fn reaper_read_invalid_header() {
+    assert!(read(b"Name,Start\n", None).is_err());
+}
+
+#[test]
+fn reaper_read_measures_unsupported() {
+    let contents = b"#,Name,Start,End,Length\nM1,,1.1.00,,\n";
+    assert!(read(contents, None).is_err());
+}
+
+#[test]
+fn reaper_write() {
+    let cue = CuePoints {
+        labels: Some(vec!["a".to_string(), "b, \"c\"".to_string(), String::new()]),
+        lengths: Some(vec![0.0, 0.5, 0.0]),
+        ..CuePoints::new(vec![0.0, 1.0, 2.0])
+    };
+    let contents = String::from_utf8(write(&cue)).unwrap();
+    assert_eq!(
+        contents,
+        "#,Name,Start,End,Length\n\
+         M1,a,0.000000,,\n\
+         R1,\"b, \"\"c\"\"\",1.000000,1.500000,0.500000\n\
+         M2,,2.000000,,\n"
+    );
+    let read_back = read(contents.as_bytes(), None).unwrap();
+    assert_eq!(read_back.timestamps, cue.timestamps);
+    assert_eq!(read_back.labels, cue.labels);
+    assert_eq!(read_back.lengths, cue.lengths);
+}
+
+#[test]
+fn reaper_write_multi_line_label_round_trip() {
+    let cue = CuePoints {
+        labels: Some(vec!["first\nsecond".to_string(), "third".to_string()]),
+        lengths: Some(vec![0.5, 0.0]),
+        ..CuePoints::new(vec![0.0, 1.0])
+    };
+    let contents = write(&cue);
+    let read_back = read(&contents, None).unwrap();
+    assert_eq!(read_back.timestamps, cue.timestamps);
+    assert_eq!(read_back.labels, cue.labels);
+    assert_eq!(read_back.lengths, cue.lengths);
+}
+
+#[test]
+fn reaper_read_reports_record_line() {
+    let contents = b"#,Name,Start,End,Length\nM1,\"a\nb\",0.5,,\nX2,,1.0,,\n";
+    let err = read(contents, None).unwrap_err();
+    assert!(err.to_string().contains("line 4"), "{}", err);
+}
@@ -0,0 +1,84 @@
+//! Minimal support for comma-separated records, as written by spreadsheets
+//! and audio editors. Fields may be quoted, and quotes inside quoted fields
+//! are escaped by doubling them. Quoted fields may span several lines.
+use crate::error::bail;
+use eyre::Result;
+
+/// A record's fields, and the line it starts on.
+pub(crate) type Record = (Vec<String>, usize);
+
+/// Split the contents into records, skipping blank lines. The first line of
+/// the contents is numbered `first_line`.
+pub(crate) fn records(contents: &str, first_line: usize) -> Result<Vec<Record>> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = contents.chars().peekable();
+    let mut quoted = false;
+    let mut line_no = first_line;
+    let mut start = first_line;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            '\n' if quoted => {
+                line_no += 1;
+                field.push(c);
+            }
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                push_record(&mut records, std::mem::take(&mut fields), start);
+                line_no += 1;
+                start = line_no;
+            }
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        bail!("Unterminated quoted field (line {})", start);
+    }
+    fields.push(field);
+    push_record(&mut records, fields, start);
+    Ok(records)
+}
+
+fn push_record(records: &mut Vec<Record>, fields: Vec<String>, line_no: usize) {
+    let blank = matches!(fields.as_slice(), [field] if field.trim().is_empty());
+    if !blank {
+        records.push((fields, line_no));
+    }
+}
+
+/// Check the header on the first line, and return the rest of the contents.
+pub(crate) fn strip_header<'a>(contents: &'a str, header: &str) -> Result<&'a str> {
+    if contents.is_empty() {
+        bail!("Expected header `{}`, but file is empty", header);
+    }
+    let (first, rest) = contents.split_once('\n').unwrap_or((contents, ""));
+    if !first.trim_end().eq_ignore_ascii_case(header) {
+        bail!(
+            "Expected header `{}`, but was `{}`",
+            header,
+            first.trim_end_matches('\r')
+        );
+    }
+    Ok(rest)
+}
+
+pub(crate) fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
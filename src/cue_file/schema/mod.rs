@@ -0,0 +1,151 @@
+//! The JSON Schema of JSON cue files.
+use crate::wave::{SamplesPerSec, CUE_VERSION, CUE_VERSION_LEGACY};
+use serde_json::{json, Value};
+
+/// The JSON Schema of JSON cue files.
+pub fn schema() -> Value {
+    let samples_per_sec = SamplesPerSec::ALL
+        .iter()
+        .map(SamplesPerSec::as_u32)
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "zcue cue file",
+        "description": "Cue points extracted from, or to be applied to, a wave file",
+        "type": "object",
+        "required": ["timestamps"],
+        "properties": {
+            "version": {
+                "description": "The version of the cue file; if missing, version 1 is assumed",
+                "type": "integer",
+                "minimum": CUE_VERSION_LEGACY,
+                "maximum": CUE_VERSION,
+            },
+            "metadata": {
+                "description": "Information about the wave file the cue points were extracted from",
+                "type": "object",
+                "properties": {
+                    "source": {
+                        "description": "The file name of the wave file",
+                        "type": "string",
+                    },
+                    "checksum": {
+                        "description": "The checksum of the audio data",
+                        "type": "string",
+                        "pattern": "^crc32:[0-9a-f]{8}$",
+                    },
+                    "format": {
+                        "type": "object",
+                        "required": ["channels", "samples_per_sec", "bits_per_sample"],
+                        "properties": {
+                            "channels": { "enum": [1] },
+                            "samples_per_sec": { "enum": samples_per_sec },
+                            "bits_per_sample": { "enum": [8, 16] },
+                        },
+                        "additionalProperties": false,
+                    },
+                    "sample_count": {
+                        "description": "The number of samples of the audio data",
+                        "type": "integer",
+                        "minimum": 0,
+                    },
+                },
+                "additionalProperties": false,
+            },
+            "sample_points": {
+                "description": "The exact cue point positions, for information only",
+                "type": "object",
+                "required": ["samples_per_sec", "sample_starts"],
+                "properties": {
+                    "samples_per_sec": { "enum": samples_per_sec },
+                    "sample_starts": {
+                        "type": "array",
+                        "items": { "type": "integer", "minimum": 0 },
+                    },
+                },
+                "additionalProperties": false,
+            },
+            "timestamps": {
+                "description": "The cue point positions in seconds",
+                "type": "array",
+                "items": { "type": "number", "minimum": 0 },
+            },
+            "labels": {
+                "description": "The cue point labels, one per timestamp",
+                "type": "array",
+                "items": { "type": "string" },
+            },
+            "lengths": {
+                "description": "The region lengths in seconds, one per timestamp, zero for markers",
+                "type": "array",
+                "items": { "type": "number", "minimum": 0 },
+            },
+            "sampler": {
+                "description": "The sampler chunk of the wave file, with the sample loops",
+                "type": "object",
+                "properties": {
+                    "manufacturer": { "$ref": "#/$defs/dword" },
+                    "product": { "$ref": "#/$defs/dword" },
+                    "sample_period": {
+                        "description": "The duration of one sample in nanoseconds",
+                        "$ref": "#/$defs/dword",
+                    },
+                    "midi_unity_note": {
+                        "description": "The MIDI note that plays the sample at its original pitch",
+                        "type": "integer",
+                        "minimum": 0,
+                        "maximum": 127,
+                    },
+                    "midi_pitch_fraction": { "$ref": "#/$defs/dword" },
+                    "smpte_format": { "enum": [0, 24, 25, 29, 30] },
+                    "smpte_offset": { "$ref": "#/$defs/dword" },
+                    "loops": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["start", "end"],
+                            "properties": {
+                                "cue_point_id": { "$ref": "#/$defs/dword" },
+                                "loop_type": {
+                                    "description": "0 is forward, 1 is alternating, 2 is backward",
+                                    "$ref": "#/$defs/dword",
+                                },
+                                "start": {
+                                    "description": "The first sample of the loop",
+                                    "$ref": "#/$defs/dword",
+                                },
+                                "end": {
+                                    "description": "The last sample of the loop",
+                                    "$ref": "#/$defs/dword",
+                                },
+                                "fraction": { "$ref": "#/$defs/dword" },
+                                "play_count": {
+                                    "description": "How often the loop is played, 0 for infinitely",
+                                    "$ref": "#/$defs/dword",
+                                },
+                            },
+                            "additionalProperties": false,
+                        },
+                    },
+                    "sampler_data": {
+                        "description": "Manufacturer-specific data after the loops",
+                        "type": "array",
+                        "items": { "type": "integer", "minimum": 0, "maximum": 255 },
+                    },
+                },
+                "additionalProperties": false,
+            },
+            "info": {
+                "description": "The INFO list of the wave file, e.g. the title (INAM) and artist (IART)",
+                "type": "object",
+                "propertyNames": { "pattern": "^[ -~]{4}$" },
+                "additionalProperties": { "type": "string" },
+            },
+        },
+        "additionalProperties": false,
+        "$defs": {
+            "dword": { "type": "integer", "minimum": 0, "maximum": u32::MAX },
+        },
+    })
+}
@@ -0,0 +1,165 @@
+use super::*;
+use crate::wave::{SamplePoints, CUE_VERSION};
+
+fn cue() -> CuePoints {
+    CuePoints {
+        sample_points: Some(SamplePoints {
+            samples_per_sec: SamplesPerSec::Hz22050,
+            sample_starts: vec![0, 11025, 22050],
+        }),
+        labels: Some(vec!["a".to_string(), String::new(), "c".to_string()]),
+        ..CuePoints::new(vec![0.0, 0.5, 1.0])
+    }
+}
+
+fn roundtrip(format: CueFormat) -> CuePoints {
+    let contents = write(format, &cue()).unwrap();
+    read(format, &contents, None).unwrap()
+}
+
+#[test]
+fn cue_format_from_extension() {
+    assert_eq!(
+        CueFormat::resolve(None, Utf8Path::new("a.TOML")),
+        CueFormat::Toml
+    );
+    assert_eq!(
+        CueFormat::resolve(None, Utf8Path::new("a.csv")),
+        CueFormat::Csv
+    );
+    assert_eq!(
+        CueFormat::resolve(None, Utf8Path::new("a.txt")),
+        CueFormat::Audacity
+    );
+    assert_eq!(
+        CueFormat::resolve(None, Utf8Path::new("a.cue")),
+        CueFormat::Json
+    );
+    assert_eq!(
+        CueFormat::resolve(Some(CueFormat::Reaper), Utf8Path::new("a.csv")),
+        CueFormat::Reaper
+    );
+}
+
+#[test]
+fn cue_format_json_preserves_sample_points() {
+    let cue = roundtrip(CueFormat::Json);
+    let sample_points = cue.sample_points.unwrap();
+    assert_eq!(sample_points.samples_per_sec, SamplesPerSec::Hz22050);
+    assert_eq!(sample_points.sample_starts, vec![0, 11025, 22050]);
+}
+
+#[test]
+fn cue_format_toml_preserves_sample_points() {
+    let cue = roundtrip(CueFormat::Toml);
+    assert_eq!(cue.timestamps, vec![0.0, 0.5, 1.0]);
+    let sample_points = cue.sample_points.unwrap();
+    assert_eq!(sample_points.samples_per_sec, SamplesPerSec::Hz22050);
+    assert_eq!(sample_points.sample_starts, vec![0, 11025, 22050]);
+}
+
+#[test]
+fn cue_format_csv_preserves_sample_points() {
+    let cue = roundtrip(CueFormat::Csv);
+    assert_eq!(cue.timestamps, vec![0.0, 0.5, 1.0]);
+    assert_eq!(
+        cue.labels,
+        Some(vec!["a".to_string(), String::new(), "c".to_string()])
+    );
+    let sample_points = cue.sample_points.unwrap();
+    assert_eq!(sample_points.samples_per_sec, SamplesPerSec::Hz22050);
+    assert_eq!(sample_points.sample_starts, vec![0, 11025, 22050]);
+}
+
+#[test]
+fn cue_format_json_migrates_legacy() {
+    let contents =
+        br#"{"sample_points":{"samples_per_sec":22050,"sample_starts":[0]},"timestamps":[0.0]}"#;
+    let cue = read(CueFormat::Json, contents, None).unwrap();
+    assert_eq!(cue.version, CUE_VERSION);
+    assert!(cue.metadata.is_none());
+    assert_eq!(cue.timestamps, vec![0.0]);
+}
+
+#[test]
+fn cue_format_json_migrates_version_2() {
+    let contents = br#"{"version":2,"timestamps":[0.0,0.5],"labels":["a","b"]}"#;
+    let cue = read(CueFormat::Json, contents, None).unwrap();
+    assert_eq!(cue.version, CUE_VERSION);
+    assert!(cue.sampler.is_none());
+    assert!(cue.info.is_none());
+    assert_eq!(cue.labels, Some(vec!["a".to_string(), "b".to_string()]));
+}
+
+#[test]
+fn cue_format_json_rejects_newer_version() {
+    let contents = br#"{"version":999,"timestamps":[0.0]}"#;
+    assert!(read(CueFormat::Json, contents, None).is_err());
+}
+
+#[test]
+fn cue_format_json_schema_is_valid_json() {
+    let schema = schema();
+    assert_eq!(
+        schema["properties"]["version"]["maximum"],
+        serde_json::json!(CUE_VERSION)
+    );
+    assert_eq!(schema["properties"]["version"]["maximum"], 3);
+}
+
+#[test]
+fn cue_format_json_schema_validates_extracted_cue_file() {
+    use crate::wave::{
+        BitsPerSample, Channels, ChunkId, Format, Info, Metadata, Sampler, WaveFile,
+    };
+    use jsonschema::{Draft, JSONSchema};
+
+    let fmt = Format {
+        channels: Channels::One,
+        samples_per_sec: SamplesPerSec::Hz22050,
+        bits_per_sample: BitsPerSample::Sixteen,
+    };
+    let mut wav = WaveFile::new(fmt, vec![0; 2000]);
+    wav.cue = Some(vec![0, 100, 200, 300]);
+    let mut sampler = Sampler::new(SamplesPerSec::Hz22050);
+    sampler.loops = Sampler::loops_from_cues(&[0, 100, 200, 300]);
+    sampler.sampler_data = vec![1, 2, 3];
+    wav.sampler = Some(sampler);
+    let mut info = Info::new();
+    info.set(ChunkId::new(*b"INAM"), "Missile lock");
+    info.set(ChunkId::new(*b"ICMT"), "Take 2");
+    wav.info = Some(info);
+
+    let mut cue = crate::wave::cue_from_wav(&wav).unwrap();
+    cue.metadata = Some(Metadata::from_wav(&wav, Some("a.wav".to_string())));
+    cue.labels = Some(vec![
+        "a".to_string(),
+        String::new(),
+        "c".to_string(),
+        "d".to_string(),
+    ]);
+    cue.lengths = Some(vec![0.0, 0.5, 0.0, 0.0]);
+    let contents = write(CueFormat::Json, &cue).unwrap();
+    let mut instance: serde_json::Value = serde_json::from_slice(&contents).unwrap();
+    assert!(instance["sampler"]["loops"][1]["cue_point_id"].is_number());
+
+    let schema = schema();
+    let validator = JSONSchema::options()
+        .with_draft(Draft::Draft202012)
+        .compile(&schema)
+        .unwrap();
+    if let Err(errors) = validator.validate(&instance) {
+        let errors: Vec<String> = errors.map(|e| e.to_string()).collect();
+        panic!("cue file doesn't match the schema: {:?}", errors);
+    }
+
+    // the nested objects are checked too
+    instance["sampler"]["loops"][0]
+        .as_object_mut()
+        .unwrap()
+        .remove("end");
+    assert!(!validator.is_valid(&instance));
+    instance["sampler"]["loops"][0]["end"] = serde_json::json!(99);
+    instance["info"]["TITLE"] = serde_json::json!("Missile lock");
+    assert!(!validator.is_valid(&instance));
+}
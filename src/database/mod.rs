@@ -0,0 +1,179 @@
+//! A database of known wave files, such as the original sounds of the games.
+//!
+//! Files are identified by the checksum and sample count of their audio
+//! data (see [`data_checksum`](crate::data_checksum)), so a file with
+//! edited cue points or metadata is still identified. Each known file
+//! records the game, archive, and name it was shipped as, and its original
+//! cue points. The database is a TOML file:
+//!
+//! ```toml
+//! version = 1
+//!
+//! [[file]]
+//! checksum = "crc32:cbf43926"
+//! sample_count = 22050
+//! game = "mw3"
+//! archive = "soundsH"
+//! name = "br340000.wav"
+//! cue = [0, 11025]
+//! ```
+use crate::error::bail;
+use crate::Result;
+use eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The current version of the database.
+pub const DATABASE_VERSION: u32 = 1;
+
+fn database_version() -> u32 {
+    DATABASE_VERSION
+}
+
+/// The games developed by Zipper Interactive™ whose sounds are supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum Game {
+    /// MechWarrior 3
+    Mw3,
+    /// MechWarrior 3: Pirate's Moon
+    Pm,
+    /// Recoil
+    Recoil,
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Mw3 => "MechWarrior 3",
+            Self::Pm => "Pirate's Moon",
+            Self::Recoil => "Recoil",
+        })
+    }
+}
+
+/// A known wave file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct KnownFile {
+    /// The checksum of the audio data, e.g. `crc32:cbf43926`
+    pub checksum: String,
+    /// The number of samples of the audio data
+    pub sample_count: u64,
+    /// The game the file was shipped with
+    pub game: Game,
+    /// The archive the file was shipped in, e.g. `soundsH`
+    pub archive: String,
+    /// The name of the file in the archive
+    pub name: String,
+    /// The original cue point sample starts
+    #[serde(default)]
+    pub cue: Vec<u32>,
+}
+
+impl KnownFile {
+    /// Whether the audio data is the same as the known file's.
+    #[inline]
+    pub fn matches(&self, checksum: &str, sample_count: u64) -> bool {
+        self.checksum == checksum && self.sample_count == sample_count
+    }
+
+    /// A one-line summary of the known file, and whether the cue points of
+    /// a file identified as it were changed, as printed by `zcue info`.
+    pub fn summary(&self, cue: Option<&[u32]>) -> String {
+        let changed = if cue.unwrap_or_default() == self.cue {
+            "unchanged"
+        } else {
+            "changed"
+        };
+        format!(
+            "{}, {} original cue point(s), cue points {}",
+            self,
+            self.cue.len(),
+            changed
+        )
+    }
+}
+
+impl fmt::Display for KnownFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} `{}/{}`", self.game, self.archive, self.name)
+    }
+}
+
+/// A database of known wave files.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Database {
+    /// The version of the database, see [`DATABASE_VERSION`]
+    #[serde(default = "database_version")]
+    pub version: u32,
+    /// The known files
+    #[serde(default, rename = "file")]
+    pub files: Vec<KnownFile>,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Self {
+            version: DATABASE_VERSION,
+            files: Vec::new(),
+        }
+    }
+}
+
+impl Database {
+    /// Read a database.
+    pub fn read(buf: &[u8]) -> Result<Self> {
+        let contents = std::str::from_utf8(buf).wrap_err("TOML file is not valid UTF-8")?;
+        let database: Self = toml::from_str(contents).map_err(eyre::Report::new)?;
+        if database.version > DATABASE_VERSION {
+            bail!(
+                "Database version {} is newer than the supported version {}, please upgrade zcue",
+                database.version,
+                DATABASE_VERSION
+            );
+        }
+        Ok(database)
+    }
+
+    /// Write the database.
+    pub fn write(&self) -> Result<Vec<u8>> {
+        let contents = toml::to_string_pretty(self).wrap_err("Internal error")?;
+        Ok(contents.into_bytes())
+    }
+
+    /// Identify a file by the checksum and sample count of its audio data.
+    ///
+    /// Identical audio data may have been shipped more than once, e.g. in
+    /// several games, in which case the first known file is returned.
+    pub fn identify(&self, checksum: &str, sample_count: u64) -> Option<&KnownFile> {
+        self.files
+            .iter()
+            .find(|known| known.matches(checksum, sample_count))
+    }
+
+    /// Add a known file, replacing the file with the same audio data, game,
+    /// and archive, if any. Returns whether a file was replaced.
+    pub fn insert(&mut self, file: KnownFile) -> bool {
+        let existing = self.files.iter_mut().find(|known| {
+            known.matches(&file.checksum, file.sample_count)
+                && known.game == file.game
+                && known.archive == file.archive
+        });
+        match existing {
+            Some(known) => {
+                *known = file;
+                true
+            }
+            None => {
+                self.files.push(file);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,85 @@
+use super::*;
+
+fn known(checksum: &str, game: Game, name: &str) -> KnownFile {
+    KnownFile {
+        checksum: checksum.to_string(),
+        sample_count: 100,
+        game,
+        archive: "soundsH".to_string(),
+        name: name.to_string(),
+        cue: vec![0, 50],
+    }
+}
+
+#[test]
+fn read_database() {
+    let contents = r#"
+version = 1
+
+[[file]]
+checksum = "crc32:cbf43926"
+sample_count = 22050
+game = "recoil"
+archive = "soundsh"
+name = "br340000.wav"
+cue = [0, 11025]
+"#;
+    let database = Database::read(contents.as_bytes()).unwrap();
+    assert_eq!(database.files.len(), 1);
+    let file = &database.files[0];
+    assert_eq!(file.game, Game::Recoil);
+    assert_eq!(file.archive, "soundsh");
+    assert_eq!(file.cue, vec![0, 11025]);
+}
+
+#[test]
+fn read_newer_version() {
+    let err = Database::read(b"version = 2\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Database version 2 is newer than the supported version 1, please upgrade zcue"
+    );
+}
+
+#[test]
+fn roundtrip() {
+    let mut database = Database::default();
+    database.insert(known("crc32:00000001", Game::Mw3, "a.wav"));
+    database.insert(known("crc32:00000002", Game::Pm, "b.wav"));
+    let actual = Database::read(&database.write().unwrap()).unwrap();
+    assert_eq!(actual.version, DATABASE_VERSION);
+    assert_eq!(actual.files, database.files);
+}
+
+#[test]
+fn identify_by_checksum_and_sample_count() {
+    let mut database = Database::default();
+    database.insert(known("crc32:00000001", Game::Mw3, "a.wav"));
+    database.insert(known("crc32:00000001", Game::Pm, "b.wav"));
+
+    let file = database.identify("crc32:00000001", 100).unwrap();
+    assert_eq!(file.name, "a.wav");
+    assert_eq!(database.identify("crc32:00000001", 101), None);
+    assert_eq!(database.identify("crc32:00000002", 100), None);
+}
+
+#[test]
+fn insert_replaces_same_game_and_archive() {
+    let mut database = Database::default();
+    assert!(!database.insert(known("crc32:00000001", Game::Mw3, "a.wav")));
+    assert!(database.insert(known("crc32:00000001", Game::Mw3, "renamed.wav")));
+    assert!(!database.insert(known("crc32:00000001", Game::Recoil, "a.wav")));
+    assert_eq!(database.files.len(), 2);
+    assert_eq!(database.files[0].name, "renamed.wav");
+}
+
+#[test]
+fn known_file_summary() {
+    let file = known("crc32:00000001", Game::Mw3, "a.wav");
+    assert_eq!(file.to_string(), "MechWarrior 3 `soundsH/a.wav`");
+    assert_eq!(
+        file.summary(Some(&[0, 50])),
+        "MechWarrior 3 `soundsH/a.wav`, 2 original cue point(s), cue points unchanged"
+    );
+    assert!(file.summary(None).ends_with("cue points changed"));
+}
@@ -0,0 +1,505 @@
+//! An interactive terminal editor for the cue points of a wave file.
+//!
+//! The editor state is independent of the terminal, which is driven by
+//! [`terminal::run`].
+use color_eyre::eyre::Result;
+use zcue::{Sampler, WaveFile};
+
+pub(crate) mod terminal;
+
+/// The unit markers are nudged by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Step {
+    Sample,
+    Millisecond,
+}
+
+/// A change to the editor state, usually from a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    /// Move the cursor by a number of columns
+    Move(i64),
+    /// Move the cursor to the start of the audio
+    Start,
+    /// Move the cursor to the end of the audio
+    End,
+    /// Select the next marker and move the cursor to it
+    SelectNext,
+    /// Select the previous marker and move the cursor to it
+    SelectPrevious,
+    /// Add a marker at the cursor
+    Add,
+    /// Delete the selected marker
+    Delete,
+    /// Move the selected marker by a number of steps
+    Nudge(i64),
+    /// Switch between nudging by samples and milliseconds
+    ToggleStep,
+    ZoomIn,
+    ZoomOut,
+    /// Zoom out to show the whole audio
+    ZoomFit,
+    Undo,
+    Redo,
+}
+
+/// How a terminal cell is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Style {
+    Normal,
+    Waveform,
+    Marker,
+    Selected,
+    Cursor,
+}
+
+/// A terminal cell.
+pub(crate) type Cell = (char, Style);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snapshot {
+    markers: Vec<u32>,
+    ids: Vec<u32>,
+    selected: Option<usize>,
+}
+
+/// The state of the editor.
+#[derive(Debug)]
+pub(crate) struct Editor {
+    samples: Vec<f32>,
+    samples_per_sec: u32,
+    /// The marker sample starts, in the order of the cue points, so the
+    /// markers keep their cue point IDs
+    markers: Vec<u32>,
+    /// The cue point ID each marker was read with, or zero if it was added
+    ids: Vec<u32>,
+    selected: Option<usize>,
+    /// The markers and their IDs as last saved
+    saved: (Vec<u32>, Vec<u32>),
+    /// The sampler chunk as read, whose loops refer to the read IDs
+    sampler: Option<Sampler>,
+    /// The sample position of the cursor
+    cursor: u64,
+    /// The sample position of the first column
+    view_start: u64,
+    /// The number of samples per column
+    zoom: u64,
+    /// The number of columns
+    width: u64,
+    step: Step,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    /// A message for the status line, cleared by the next action
+    pub(crate) message: Option<String>,
+}
+
+impl Editor {
+    pub(crate) fn new(wav: &WaveFile, width: u16) -> Result<Self> {
+        let samples = zcue::render::samples(wav)?;
+        let markers = wav.cue.clone().unwrap_or_default();
+        // Cast safety: the cue chunk has at most u32::MAX cue points
+        let ids = (1..=markers.len() as u32).collect::<Vec<_>>();
+        let mut editor = Self {
+            samples,
+            samples_per_sec: wav.fmt.samples_per_sec.as_u32(),
+            saved: (markers.clone(), ids.clone()),
+            markers,
+            ids,
+            selected: None,
+            sampler: wav.sampler.clone(),
+            cursor: 0,
+            view_start: 0,
+            zoom: 1,
+            width: 1,
+            step: Step::Sample,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            message: None,
+        };
+        editor.set_width(width);
+        editor.zoom = editor.fit_zoom();
+        Ok(editor)
+    }
+
+    /// Whether the markers changed since they were last saved.
+    pub(crate) fn is_modified(&self) -> bool {
+        self.markers != self.saved.0 || self.ids != self.saved.1
+    }
+
+    pub(crate) fn mark_saved(&mut self) {
+        self.saved = (self.markers.clone(), self.ids.clone());
+    }
+
+    /// Replace the cue points of the wave file with the markers.
+    ///
+    /// The cue points are numbered in the order of the markers, so the
+    /// sample loops are moved to the new IDs of their cue points, or to no
+    /// cue point if it was deleted.
+    pub(crate) fn apply_to(&self, wav: &mut WaveFile) -> Result<()> {
+        let mut cue = if wav.cue.is_some() {
+            zcue::cue_from_wav(wav)?
+        } else {
+            zcue::CuePoints::new(Vec::new())
+        };
+        let hz = f64::from(self.samples_per_sec);
+        cue.timestamps = self.markers.iter().map(|&s| f64::from(s) / hz).collect();
+        cue.sample_points = None;
+        cue.labels = None;
+        cue.sampler = self.sampler.clone().map(|mut sampler| {
+            for sample_loop in &mut sampler.loops {
+                let index = match sample_loop.cue_point_id {
+                    0 => None,
+                    id => self.ids.iter().position(|&other| other == id),
+                };
+                // Cast safety: there are at most as many markers as cue points
+                sample_loop.cue_point_id = index.map_or(0, |index| index as u32 + 1);
+            }
+            sampler
+        });
+        // the cue points are replaced, not overwritten
+        wav.cue = None;
+        zcue::cue_to_wav(wav, cue)?;
+        Ok(())
+    }
+
+    fn sample_count(&self) -> u64 {
+        // Cast safety: u64 >= usize
+        self.samples.len() as u64
+    }
+
+    /// The zoom that shows the whole audio, and the end of the audio.
+    fn fit_zoom(&self) -> u64 {
+        (self.sample_count() + 1).div_ceil(self.width)
+    }
+
+    /// Set the number of columns, e.g. when the terminal is resized.
+    pub(crate) fn set_width(&mut self, width: u16) {
+        self.width = u64::from(width.max(1));
+        self.zoom = self.zoom.min(self.fit_zoom());
+        self.scroll_to_cursor();
+    }
+
+    /// Scroll the view so the cursor is visible.
+    fn scroll_to_cursor(&mut self) {
+        let span = self.width * self.zoom;
+        if self.cursor < self.view_start {
+            self.view_start = self.cursor - self.cursor % self.zoom;
+        } else if self.cursor >= self.view_start + span {
+            let column = self.cursor / self.zoom;
+            self.view_start = (column + 1).saturating_sub(self.width) * self.zoom;
+        }
+    }
+
+    /// Scroll the view so the cursor is in the middle, e.g. after zooming.
+    fn center_on_cursor(&mut self) {
+        let column = self.cursor / self.zoom;
+        // the cursor may be at the end of the audio, one past the last sample
+        let last = self.sample_count() / self.zoom + 1;
+        let first = column
+            .saturating_sub(self.width / 2)
+            .min(last.saturating_sub(self.width));
+        self.view_start = first * self.zoom;
+        self.scroll_to_cursor();
+    }
+
+    fn set_cursor(&mut self, cursor: u64) {
+        self.cursor = cursor.min(self.sample_count());
+        self.scroll_to_cursor();
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            markers: self.markers.clone(),
+            ids: self.ids.clone(),
+            selected: self.selected,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.markers = snapshot.markers;
+        self.ids = snapshot.ids;
+        self.selected = snapshot.selected;
+        if let Some(index) = self.selected {
+            self.set_cursor(u64::from(self.markers[index]));
+        }
+    }
+
+    /// Record the state before a change, for undo.
+    fn push_undo(&mut self) {
+        self.undo.push(self.snapshot());
+        self.redo.clear();
+    }
+
+    /// The number of samples to nudge by per step.
+    fn step_samples(&self) -> i64 {
+        match self.step {
+            Step::Sample => 1,
+            Step::Millisecond => (i64::from(self.samples_per_sec) + 500) / 1000,
+        }
+    }
+
+    /// The indices of the markers, in ascending order of their positions.
+    fn by_position(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.markers.len()).collect();
+        order.sort_by_key(|&index| (self.markers[index], index));
+        order
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = Some(index);
+        self.set_cursor(u64::from(self.markers[index]));
+    }
+
+    pub(crate) fn apply(&mut self, action: Action) {
+        self.message = None;
+        match action {
+            Action::Move(columns) => {
+                let delta = columns.saturating_mul(self.zoom as i64);
+                self.set_cursor(self.cursor.saturating_add_signed(delta));
+            }
+            Action::Start => self.set_cursor(0),
+            Action::End => self.set_cursor(self.sample_count()),
+            Action::SelectNext | Action::SelectPrevious if self.markers.is_empty() => {
+                self.message = Some("No markers".to_string());
+            }
+            Action::SelectNext => {
+                let order = self.by_position();
+                let rank = match self.selected {
+                    // Panic safety: the selected marker is in the order
+                    Some(index) => order.iter().position(|&i| i == index).unwrap() + 1,
+                    None => order
+                        .iter()
+                        .position(|&i| u64::from(self.markers[i]) >= self.cursor)
+                        .unwrap_or(order.len()),
+                };
+                self.select(order[rank.min(order.len() - 1)]);
+            }
+            Action::SelectPrevious => {
+                let order = self.by_position();
+                let rank = match self.selected {
+                    // Panic safety: the selected marker is in the order
+                    Some(index) => order.iter().position(|&i| i == index).unwrap(),
+                    None => order
+                        .iter()
+                        .rposition(|&i| u64::from(self.markers[i]) <= self.cursor)
+                        .map_or(0, |rank| rank + 1),
+                };
+                self.select(order[rank.saturating_sub(1)]);
+            }
+            Action::Add => {
+                // Cast safety: wave files have at most u32::MAX samples
+                let position = self.cursor.min(u64::from(u32::MAX)) as u32;
+                match self.markers.iter().position(|&m| m == position) {
+                    Some(index) => {
+                        self.selected = Some(index);
+                        self.message = Some("Marker already exists".to_string());
+                    }
+                    None => {
+                        self.push_undo();
+                        self.markers.push(position);
+                        self.ids.push(0);
+                        self.selected = Some(self.markers.len() - 1);
+                    }
+                }
+            }
+            Action::Delete => match self.selected {
+                Some(index) => {
+                    // select the next marker by position, or the previous
+                    // if it was the last
+                    let order = self.by_position();
+                    // Panic safety: the selected marker is in the order
+                    let rank = order.iter().position(|&i| i == index).unwrap();
+                    let next = order
+                        .get(rank + 1)
+                        .or_else(|| rank.checked_sub(1).map(|r| &order[r]));
+                    self.push_undo();
+                    self.markers.remove(index);
+                    self.ids.remove(index);
+                    // the markers after the deleted one move down
+                    self.selected = next.map(|&i| if i > index { i - 1 } else { i });
+                }
+                None => self.message = Some("No marker selected".to_string()),
+            },
+            Action::Nudge(steps) => match self.selected {
+                Some(index) => {
+                    let delta = steps.saturating_mul(self.step_samples());
+                    let max = self.sample_count().min(u64::from(u32::MAX));
+                    let position = u64::from(self.markers[index])
+                        .saturating_add_signed(delta)
+                        .min(max);
+                    // Cast safety: the position is clamped to u32::MAX
+                    let position = position as u32;
+                    // e.g. clamped at the start or the end of the audio
+                    if position == self.markers[index] {
+                        return;
+                    }
+                    if self.markers.contains(&position) {
+                        self.message = Some("Marker already exists".to_string());
+                        return;
+                    }
+                    self.push_undo();
+                    self.markers[index] = position;
+                    self.select(index);
+                }
+                None => self.message = Some("No marker selected".to_string()),
+            },
+            Action::ToggleStep => {
+                self.step = match self.step {
+                    Step::Sample => Step::Millisecond,
+                    Step::Millisecond => Step::Sample,
+                };
+            }
+            Action::ZoomIn => {
+                self.zoom = (self.zoom / 2).max(1);
+                self.center_on_cursor();
+            }
+            Action::ZoomOut => {
+                self.zoom = (self.zoom * 2).min(self.fit_zoom());
+                self.center_on_cursor();
+            }
+            Action::ZoomFit => {
+                self.zoom = self.fit_zoom();
+                self.center_on_cursor();
+            }
+            Action::Undo => match self.undo.pop() {
+                Some(snapshot) => {
+                    self.redo.push(self.snapshot());
+                    self.restore(snapshot);
+                }
+                None => self.message = Some("Nothing to undo".to_string()),
+            },
+            Action::Redo => match self.redo.pop() {
+                Some(snapshot) => {
+                    self.undo.push(self.snapshot());
+                    self.restore(snapshot);
+                }
+                None => self.message = Some("Nothing to redo".to_string()),
+            },
+        }
+    }
+
+    /// The minimum and maximum sample of each column, if the column is
+    /// within the audio.
+    fn columns(&self) -> Vec<Option<(f32, f32)>> {
+        (0..self.width)
+            .map(|column| {
+                // Cast safety: the positions are within the samples
+                let first = (self.view_start + column * self.zoom) as usize;
+                let last = (first + self.zoom as usize).min(self.samples.len());
+                let samples = self.samples.get(first..last)?;
+                samples.iter().fold(None, |range, &s| match range {
+                    None => Some((s, s)),
+                    Some((min, max)) => Some((f32::min(min, s), f32::max(max, s))),
+                })
+            })
+            .collect()
+    }
+
+    /// Draw the waveform with the markers and the cursor, as rows of cells.
+    ///
+    /// The first row numbers the markers. Each row of the waveform is two
+    /// half-block characters high.
+    pub(crate) fn draw(&self, rows: u16) -> Vec<Vec<Cell>> {
+        let wave_rows = u64::from(rows.max(2) - 1);
+        let half_rows = 2 * wave_rows;
+        // Cast safety: the amplitude is clamped to the half rows
+        let half_row = |amplitude: f32| {
+            let position = (1.0 - amplitude.clamp(-1.0, 1.0)) / 2.0 * half_rows as f32;
+            (position as u64).min(half_rows - 1)
+        };
+
+        // Cast safety: the width is a u16
+        let width = self.width as usize;
+        let mut grid = vec![vec![(' ', Style::Normal); width]; wave_rows as usize + 1];
+
+        for (x, range) in self.columns().into_iter().enumerate() {
+            let Some((min, max)) = range else {
+                continue;
+            };
+            let (top, bottom) = (half_row(max), half_row(min));
+            for row in 0..wave_rows {
+                let upper = (top..=bottom).contains(&(2 * row));
+                let lower = (top..=bottom).contains(&(2 * row + 1));
+                let c = match (upper, lower) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                };
+                grid[row as usize + 1][x] = (c, Style::Waveform);
+            }
+        }
+
+        let span = self.view_start..self.view_start + self.width * self.zoom;
+        for (index, &marker) in self.markers.iter().enumerate() {
+            let marker = u64::from(marker);
+            if !span.contains(&marker) {
+                continue;
+            }
+            // Cast safety: the column is within the width
+            let x = ((marker - self.view_start) / self.zoom) as usize;
+            let style = if self.selected == Some(index) {
+                Style::Selected
+            } else {
+                Style::Marker
+            };
+            for row in grid.iter_mut().skip(1) {
+                let (c, _) = row[x];
+                row[x] = (if c == ' ' { '│' } else { c }, style);
+            }
+            let label = (index + 1).to_string();
+            for (i, c) in label.chars().enumerate() {
+                if let Some(cell) = grid[0].get_mut(x + i) {
+                    *cell = (c, style);
+                }
+            }
+        }
+
+        if span.contains(&self.cursor) {
+            // Cast safety: the column is within the width
+            let x = ((self.cursor - self.view_start) / self.zoom) as usize;
+            for row in grid.iter_mut().skip(1) {
+                row[x].1 = Style::Cursor;
+            }
+        }
+        grid
+    }
+
+    fn format_position(&self, position: u64) -> String {
+        format!(
+            "{:.3}s ({})",
+            position as f64 / f64::from(self.samples_per_sec),
+            position
+        )
+    }
+
+    /// The status line, with the cursor, selected marker, and settings.
+    pub(crate) fn status(&self) -> String {
+        let mut status = format!("cursor {}", self.format_position(self.cursor));
+        match self.selected {
+            Some(index) => status.push_str(&format!(
+                "  marker {}/{} at {}",
+                index + 1,
+                self.markers.len(),
+                self.format_position(u64::from(self.markers[index]))
+            )),
+            None => status.push_str(&format!("  {} marker(s)", self.markers.len())),
+        }
+        let step = match self.step {
+            Step::Sample => "sample",
+            Step::Millisecond => "ms",
+        };
+        status.push_str(&format!("  step: 1 {}  zoom: {}x", step, self.zoom));
+        if self.is_modified() {
+            status.push_str("  [modified]");
+        }
+        if let Some(message) = &self.message {
+            status.push_str("  ");
+            status.push_str(message);
+        }
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,166 @@
+//! Drive the editor in a terminal with crossterm.
+use super::{Action, Editor, Style};
+use color_eyre::eyre::{Context as _, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, SetAttribute, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use std::io::{self, Write as _};
+
+const HELP: &str = "←/→ move  Tab select  a add  d delete  ,/. nudge  </> nudge x10  \
+                    m step  +/- zoom  0 fit  u undo  r redo  s save  q quit";
+
+/// Restores the terminal when dropped, even on errors.
+struct Guard;
+
+impl Guard {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode().wrap_err("Error starting the terminal editor")?;
+        let guard = Self;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// What a key press does.
+enum Command {
+    Edit(Action),
+    Save,
+    Quit,
+}
+
+fn command(key: KeyEvent) -> Option<Command> {
+    let action = match key.code {
+        KeyCode::Left if key.modifiers.contains(KeyModifiers::SHIFT) => Action::Move(-10),
+        KeyCode::Right if key.modifiers.contains(KeyModifiers::SHIFT) => Action::Move(10),
+        KeyCode::Left => Action::Move(-1),
+        KeyCode::Right => Action::Move(1),
+        KeyCode::PageUp => Action::Move(-40),
+        KeyCode::PageDown => Action::Move(40),
+        KeyCode::Home => Action::Start,
+        KeyCode::End => Action::End,
+        KeyCode::Tab => Action::SelectNext,
+        KeyCode::BackTab => Action::SelectPrevious,
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            return Some(Command::Quit)
+        }
+        KeyCode::Char('a') | KeyCode::Insert => Action::Add,
+        KeyCode::Char('d') | KeyCode::Delete => Action::Delete,
+        KeyCode::Char(',') => Action::Nudge(-1),
+        KeyCode::Char('.') => Action::Nudge(1),
+        KeyCode::Char('<') => Action::Nudge(-10),
+        KeyCode::Char('>') => Action::Nudge(10),
+        KeyCode::Char('m') => Action::ToggleStep,
+        KeyCode::Char('+') | KeyCode::Char('=') => Action::ZoomIn,
+        KeyCode::Char('-') => Action::ZoomOut,
+        KeyCode::Char('0') => Action::ZoomFit,
+        KeyCode::Char('u') => Action::Undo,
+        KeyCode::Char('r') => Action::Redo,
+        KeyCode::Char('s') => return Some(Command::Save),
+        KeyCode::Char('q') | KeyCode::Esc => return Some(Command::Quit),
+        _ => return None,
+    };
+    Some(Command::Edit(action))
+}
+
+fn color(style: Style) -> Color {
+    match style {
+        Style::Normal => Color::Reset,
+        Style::Waveform => Color::Cyan,
+        Style::Marker => Color::Red,
+        Style::Selected => Color::Yellow,
+        Style::Cursor => Color::White,
+    }
+}
+
+fn draw(editor: &Editor, title: &str, rows: u16) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    queue!(
+        stdout,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::MoveTo(0, 0),
+        SetAttribute(Attribute::Bold),
+        Print(title),
+        SetAttribute(Attribute::Reset),
+    )?;
+    // the title, status, and help lines take three rows
+    let grid = editor.draw(rows.saturating_sub(3));
+    for (row, y) in grid.iter().zip(1..) {
+        queue!(stdout, cursor::MoveTo(0, y))?;
+        for &(c, style) in row {
+            if style == Style::Cursor {
+                queue!(stdout, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(stdout, SetForegroundColor(color(style)), Print(c))?;
+            if style == Style::Cursor {
+                queue!(stdout, SetAttribute(Attribute::NoReverse))?;
+            }
+        }
+    }
+    queue!(
+        stdout,
+        SetForegroundColor(Color::Reset),
+        cursor::MoveTo(0, rows.saturating_sub(2)),
+        Print(editor.status()),
+        cursor::MoveTo(0, rows.saturating_sub(1)),
+        SetAttribute(Attribute::Dim),
+        Print(HELP),
+        SetAttribute(Attribute::Reset),
+    )?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Run the editor until the user quits. `save` is called when the user
+/// saves, and returns the message to show.
+pub(crate) fn run(
+    editor: &mut Editor,
+    title: &str,
+    mut save: impl FnMut(&Editor) -> Result<String>,
+) -> Result<()> {
+    let _guard = Guard::enter()?;
+    let mut confirm_quit = false;
+    loop {
+        let (columns, rows) = terminal::size()?;
+        editor.set_width(columns);
+        draw(editor, title, rows)?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        let Some(command) = command(key) else {
+            continue;
+        };
+        match command {
+            Command::Edit(action) => editor.apply(action),
+            Command::Save => {
+                editor.message = Some(match save(editor) {
+                    Ok(message) => {
+                        editor.mark_saved();
+                        message
+                    }
+                    Err(err) => {
+                        let causes: Vec<String> = err.chain().map(ToString::to_string).collect();
+                        format!("Error: {}", causes.join(": "))
+                    }
+                });
+            }
+            Command::Quit if editor.is_modified() && !confirm_quit => {
+                editor.message = Some("Unsaved changes, press q again to quit".to_string());
+                confirm_quit = true;
+                continue;
+            }
+            Command::Quit => return Ok(()),
+        }
+        confirm_quit = false;
+    }
+}
@@ -0,0 +1,258 @@
+use super::*;
+use zcue::{BitsPerSample, Channels, Format, SampleLoop, SamplesPerSec};
+
+/// 999 samples, rising from -1 to 1, with cue points.
+fn wav(cue: Option<Vec<u32>>) -> WaveFile {
+    let mut data = Vec::new();
+    for i in 0..999i32 {
+        let sample = (i * 65 - 32768).clamp(-32768, 32767) as i16;
+        data.extend(sample.to_le_bytes());
+    }
+    let fmt = Format {
+        channels: Channels::One,
+        samples_per_sec: SamplesPerSec::Hz11025,
+        bits_per_sample: BitsPerSample::Sixteen,
+    };
+    let mut wav = WaveFile::new(fmt, data);
+    wav.cue = cue;
+    wav
+}
+
+fn editor(cue: Vec<u32>) -> Editor {
+    Editor::new(&wav(Some(cue)), 100).unwrap()
+}
+
+#[test]
+fn new_keeps_cue_order_and_fits_audio() {
+    let editor = editor(vec![500, 100, 500]);
+    assert_eq!(editor.markers, vec![500, 100, 500]);
+    assert_eq!(editor.ids, vec![1, 2, 3]);
+    assert_eq!(editor.zoom, 10);
+    assert!(!editor.is_modified());
+}
+
+#[test]
+fn add_and_delete() {
+    let mut editor = editor(vec![100, 500]);
+    editor.apply(Action::Move(30));
+    assert_eq!(editor.cursor, 300);
+    editor.apply(Action::Add);
+    assert_eq!(editor.markers, vec![100, 500, 300]);
+    assert_eq!(editor.ids, vec![1, 2, 0]);
+    assert_eq!(editor.selected, Some(2));
+    assert!(editor.is_modified());
+
+    editor.apply(Action::Add);
+    assert_eq!(editor.message.as_deref(), Some("Marker already exists"));
+    assert_eq!(editor.undo.len(), 1);
+
+    // the next marker by position is selected after deleting
+    editor.apply(Action::SelectNext);
+    assert_eq!(editor.selected, Some(1));
+    editor.apply(Action::Delete);
+    assert_eq!(editor.markers, vec![100, 300]);
+    assert_eq!(editor.ids, vec![1, 0]);
+    assert_eq!(editor.selected, Some(1));
+    editor.apply(Action::SelectPrevious);
+    editor.apply(Action::Delete);
+    assert_eq!(editor.markers, vec![300]);
+    assert_eq!(editor.selected, Some(0));
+    editor.apply(Action::Delete);
+    assert_eq!(editor.markers, Vec::<u32>::new());
+    assert_eq!(editor.selected, None);
+    editor.apply(Action::Delete);
+    assert_eq!(editor.message.as_deref(), Some("No marker selected"));
+}
+
+#[test]
+fn nudge_by_sample_and_millisecond() {
+    let mut editor = editor(vec![100, 500]);
+    editor.apply(Action::SelectNext);
+    assert_eq!((editor.selected, editor.cursor), (Some(0), 100));
+
+    editor.apply(Action::Nudge(-1));
+    assert_eq!(editor.markers, vec![99, 500]);
+    editor.apply(Action::ToggleStep);
+    // 11 samples per millisecond at 11025 Hz
+    editor.apply(Action::Nudge(10));
+    assert_eq!(editor.markers, vec![209, 500]);
+    assert_eq!(editor.cursor, 209);
+
+    // nudging past another marker keeps the cue point ID
+    editor.apply(Action::Nudge(30));
+    assert_eq!(editor.markers, vec![539, 500]);
+    assert_eq!(editor.selected, Some(0));
+    editor.apply(Action::SelectPrevious);
+    assert_eq!(editor.selected, Some(1));
+    editor.apply(Action::SelectNext);
+
+    // nudging is clamped to the audio
+    editor.apply(Action::Nudge(-1000));
+    assert_eq!(editor.markers, vec![0, 500]);
+    let undo = editor.undo.len();
+    editor.apply(Action::Nudge(-1));
+    assert_eq!(editor.markers, vec![0, 500]);
+    assert_eq!((editor.message.as_deref(), editor.undo.len()), (None, undo));
+    editor.apply(Action::Nudge(1000));
+    assert_eq!(editor.markers, vec![999, 500]);
+    editor.apply(Action::Nudge(1));
+    assert_eq!(editor.message, None);
+
+    editor.apply(Action::ToggleStep);
+    editor.apply(Action::Nudge(-499));
+    assert_eq!(editor.message.as_deref(), Some("Marker already exists"));
+}
+
+#[test]
+fn undo_and_redo() {
+    let mut editor = editor(vec![100]);
+    editor.apply(Action::SelectNext);
+    editor.apply(Action::Nudge(5));
+    editor.apply(Action::Delete);
+    assert_eq!(editor.markers, Vec::<u32>::new());
+
+    editor.apply(Action::Undo);
+    assert_eq!(editor.markers, vec![105]);
+    editor.apply(Action::Undo);
+    assert_eq!(editor.markers, vec![100]);
+    assert!(!editor.is_modified());
+    editor.apply(Action::Undo);
+    assert_eq!(editor.message.as_deref(), Some("Nothing to undo"));
+
+    editor.apply(Action::Redo);
+    assert_eq!(editor.markers, vec![105]);
+    // a change clears the redo history
+    editor.apply(Action::Nudge(1));
+    editor.apply(Action::Redo);
+    assert_eq!(editor.message.as_deref(), Some("Nothing to redo"));
+    assert_eq!(editor.markers, vec![106]);
+}
+
+#[test]
+fn zoom_keeps_cursor_visible() {
+    let mut editor = editor(vec![]);
+    editor.apply(Action::End);
+    editor.apply(Action::ZoomIn);
+    assert_eq!(editor.zoom, 5);
+    assert_eq!(editor.view_start, 500);
+    for _ in 0..5 {
+        editor.apply(Action::ZoomIn);
+    }
+    assert_eq!(editor.zoom, 1);
+    assert_eq!(editor.view_start, 900);
+
+    editor.apply(Action::Start);
+    assert_eq!(editor.view_start, 0);
+    editor.apply(Action::Move(150));
+    assert_eq!(editor.view_start, 51);
+
+    editor.apply(Action::ZoomOut);
+    editor.apply(Action::ZoomFit);
+    assert_eq!((editor.zoom, editor.view_start), (10, 0));
+    editor.apply(Action::ZoomOut);
+    assert_eq!(editor.zoom, 10);
+}
+
+#[test]
+fn draw_waveform_and_markers() {
+    let mut editor = editor(vec![0, 995]);
+    editor.apply(Action::End);
+    editor.apply(Action::SelectPrevious);
+    editor.apply(Action::Move(-45));
+    let grid = editor.draw(5);
+    assert_eq!(grid.len(), 5);
+    assert!(grid.iter().all(|row| row.len() == 100));
+
+    let label: String = grid[0].iter().map(|&(c, _)| c).collect();
+    assert_eq!(label.trim_end(), format!("1{}2", " ".repeat(98)));
+    assert_eq!(grid[0][99], ('2', Style::Selected));
+
+    // the first marker is at the bottom of the rising waveform, the cursor
+    // just above the middle
+    let column = |x: usize| -> String { grid[1..].iter().map(|row| row[x].0).collect() };
+    assert_eq!(column(0), "│││▄");
+    assert_eq!(grid[1][0].1, Style::Marker);
+    assert_eq!(column(54), " ▄  ");
+    assert_eq!(grid[1][54].1, Style::Cursor);
+    assert_eq!(column(99), "▀│││");
+    assert_eq!(grid[4][99].1, Style::Selected);
+}
+
+#[test]
+fn status_line() {
+    let mut editor = editor(vec![110]);
+    editor.apply(Action::SelectNext);
+    assert_eq!(
+        editor.status(),
+        "cursor 0.010s (110)  marker 1/1 at 0.010s (110)  step: 1 sample  zoom: 10x"
+    );
+    editor.apply(Action::Delete);
+    assert_eq!(
+        editor.status(),
+        "cursor 0.010s (110)  0 marker(s)  step: 1 sample  zoom: 10x  [modified]"
+    );
+}
+
+#[test]
+fn apply_markers_to_wave_file() {
+    let mut wav = wav(None);
+    let mut editor = Editor::new(&wav, 100).unwrap();
+    editor.apply(Action::Move(12));
+    editor.apply(Action::Add);
+    editor.apply(Action::End);
+    editor.apply(Action::Add);
+    editor.apply_to(&mut wav).unwrap();
+    assert_eq!(wav.cue, Some(vec![120, 999]));
+
+    editor.mark_saved();
+    assert!(!editor.is_modified());
+    editor.apply(Action::Delete);
+    editor.apply_to(&mut wav).unwrap();
+    assert_eq!(wav.cue, Some(vec![120]));
+}
+
+#[test]
+fn apply_keeps_sample_loop_cue_points() {
+    let sample_loop = |cue_point_id, start| SampleLoop {
+        cue_point_id,
+        loop_type: 0,
+        start,
+        end: start + 10,
+        fraction: 0,
+        play_count: 0,
+    };
+    let mut wav = wav(Some(vec![300, 100, 200]));
+    let mut sampler = Sampler::new(SamplesPerSec::Hz11025);
+    sampler.loops = vec![sample_loop(1, 300), sample_loop(3, 200), sample_loop(0, 0)];
+    wav.sampler = Some(sampler);
+
+    let mut editor = Editor::new(&wav, 100).unwrap();
+    // delete the first cue point, which is at 300
+    editor.apply(Action::Move(25));
+    editor.apply(Action::SelectNext);
+    editor.apply(Action::Delete);
+    editor.apply(Action::Start);
+    editor.apply(Action::Add);
+    editor.apply_to(&mut wav).unwrap();
+    assert_eq!(wav.cue, Some(vec![100, 200, 0]));
+    let loops = &wav.sampler.as_ref().unwrap().loops;
+    let ids: Vec<u32> = loops.iter().map(|l| l.cue_point_id).collect();
+    assert_eq!(ids, vec![0, 2, 0]);
+    assert_eq!(loops[1].start, 200);
+
+    // the loops still refer to the cue points as read
+    editor.mark_saved();
+    editor.apply(Action::Undo);
+    editor.apply(Action::Undo);
+    assert!(editor.is_modified());
+    editor.apply_to(&mut wav).unwrap();
+    assert_eq!(wav.cue, Some(vec![300, 100, 200]));
+    let ids: Vec<u32> = wav
+        .sampler
+        .unwrap()
+        .loops
+        .iter()
+        .map(|l| l.cue_point_id)
+        .collect();
+    assert_eq!(ids, vec![1, 3, 0]);
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+
+/// An error with the chain of its causes, e.g. a parse error and the line
+/// of the cue file it is on.
+///
+/// Reading a wave file fails with the more specific [`ReadError`], which an
+/// error may also be caused by, see [`Error::find`].
+///
+/// [`ReadError`]: crate::ReadError
+pub struct Error(eyre::Report);
+
+/// The result of zcue functions, which fail with an [`Error`] by default.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    /// The error and its causes, outermost first.
+    pub fn chain(&self) -> impl Iterator<Item = &(dyn std::error::Error + 'static)> {
+        self.0.chain()
+    }
+
+    /// The innermost cause of the error.
+    pub fn root_cause(&self) -> &(dyn std::error::Error + 'static) {
+        self.0.root_cause()
+    }
+
+    /// The outermost error or cause of the type, if any.
+    pub fn find<E: std::error::Error + 'static>(&self) -> Option<&E> {
+        self.chain().find_map(|cause| cause.downcast_ref())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.chain().nth(1)
+    }
+}
+
+impl From<eyre::Report> for Error {
+    fn from(report: eyre::Report) -> Self {
+        Self(report)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self(err.into())
+    }
+}
+
+impl From<crate::ReadError> for Error {
+    fn from(err: crate::ReadError) -> Self {
+        Self(err.into())
+    }
+}
+
+/// Return early with an error, like `eyre::bail!`, but converted into the
+/// error type of the function, so it also works in public functions.
+macro_rules! bail {
+    ($($arg:tt)*) => {
+        return Err(eyre::eyre!($($arg)*).into())
+    };
+}
+pub(crate) use bail;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,42 @@
+use super::*;
+use crate::ReadError;
+use eyre::WrapErr as _;
+use std::error::Error as _;
+
+#[test]
+fn error_display_and_chain() {
+    let err: Error = Err::<(), _>(ReadError::Truncated { offset: 12 })
+        .wrap_err("Error parsing cue file")
+        .unwrap_err()
+        .into();
+    assert_eq!(err.to_string(), "Error parsing cue file");
+    let causes: Vec<String> = err.chain().map(ToString::to_string).collect();
+    assert_eq!(causes.len(), 2);
+    assert_eq!(causes[0], "Error parsing cue file");
+    assert_eq!(err.root_cause().to_string(), causes[1]);
+}
+
+#[test]
+fn error_source_skips_own_message() {
+    let err: Error = Err::<(), _>(ReadError::Truncated { offset: 12 })
+        .wrap_err("Error parsing cue file")
+        .unwrap_err()
+        .into();
+    let source = err.source().unwrap();
+    assert!(source.downcast_ref::<ReadError>().is_some());
+    assert!(source.source().is_none());
+}
+
+#[test]
+fn error_find_cause() {
+    let err = Error::from(ReadError::Truncated { offset: 12 });
+    assert_eq!(
+        err.find::<ReadError>().and_then(ReadError::offset),
+        Some(12)
+    );
+    assert!(err.source().is_none());
+    assert!(err.find::<std::io::Error>().is_none());
+
+    let err = Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+    assert!(err.find::<std::io::Error>().is_some());
+}
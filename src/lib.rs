@@ -0,0 +1,54 @@
+//! Extract or apply cue points from wave files of certain games developed by
+//! Zipper Interactive™.
+//!
+//! A wave file is read into a [`WaveFile`] with [`read`], and written back
+//! with [`write`]. Cue points are converted to and from the file-independent
+//! [`CuePoints`] model with [`cue_from_wav`] and [`cue_to_wav`], or applied
+//! with [`apply_cue`] as `zcue apply` does. The [`cue_file`] module reads and
+//! writes cue points in various formats.
+//!
+//! The [`manifest`] module describes building a whole sound mod from a
+//! TOML manifest, incrementally, with [`manifest::build_entry`]. The
+//! [`render`] module draws the waveform and cue points of a wave file as an
+//! image.
+//!
+//! When the audio data isn't needed, [`read_header`] reads only the header
+//! and cue points from a seekable reader, e.g. a buffered file.
+//!
+//! Reading a wave file fails with a typed [`ReadError`]. Other functions
+//! fail with an [`Error`], with the chain of causes.
+//!
+//! The [`WaveFile`], [`WaveHeader`], and [`CuePoints`] structs are
+//! `#[non_exhaustive]`, since new chunks and cue file versions add fields.
+//!
+//! ```no_run
+//! # fn main() -> zcue::Result<()> {
+//! let buf = std::fs::read("br340000.wav")?;
+//! let wav = zcue::read(&buf)?;
+//! let cue = zcue::cue_from_wav(&wav)?;
+//! println!("{:?}", cue.timestamps);
+//! # Ok(())
+//! # }
+//! ```
+#![warn(missing_docs)]
+
+mod checksum;
+pub mod cue_file;
+pub mod database;
+mod error;
+pub mod manifest;
+pub mod render;
+pub mod subtitles;
+mod wave;
+
+pub use error::{Error, Result};
+
+pub use wave::{
+    apply_cue, apply_cue_to_header, convert, cue_from_header, cue_from_wav, cue_to_header,
+    cue_to_wav, data_checksum, offset_timestamps, read, read_header, read_header_with, roundtrip,
+    update_in_place, write, write_flac, write_with, AdpcmLayout, ApplyOptions, Bext, BitsPerSample,
+    Channels, ChunkId, ChunkInfo, Codec, Container, CuePoints, CuePosition, Encoding, Expected,
+    Format, Info, Metadata, Mismatch, Profile, ReadError, SampleLoop, SamplePoints, Sampler,
+    SamplesPerSec, UpdateMethod, Value, Violation, WaveFile, WaveHeader, CUE_VERSION,
+    CUE_VERSION_LEGACY,
+};
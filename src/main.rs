@@ -1,68 +1,788 @@
 mod cli;
-mod wave;
+mod edit;
+mod output;
 
-use cli::{ApplyArgs, Commands, ExtractArgs, MainArgs};
-use color_eyre::eyre::{eyre, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use cli::{
+    ApplyArgs, BuildArgs, CatalogArgs, Commands, ConvertArgs, EditArgs, ExtractArgs, InfoArgs,
+    MainArgs, RenderArgs, RestoreArgs, RoundtripArgs, SchemaArgs, SubtitlesCommands,
+    SubtitlesExportArgs, SubtitlesImportArgs, ValidateArgs,
+};
+use color_eyre::eyre::{bail, eyre, Context, Report, Result};
+use std::io::{BufReader, Read as _, Write as _};
+use zcue::{cue_file, database, manifest, render, subtitles};
+
+/// Whether the path is `-`, which means standard input or output.
+fn is_stdio(path: &Utf8Path) -> bool {
+    path.as_str() == "-"
+}
+
+/// Read a file, or standard input if the path is `-`.
+fn read_file(path: &Utf8Path) -> std::io::Result<Vec<u8>> {
+    if is_stdio(path) {
+        let mut buf = Vec::new();
+        std::io::stdin().lock().read_to_end(&mut buf)?;
+        Ok(buf)
+    } else {
+        std::fs::read(path)
+    }
+}
+
+/// Write a file, or standard output if the path is `-`.
+fn write_file(path: &Utf8Path, contents: &[u8]) -> std::io::Result<()> {
+    if is_stdio(path) {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(contents)?;
+        stdout.flush()
+    } else {
+        std::fs::write(path, contents)
+    }
+}
+
+/// Check that at most one input and one output is `-`. If an output is, the
+/// output of the command is printed to standard error instead.
+fn check_stdio(inputs: &[&Utf8Path], outputs: &[&Utf8Path]) -> Result<()> {
+    if inputs.iter().filter(|path| is_stdio(path)).count() > 1 {
+        bail!("Only one input file can be read from standard input (`-`)");
+    }
+    match outputs.iter().filter(|path| is_stdio(path)).count() {
+        0 => {}
+        1 => output::redirect(),
+        _ => bail!("Only one output file can be written to standard output (`-`)"),
+    }
+    Ok(())
+}
+
+fn read_cue_file(
+    cue_file: &Utf8Path,
+    format: Option<cue_file::CueFormat>,
+    samples_per_sec: zcue::SamplesPerSec,
+) -> Result<zcue::CuePoints> {
+    tracing::trace!("reading cue file `{}`", cue_file);
+    let contents =
+        read_file(cue_file).wrap_err_with(|| format!("Error reading cue file `{}`", cue_file))?;
+    let format = cue_file::CueFormat::resolve(format, cue_file);
+    let cue = cue_file::read(format, &contents, Some(samples_per_sec))
+        .wrap_err_with(|| format!("Error parsing cue file `{}`", cue_file))?;
+
+    output::read(cue_file);
+    Ok(cue)
+}
+
+/// Whether the file is FLAC by the file extension.
+fn is_flac(path: &Utf8Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("flac"))
+}
+
+/// Whether the file is AIFF, AIFF-C, or FLAC by the file extension, which is
+/// converted when reading it.
+fn is_converted(path: &Utf8Path) -> bool {
+    is_flac(path)
+        || path.extension().is_some_and(|ext| {
+            ["aif", "aiff", "aifc"]
+                .iter()
+                .any(|aiff| ext.eq_ignore_ascii_case(aiff))
+        })
+}
+
+fn read_wave_file(path: &Utf8Path) -> Result<zcue::WaveFile> {
+    tracing::trace!("reading input file `{}`", path);
+    let buf = read_file(path).wrap_err_with(|| format!("Error reading input file `{}`", path))?;
+    zcue::read(&buf).wrap_err_with(|| format!("Error parsing input file `{}`", path))
+}
+
+/// Write the wave file, or encode it as FLAC if the output file has a
+/// `.flac` extension.
+fn encode_wave_file(
+    path: &Utf8Path,
+    wav: &zcue::WaveFile,
+    cue_position: zcue::CuePosition,
+) -> Result<Vec<u8>> {
+    let contents = if is_flac(path) {
+        zcue::write_flac(wav)
+    } else {
+        zcue::write_with(wav, cue_position)
+    };
+    contents.wrap_err("Error constructing output file")
+}
+
+fn write_wave_file(
+    path: &Utf8Path,
+    wav: &zcue::WaveFile,
+    cue_position: zcue::CuePosition,
+) -> Result<()> {
+    tracing::trace!("writing output file `{}`", path);
+    let contents = encode_wave_file(path, wav, cue_position)?;
+    write_file(path, &contents).wrap_err_with(|| format!("Error writing output file `{}`", path))
+}
+
+/// Update the wave file in place, with an optional backup.
+fn update_file(
+    path: &Utf8Path,
+    header: &zcue::WaveHeader,
+    cue_position: zcue::CuePosition,
+    backup: bool,
+) -> Result<()> {
+    let backup = backup.then(|| Utf8PathBuf::from(format!("{}.bak", path)));
+    tracing::trace!("updating input file `{}`", path);
+    let method = zcue::update_in_place(
+        path.as_std_path(),
+        header,
+        cue_position,
+        backup.as_deref().map(Utf8Path::as_std_path),
+    )
+    .wrap_err_with(|| format!("Error updating input file `{}`", path))?;
+
+    if let Some(backup) = &backup {
+        output::wrote(backup);
+    }
+    match method {
+        zcue::UpdateMethod::Patched => output::wrote_with("Patched", path),
+        zcue::UpdateMethod::Padded => output::wrote_with("Padded", path),
+        zcue::UpdateMethod::Appended => output::wrote_with("Appended to", path),
+        zcue::UpdateMethod::Rewritten => output::wrote_with("Rewrote", path),
+    }
+    Ok(())
+}
+
+fn apply_options(args: &ApplyArgs) -> zcue::ApplyOptions {
+    let mut options = zcue::ApplyOptions::default();
+    options.bext_offset = args.bext_offset;
+    options.loops_from_cues = args.loops_from_cues;
+    options.strip_info = args.strip_info;
+    options
+}
+
+fn apply_in_place(args: ApplyArgs) -> Result<()> {
+    if is_converted(&args.input_file) {
+        bail!(
+            "AIFF and FLAC files can't be updated in place, specify an output file to convert them"
+        );
+    }
+    if is_stdio(&args.input_file) {
+        bail!("Standard input can't be updated in place, specify an output file");
+    }
+    let (mut header, mut reader) = read_header(&args.input_file)?;
+
+    output::read(&args.input_file);
+
+    let cue = read_cue_file(&args.cue_file, args.format, header.fmt.samples_per_sec)?;
+    zcue::apply_cue_to_header(&mut header, cue, &mut reader, apply_options(&args))?;
+    drop(reader);
+    args.profile.validate_header(&header)?;
+
+    update_file(&args.input_file, &header, args.cue_position, args.backup)
+}
 
 fn apply(args: ApplyArgs) -> Result<()> {
-    tracing::trace!("reading cue file `{}`", args.cue_file);
-    let contents = std::fs::read(&args.cue_file)
-        .wrap_err_with(|| format!("Error reading cue file `{}`", args.cue_file))?;
-    let cue: wave::CuePoints = serde_json::from_slice(&contents)
-        .wrap_err_with(|| format!("Error parsing cue file `{}`", args.cue_file))?;
+    check_stdio(
+        &[&args.input_file, &args.cue_file],
+        args.output_file.as_deref().as_slice(),
+    )?;
+    let Some(output_file) = args.output_file.clone() else {
+        return apply_in_place(args);
+    };
 
-    println!("Read `{}`", args.cue_file);
+    let mut wav = read_wave_file(&args.input_file)?;
 
-    tracing::trace!("reading input file `{}`", args.input_file);
-    let buf = std::fs::read(&args.input_file)
-        .wrap_err_with(|| format!("Error reading input file `{}`", args.input_file))?;
-    let mut wav = wave::read(&buf)
-        .wrap_err_with(|| format!("Error parsing input file `{}`", args.input_file))?;
+    output::read(&args.input_file);
 
-    println!("Read `{}`", args.input_file);
+    let cue = read_cue_file(&args.cue_file, args.format, wav.fmt.samples_per_sec)?;
+    zcue::apply_cue(&mut wav, cue, apply_options(&args))?;
+    args.profile.validate(&wav)?;
 
-    wave::cue_to_wav(&mut wav, cue)?;
+    write_wave_file(&output_file, &wav, args.cue_position)?;
 
-    tracing::trace!("writing output file `{}`", args.output_file);
-    let contents = wave::write(&wav).wrap_err("Error constructing output file")?;
-    std::fs::write(&args.output_file, contents)
-        .wrap_err_with(|| format!("Error writing output file `{}`", args.output_file))?;
+    output::wrote(&output_file);
+    Ok(())
+}
+
+fn convert(args: ConvertArgs) -> Result<()> {
+    check_stdio(&[&args.input_file], &[&args.output_file])?;
+    let wav = read_wave_file(&args.input_file)?;
+
+    output::read(&args.input_file);
 
-    println!("Wrote `{}`", args.output_file);
+    let wav = zcue::convert(&wav, args.codec)?;
+
+    write_wave_file(&args.output_file, &wav, zcue::CuePosition::Original)?;
+
+    output::wrote(&args.output_file);
     Ok(())
 }
 
+fn read_header(path: &Utf8Path) -> Result<(zcue::WaveHeader, BufReader<std::fs::File>)> {
+    read_header_with(path, zcue::Profile::Generic)
+}
+
+fn read_header_with(
+    path: &Utf8Path,
+    profile: zcue::Profile,
+) -> Result<(zcue::WaveHeader, BufReader<std::fs::File>)> {
+    tracing::trace!("reading input file `{}`", path);
+    let file = std::fs::File::open(path)
+        .wrap_err_with(|| format!("Error reading input file `{}`", path))?;
+    let mut reader = BufReader::new(file);
+    let header = zcue::read_header_with(&mut reader, profile)
+        .wrap_err_with(|| format!("Error parsing input file `{}`", path))?;
+    Ok((header, reader))
+}
+
+/// Expand directories to the wave files they contain, recursively.
+fn wave_files(paths: &[Utf8PathBuf]) -> Result<Vec<Utf8PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let mut entries = path
+            .read_dir_utf8()
+            .wrap_err_with(|| format!("Error reading directory `{}`", path))?
+            .map(|entry| entry.map(|entry| entry.into_path()))
+            .collect::<std::io::Result<Vec<_>>>()
+            .wrap_err_with(|| format!("Error reading directory `{}`", path))?;
+        entries.sort();
+        for entry in entries {
+            let is_wave = entry
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"));
+            if entry.is_dir() {
+                files.extend(wave_files(&[entry])?);
+            } else if is_wave {
+                files.push(entry);
+            }
+        }
+    }
+    Ok(files)
+}
+
 fn extract(args: ExtractArgs) -> Result<()> {
-    tracing::trace!("reading input file `{}`", args.input_file);
-    let buf = std::fs::read(&args.input_file)
-        .wrap_err_with(|| format!("Error reading input file `{}`", args.input_file))?;
-    let wav = wave::read(&buf)
-        .wrap_err_with(|| format!("Error parsing input file `{}`", args.input_file))?;
+    check_stdio(&[&args.input_file], &[&args.cue_file])?;
+    let source = args
+        .input_file
+        .file_name()
+        .filter(|_| !is_stdio(&args.input_file))
+        .map(str::to_string);
+    let (mut cue, metadata, offset) =
+        if is_converted(&args.input_file) || is_stdio(&args.input_file) {
+            // the checksum is of the converted audio data, as written by
+            // `apply`, and standard input can't be read as a header only
+            let wav = read_wave_file(&args.input_file)?;
+            let cue = zcue::cue_from_wav(&wav)?;
+            let metadata = zcue::Metadata::from_wav(&wav, source);
+            let offset = args.bext_offset.then(|| wav.bext_offset()).transpose()?;
+            (cue, metadata, offset)
+        } else {
+            let (header, mut reader) = read_header(&args.input_file)?;
+            let checksum = header
+                .data_checksum(&mut reader)
+                .wrap_err_with(|| format!("Error reading input file `{}`", args.input_file))?;
+            let cue = zcue::cue_from_header(&header)?;
+            let metadata = zcue::Metadata::from_header(&header, Some(checksum), source);
+            let offset = args.bext_offset.then(|| header.bext_offset()).transpose()?;
+            (cue, metadata, offset)
+        };
 
-    println!("Read `{}`", args.input_file);
+    output::read(&args.input_file);
 
-    let mut cue = wave::cue_from_wav(&wav)?;
     if !args.samples {
         cue.sample_points = None;
     }
+    if let Some(offset) = offset {
+        zcue::offset_timestamps(&mut cue, offset)?;
+    }
+    cue.metadata = Some(metadata);
 
     tracing::trace!("writing cue file `{}`", args.cue_file);
-    let mut contents = serde_json::to_vec_pretty(&cue).wrap_err("Internal error")?;
-    contents.push(b'\n');
-    std::fs::write(&args.cue_file, contents)
+    let format = cue_file::CueFormat::resolve(args.format, &args.cue_file);
+    let contents = cue_file::write(format, &cue)?;
+    write_file(&args.cue_file, &contents)
         .wrap_err_with(|| format!("Error writing cue file `{}`", args.cue_file))?;
 
-    println!("Wrote `{}`", args.cue_file);
+    output::wrote(&args.cue_file);
+    Ok(())
+}
+
+fn info(args: InfoArgs) -> Result<()> {
+    let database = args.database.as_deref().map(read_database).transpose()?;
+    for input_file in wave_files(&args.input_files)? {
+        let (header, mut reader) = read_header(&input_file)?;
+        output::message(format!("`{}`: {}", input_file, header.summary()));
+        if let Some(bext) = &header.bext {
+            let summary = bext.summary(header.fmt.samples_per_sec);
+            output::message(format!("  bext: {}", summary));
+        }
+        if let Some(database) = &database {
+            let checksum = header_checksum(&header, &mut reader, &input_file)?;
+            match database.identify(&checksum, header.sample_count()) {
+                Some(known) => {
+                    let summary = known.summary(header.cue.as_deref());
+                    output::message(format!("  known: {}", summary));
+                }
+                None => output::message(format!("  unknown: {}", checksum)),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_database(path: &Utf8Path) -> Result<database::Database> {
+    tracing::trace!("reading database file `{}`", path);
+    let contents =
+        std::fs::read(path).wrap_err_with(|| format!("Error reading database file `{}`", path))?;
+    database::Database::read(&contents)
+        .wrap_err_with(|| format!("Error parsing database file `{}`", path))
+}
+
+/// Compute the checksum of the audio data of the wave file header.
+fn header_checksum(
+    header: &zcue::WaveHeader,
+    reader: &mut BufReader<std::fs::File>,
+    path: &Utf8Path,
+) -> Result<String> {
+    header
+        .data_checksum(reader)
+        .wrap_err_with(|| format!("Error reading input file `{}`", path))
+}
+
+fn catalog(args: CatalogArgs) -> Result<()> {
+    let mut database = if args.database_file.exists() {
+        let database = read_database(&args.database_file)?;
+        output::read(&args.database_file);
+        database
+    } else {
+        database::Database::default()
+    };
+
+    for input_file in wave_files(&args.input_files)? {
+        let (header, mut reader) = read_header(&input_file)?;
+        let checksum = header_checksum(&header, &mut reader, &input_file)?;
+        let name = input_file.file_name().unwrap_or_default().to_string();
+        let replaced = database.insert(database::KnownFile {
+            checksum,
+            sample_count: header.sample_count(),
+            game: args.game,
+            archive: args.archive.clone(),
+            name,
+            cue: header.cue.unwrap_or_default(),
+        });
+        let status = if replaced { "Replaced" } else { "Added" };
+        output::result(&input_file, status, None);
+    }
+
+    tracing::trace!("writing database file `{}`", args.database_file);
+    std::fs::write(&args.database_file, database.write()?)
+        .wrap_err_with(|| format!("Error writing database file `{}`", args.database_file))?;
+
+    output::wrote(&args.database_file);
+    Ok(())
+}
+
+fn identify<'a>(
+    database: &'a database::Database,
+    checksum: &str,
+    sample_count: u64,
+    path: &Utf8Path,
+) -> Result<&'a database::KnownFile> {
+    let known = database
+        .identify(checksum, sample_count)
+        .ok_or_else(|| eyre!("`{}` is not a known file", path))?;
+    output::message(format!("Identified `{}` as {}", path, known));
+    Ok(known)
+}
+
+fn restore(args: RestoreArgs) -> Result<()> {
+    check_stdio(&[&args.input_file], args.output_file.as_deref().as_slice())?;
+    let database = read_database(&args.database_file)?;
+
+    output::read(&args.database_file);
+
+    let Some(output_file) = args.output_file.clone() else {
+        if is_stdio(&args.input_file) {
+            bail!("Standard input can't be updated in place, specify an output file");
+        }
+        let (mut header, mut reader) = read_header(&args.input_file)?;
+        let checksum = header_checksum(&header, &mut reader, &args.input_file)?;
+        drop(reader);
+        let known = identify(
+            &database,
+            &checksum,
+            header.sample_count(),
+            &args.input_file,
+        )?;
+        header.cue = Some(known.cue.clone());
+        return update_file(&args.input_file, &header, args.cue_position, args.backup);
+    };
+
+    let mut wav = read_wave_file(&args.input_file)?;
+
+    output::read(&args.input_file);
+
+    let checksum = zcue::data_checksum(&wav.data);
+    let known = identify(&database, &checksum, wav.sample_count(), &args.input_file)?;
+    wav.cue = Some(known.cue.clone());
+
+    write_wave_file(&output_file, &wav, args.cue_position)?;
+
+    output::wrote(&output_file);
+    Ok(())
+}
+
+fn validate(args: ValidateArgs) -> Result<()> {
+    let input_files = wave_files(&args.input_files)?;
+    let mut failed = 0usize;
+    for input_file in &input_files {
+        let result = read_header_with(input_file, args.profile)
+            .and_then(|(header, _)| Ok(args.profile.validate_header(&header)?));
+        match result {
+            Ok(()) => output::result(input_file, "OK", None),
+            Err(e) => {
+                failed += 1;
+                output::result(input_file, "FAILED", Some(&e));
+            }
+        }
+    }
+    if failed > 0 {
+        bail!(
+            "{} of {} file(s) failed validation",
+            failed,
+            input_files.len()
+        );
+    }
+    Ok(())
+}
+
+fn roundtrip(args: RoundtripArgs) -> Result<()> {
+    let input_files = wave_files(&args.input_files)?;
+    let mut failed = 0usize;
+    for input_file in &input_files {
+        let result = std::fs::read(input_file)
+            .wrap_err("Error reading file")
+            .and_then(|buf| Ok(zcue::roundtrip(&buf)?));
+        match result {
+            Ok(None) => output::result(input_file, "OK", None),
+            Ok(Some(mismatch)) => {
+                failed += 1;
+                let err = eyre!(
+                    "Differs at offset {} in chunk `{}` ({} bytes read, {} bytes written)",
+                    mismatch.offset,
+                    mismatch.chunk_id,
+                    mismatch.original_size,
+                    mismatch.written_size,
+                );
+                output::result(input_file, "FAILED", Some(&err));
+            }
+            Err(e) => {
+                failed += 1;
+                output::result(input_file, "FAILED", Some(&e));
+            }
+        }
+    }
+    if failed > 0 {
+        bail!(
+            "{} of {} file(s) failed the round trip",
+            failed,
+            input_files.len()
+        );
+    }
+    Ok(())
+}
+
+fn build(args: BuildArgs) -> Result<()> {
+    tracing::trace!("reading manifest file `{}`", args.manifest_file);
+    let contents = std::fs::read(&args.manifest_file)
+        .wrap_err_with(|| format!("Error reading manifest file `{}`", args.manifest_file))?;
+    let manifest = manifest::read(&contents)
+        .wrap_err_with(|| format!("Error parsing manifest file `{}`", args.manifest_file))?;
+
+    output::read(&args.manifest_file);
+
+    let base_dir = args.manifest_file.parent().unwrap_or(Utf8Path::new(""));
+    let output_dir = base_dir.join(&manifest.output_dir);
+    let state_file = output_dir.join(manifest::STATE_FILE_NAME);
+    let state = match std::fs::read(&state_file) {
+        Ok(contents) if !args.force => manifest::BuildState::read(&contents),
+        _ => manifest::BuildState::default(),
+    };
+
+    let mut next_state = manifest::BuildState::default();
+    let mut report = manifest::Report::default();
+    for entry in &manifest.entries {
+        let result =
+            manifest::build_entry(entry, base_dir, &output_dir, &state).map_err(Report::from);
+        match &result {
+            Ok((manifest::Status::Built, _)) => output::result(&entry.target, "Built", None),
+            Ok(_) => output::result(&entry.target, "Up to date", None),
+            Err(e) => output::result(&entry.target, "FAILED", Some(e)),
+        }
+        // the whole chain, since the root cause doesn't name the file
+        let result = result.map_err(|e| {
+            let causes: Vec<String> = e.chain().map(ToString::to_string).collect();
+            causes.join(": ")
+        });
+        if let Ok((_, output)) = &result {
+            next_state
+                .outputs
+                .insert(entry.target.clone(), output.clone());
+        }
+        report
+            .entries
+            .push(manifest::ReportEntry::new(entry, result));
+    }
+
+    tracing::trace!("writing build state `{}`", state_file);
+    std::fs::create_dir_all(&output_dir)
+        .wrap_err_with(|| format!("Error creating directory `{}`", output_dir))?;
+    std::fs::write(&state_file, next_state.write()?)
+        .wrap_err_with(|| format!("Error writing build state `{}`", state_file))?;
+
+    if let Some(report_file) = &args.report {
+        tracing::trace!("writing report file `{}`", report_file);
+        std::fs::write(report_file, report.write()?)
+            .wrap_err_with(|| format!("Error writing report file `{}`", report_file))?;
+        output::wrote(report_file);
+    }
+
+    let failed = report.count(manifest::Status::Failed);
+    if failed > 0 {
+        bail!(
+            "{} of {} entries failed to build",
+            failed,
+            report.entries.len()
+        );
+    }
+    output::message(format!(
+        "Built {} and kept {} up-to-date of {} entries",
+        report.count(manifest::Status::Built),
+        report.count(manifest::Status::UpToDate),
+        report.entries.len()
+    ));
+    Ok(())
+}
+
+fn subtitles_export(args: SubtitlesExportArgs) -> Result<()> {
+    let subtitle_format = match args.subtitle_format {
+        Some(subtitle_format) => subtitle_format,
+        None => args
+            .output_file
+            .extension()
+            .and_then(subtitles::SubtitleFormat::from_extension)
+            .ok_or_else(|| {
+                eyre!(
+                    "Cannot determine subtitle format of `{}`, specify `--subtitle-format`",
+                    args.output_file
+                )
+            })?,
+    };
+
+    let is_wave = is_converted(&args.input_file)
+        || args
+            .input_file
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"));
+
+    tracing::trace!("reading input file `{}`", args.input_file);
+    let buf = std::fs::read(&args.input_file)
+        .wrap_err_with(|| format!("Error reading input file `{}`", args.input_file))?;
+    let (cue, duration) = if is_wave {
+        let wav = zcue::read(&buf)
+            .wrap_err_with(|| format!("Error parsing input file `{}`", args.input_file))?;
+        let cue = zcue::cue_from_wav(&wav)?;
+        (cue, f64::from(wav.duration()))
+    } else {
+        let format = cue_file::CueFormat::resolve(args.format, &args.input_file);
+        let samples_per_sec = args
+            .samples_per_sec
+            .map(|hz| {
+                zcue::SamplesPerSec::from_u32(hz)
+                    .ok_or_else(|| eyre!("Unsupported sample rate {} Hz", hz))
+            })
+            .transpose()?;
+        let cue = cue_file::read(format, &buf, samples_per_sec)
+            .wrap_err_with(|| format!("Error parsing cue file `{}`", args.input_file))?;
+        let Some(duration) = args.duration else {
+            bail!("The audio duration is required for cue files, specify `--duration`");
+        };
+        (cue, duration)
+    };
+
+    output::read(&args.input_file);
+
+    tracing::trace!("reading lines file `{}`", args.lines_file);
+    let contents = std::fs::read(&args.lines_file)
+        .wrap_err_with(|| format!("Error reading lines file `{}`", args.lines_file))?;
+    let lines = subtitles::read_lines(&contents)
+        .wrap_err_with(|| format!("Error parsing lines file `{}`", args.lines_file))?;
+
+    output::read(&args.lines_file);
+
+    let subs = subtitles::from_cue(&cue, lines, duration)?;
+
+    tracing::trace!("writing subtitle file `{}`", args.output_file);
+    let contents = subtitles::write(subtitle_format, &subs);
+    std::fs::write(&args.output_file, contents)
+        .wrap_err_with(|| format!("Error writing subtitle file `{}`", args.output_file))?;
+
+    output::wrote(&args.output_file);
+    Ok(())
+}
+
+fn subtitles_import(args: SubtitlesImportArgs) -> Result<()> {
+    check_stdio(&[], &[&args.cue_file])?;
+    tracing::trace!("reading subtitle file `{}`", args.subtitle_file);
+    let contents = std::fs::read(&args.subtitle_file)
+        .wrap_err_with(|| format!("Error reading subtitle file `{}`", args.subtitle_file))?;
+    let subs = subtitles::read(&contents)
+        .wrap_err_with(|| format!("Error parsing subtitle file `{}`", args.subtitle_file))?;
+
+    output::read(&args.subtitle_file);
+
+    let cue = subtitles::to_cue(&subs);
+
+    tracing::trace!("writing cue file `{}`", args.cue_file);
+    let format = cue_file::CueFormat::resolve(args.format, &args.cue_file);
+    let contents = cue_file::write(format, &cue)?;
+    write_file(&args.cue_file, &contents)
+        .wrap_err_with(|| format!("Error writing cue file `{}`", args.cue_file))?;
+
+    output::wrote(&args.cue_file);
+
+    if let Some(lines_file) = &args.lines {
+        tracing::trace!("writing lines file `{}`", lines_file);
+        let mut contents = String::new();
+        for sub in &subs {
+            contents.push_str(&sub.text);
+            contents.push('\n');
+        }
+        std::fs::write(lines_file, contents)
+            .wrap_err_with(|| format!("Error writing lines file `{}`", lines_file))?;
+
+        output::wrote(lines_file);
+    }
+    Ok(())
+}
+
+fn render(args: RenderArgs) -> Result<()> {
+    let image_format = match args.image_format {
+        Some(image_format) => image_format,
+        None => args
+            .output_file
+            .extension()
+            .and_then(render::ImageFormat::from_extension)
+            .ok_or_else(|| {
+                eyre!(
+                    "Cannot determine image format of `{}`, specify `--image-format`",
+                    args.output_file
+                )
+            })?,
+    };
+
+    let mut inputs = vec![args.input_file.as_path()];
+    inputs.extend(args.cue_file.as_deref());
+    check_stdio(&inputs, &[])?;
+
+    let wav = read_wave_file(&args.input_file)?;
+
+    output::read(&args.input_file);
+
+    let cue = match &args.cue_file {
+        Some(cue_file) => Some(read_cue_file(
+            cue_file,
+            args.format,
+            wav.fmt.samples_per_sec,
+        )?),
+        None if wav.cue.is_some() => Some(zcue::cue_from_wav(&wav)?),
+        None => None,
+    };
+
+    let options = render::RenderOptions {
+        width: args.width,
+        height: args.height,
+        start: args.start,
+        end: args.end,
+    };
+    let contents = render::render(image_format, &wav, cue.as_ref(), &options)?;
+
+    tracing::trace!("writing image file `{}`", args.output_file);
+    std::fs::write(&args.output_file, contents)
+        .wrap_err_with(|| format!("Error writing image file `{}`", args.output_file))?;
+
+    output::wrote(&args.output_file);
+    Ok(())
+}
+
+fn edit(args: EditArgs) -> Result<()> {
+    if is_stdio(&args.input_file) || args.output_file.as_deref().is_some_and(is_stdio) {
+        bail!("The terminal editor can't read from standard input or write to standard output");
+    }
+    let output_file = match args.output_file {
+        Some(output_file) => output_file,
+        None if is_converted(&args.input_file) && !is_flac(&args.input_file) => {
+            bail!("AIFF files can't be saved in place, specify an output file")
+        }
+        None => args.input_file.clone(),
+    };
+
+    let mut wav = read_wave_file(&args.input_file)?;
+
+    output::read(&args.input_file);
+
+    let (columns, _) = crossterm::terminal::size().unwrap_or((80, 24));
+    let mut editor = edit::Editor::new(&wav, columns)?;
+    let mut saved = false;
+    edit::terminal::run(&mut editor, args.input_file.as_str(), |editor| {
+        editor.apply_to(&mut wav)?;
+        write_wave_file(&output_file, &wav, args.cue_position)?;
+        saved = true;
+        Ok(format!("Wrote `{}`", output_file))
+    })?;
+
+    if saved {
+        output::wrote(&output_file);
+    }
+    Ok(())
+}
+
+fn schema(args: SchemaArgs) -> Result<()> {
+    let mut contents = serde_json::to_vec_pretty(&cue_file::schema()).wrap_err("Internal error")?;
+    contents.push(b'\n');
+
+    match &args.output_file {
+        Some(output_file) => {
+            tracing::trace!("writing schema file `{}`", output_file);
+            std::fs::write(output_file, contents)
+                .wrap_err_with(|| format!("Error writing schema file `{}`", output_file))?;
+            output::wrote(output_file);
+        }
+        None => {
+            use std::io::Write as _;
+            output::raw();
+            std::io::stdout()
+                .write_all(&contents)
+                .wrap_err("Error writing schema")?;
+        }
+    }
     Ok(())
 }
 
 fn setup_logging() -> Result<()> {
-    let env = tracing_subscriber::EnvFilter::from_env("RUST_LOG");
+    use tracing_subscriber::layer::SubscriberExt as _;
+    use tracing_subscriber::util::SubscriberInitExt as _;
+    use tracing_subscriber::Layer as _;
 
-    tracing_subscriber::fmt()
+    let env = tracing_subscriber::EnvFilter::from_env("RUST_LOG");
+    let fmt = tracing_subscriber::fmt::layer()
         .with_ansi(std::io::IsTerminal::is_terminal(&std::io::stderr()))
         .with_writer(std::io::stderr)
-        .with_env_filter(env)
+        .with_filter(env);
+    // warnings are collected for JSON output, regardless of `RUST_LOG`
+    let warnings = output::WarningLayer.with_filter(tracing_subscriber::filter::LevelFilter::WARN);
+
+    tracing_subscriber::registry()
+        .with(fmt)
+        .with(warnings)
         .try_init()
         .map_err(|e| eyre!(e))
 }
@@ -71,10 +791,43 @@ fn main() -> Result<()> {
     color_eyre::install()?;
     setup_logging()?;
 
-    use clap::Parser as _;
-    let args = MainArgs::parse();
-    match args.command {
+    use clap::{CommandFactory as _, Parser as _};
+    let args = match MainArgs::try_parse() {
+        Ok(args) => args,
+        Err(err) => {
+            // the arguments couldn't be parsed, so look for the output
+            // format and the command in them
+            let raw: Vec<String> = std::env::args().skip(1).collect();
+            let json = raw.iter().any(|arg| arg == "--output=json")
+                || raw
+                    .windows(2)
+                    .any(|pair| pair[0] == "--output" && pair[1] == "json");
+            let main_command = MainArgs::command();
+            let command = raw
+                .iter()
+                .find(|arg| main_command.find_subcommand(arg.as_str()).is_some())
+                .map_or("", String::as_str);
+            output::usage_error(err, json, command);
+        }
+    };
+    output::init(args.output, args.quiet, args.command.name());
+    let result = match args.command {
         Commands::Apply(args) => apply(args),
+        Commands::Convert(args) => convert(args),
         Commands::Extract(args) => extract(args),
-    }
+        Commands::Subtitles(args) => match args.command {
+            SubtitlesCommands::Export(args) => subtitles_export(args),
+            SubtitlesCommands::Import(args) => subtitles_import(args),
+        },
+        Commands::Info(args) => info(args),
+        Commands::Validate(args) => validate(args),
+        Commands::Roundtrip(args) => roundtrip(args),
+        Commands::Build(args) => build(args),
+        Commands::Catalog(args) => catalog(args),
+        Commands::Restore(args) => restore(args),
+        Commands::Render(args) => render(args),
+        Commands::Edit(args) => edit(args),
+        Commands::Schema(args) => schema(args),
+    };
+    output::finish(result)
 }
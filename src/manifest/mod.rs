@@ -0,0 +1,381 @@
+//! Project manifests, which describe building a whole sound mod.
+//!
+//! A manifest is a TOML file with an `[[entry]]` table per output, listing
+//! the source file, the cue file to apply, an optional conversion, and the
+//! archive entry to write:
+//!
+//! ```toml
+//! output_dir = "build"
+//!
+//! [[entry]]
+//! target = "soundsH/br340000.wav"
+//! source = "masters/br340000.flac"
+//! cue = "cues/br340000.json"
+//! codec = "ima-adpcm"
+//! ```
+//!
+//! The build state records a fingerprint of the inputs of every output, so
+//! [`build_entry`] only builds entries whose inputs changed again.
+use crate::checksum::Crc32;
+use crate::cue_file::CueFormat;
+use crate::error::bail;
+use crate::wave::{
+    apply_cue, apply_options, convert, data_checksum, format_checksum, write_flac, write_with,
+    ApplyOptions, Codec, CuePosition, WaveFile,
+};
+use crate::Result;
+use camino::{Utf8Path, Utf8PathBuf};
+use eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// The file name of the build state, in the output directory.
+pub const STATE_FILE_NAME: &str = ".zcue-build.json";
+/// The current version of the build state.
+///
+/// A build state with a different version is ignored, so everything is
+/// built again.
+pub const STATE_VERSION: u32 = 1;
+
+fn default_output_dir() -> Utf8PathBuf {
+    Utf8PathBuf::from("build")
+}
+
+/// A project manifest.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// The directory to write the outputs and build state to, relative to
+    /// the manifest
+    #[serde(default = "default_output_dir")]
+    pub output_dir: Utf8PathBuf,
+    /// The outputs to build, in order
+    #[serde(default, rename = "entry")]
+    pub entries: Vec<Entry>,
+}
+
+/// An output of a manifest, and how to build it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+    /// The archive entry to write, relative to the output directory (e.g.
+    /// `soundsH/br340000.wav`). A `.flac` extension encodes it as FLAC
+    pub target: Utf8PathBuf,
+    /// The wave, AIFF, or FLAC file to read, relative to the manifest
+    pub source: Utf8PathBuf,
+    /// The cue file to apply, relative to the manifest. If missing, the cue
+    /// points of the source file are kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cue: Option<Utf8PathBuf>,
+    /// The format of the cue file [default: from the file extension]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<CueFormat>,
+    /// The codec to convert the audio data to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codec: Option<Codec>,
+    /// Where to write the cue chunk, relative to the data chunk
+    #[serde(default)]
+    pub cue_position: CuePosition,
+    /// Replace the sample loops with loops between pairs of cue points
+    #[serde(default)]
+    pub loops_from_cues: bool,
+    /// Remove the `INFO` metadata, instead of keeping it
+    #[serde(default)]
+    pub strip_info: bool,
+    /// The timestamps are the time of day, so offset them by the `bext`
+    /// time reference
+    #[serde(default)]
+    pub bext_offset: bool,
+}
+
+/// Read and validate a manifest.
+///
+/// The targets must be unique, and relative paths inside the output
+/// directory other than the build state.
+pub fn read(buf: &[u8]) -> Result<Manifest> {
+    let contents = std::str::from_utf8(buf).wrap_err("TOML file is not valid UTF-8")?;
+    let manifest: Manifest = toml::from_str(contents).map_err(eyre::Report::new)?;
+
+    let mut targets = HashSet::new();
+    for entry in &manifest.entries {
+        let is_inside = entry.target.components().count() > 0
+            && entry
+                .target
+                .components()
+                .all(|component| matches!(component, camino::Utf8Component::Normal(_)));
+        if !is_inside {
+            bail!(
+                "Target `{}` is not a relative path inside the output directory",
+                entry.target
+            );
+        }
+        // case-insensitive, as on Windows and macOS
+        if entry.target.as_str().eq_ignore_ascii_case(STATE_FILE_NAME) {
+            bail!(
+                "Target `{}` is the build state file of the output directory",
+                entry.target
+            );
+        }
+        if !targets.insert(entry.target.as_path()) {
+            bail!("Target `{}` is listed more than once", entry.target);
+        }
+    }
+    Ok(manifest)
+}
+
+/// The fingerprint of the inputs of an entry: the settings, the contents of
+/// the source and cue files, and the zcue version.
+///
+/// The sizes of the source and cue files follow the CRC-32, so a change
+/// that collides with the CRC-32 must also keep the sizes, e.g.
+/// `crc32:cbf43926:1024:512`.
+pub fn fingerprint(entry: &Entry, source: &[u8], cue: Option<&[u8]>) -> Result<String> {
+    let settings = serde_json::to_vec(entry).wrap_err("Internal error")?;
+    let mut crc = Crc32::new();
+    crc.update(env!("CARGO_PKG_VERSION").as_bytes());
+    // the lengths keep the inputs apart
+    for input in [settings.as_slice(), source, cue.unwrap_or_default()] {
+        crc.update(&(input.len() as u64).to_le_bytes());
+        crc.update(input);
+    }
+    crc.update(&[u8::from(cue.is_some())]);
+    Ok(format!(
+        "{}:{}:{}",
+        format_checksum(crc.finish()),
+        source.len(),
+        cue.map_or(0, <[u8]>::len)
+    ))
+}
+
+/// A built output, as recorded in the build state.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Output {
+    /// The fingerprint of the inputs, see [`fingerprint`]
+    pub inputs: String,
+    /// The checksum of the output file, e.g. `crc32:cbf43926`
+    pub checksum: String,
+    /// The number of samples of the audio data
+    pub sample_count: u64,
+    /// The number of cue points
+    pub cue_count: usize,
+}
+
+/// The outputs of the last build, by target.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BuildState {
+    /// The version of the build state, see [`STATE_VERSION`]
+    pub version: u32,
+    /// The outputs, by target
+    pub outputs: BTreeMap<Utf8PathBuf, Output>,
+}
+
+impl Default for BuildState {
+    fn default() -> Self {
+        Self {
+            version: STATE_VERSION,
+            outputs: BTreeMap::new(),
+        }
+    }
+}
+
+impl BuildState {
+    /// Read the build state. An unreadable build state or an older version
+    /// is ignored, since everything can be built again.
+    pub fn read(buf: &[u8]) -> Self {
+        match serde_json::from_slice::<Self>(buf) {
+            Ok(state) if state.version == STATE_VERSION => state,
+            Ok(state) => {
+                tracing::warn!(
+                    "Build state version {} is not the supported version {}, ignoring it...",
+                    state.version,
+                    STATE_VERSION
+                );
+                Self::default()
+            }
+            Err(e) => {
+                tracing::warn!("Build state is invalid ({}), ignoring it...", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Write the build state.
+    pub fn write(&self) -> Result<Vec<u8>> {
+        let mut contents = serde_json::to_vec_pretty(self).wrap_err("Internal error")?;
+        contents.push(b'\n');
+        Ok(contents)
+    }
+
+    /// The output of the last build of the target, if the inputs are the
+    /// same and the output file is unchanged.
+    pub fn up_to_date(&self, target: &Utf8Path, inputs: &str, output: &[u8]) -> Option<&Output> {
+        self.outputs.get(target).filter(|previous| {
+            previous.inputs == inputs && previous.checksum == data_checksum(output)
+        })
+    }
+}
+
+/// Write the wave file, or encode it as FLAC if the target has a `.flac`
+/// extension.
+fn encode(target: &Utf8Path, wav: &WaveFile, cue_position: CuePosition) -> Result<Vec<u8>> {
+    let is_flac = target
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("flac"));
+    let contents = if is_flac {
+        write_flac(wav)
+    } else {
+        write_with(wav, cue_position)
+    };
+    Ok(contents.wrap_err("Error constructing output file")?)
+}
+
+/// Build an entry of a manifest, unless the inputs and output are unchanged
+/// since the build recorded in the build state.
+///
+/// The source and cue files are relative to the base directory, i.e. the
+/// directory of the manifest, and the target to the output directory.
+pub fn build_entry(
+    entry: &Entry,
+    base_dir: &Utf8Path,
+    output_dir: &Utf8Path,
+    state: &BuildState,
+) -> Result<(Status, Output)> {
+    let source_file = base_dir.join(&entry.source);
+    tracing::trace!("reading source file `{}`", source_file);
+    let source = std::fs::read(&source_file)
+        .wrap_err_with(|| format!("Error reading source file `{}`", source_file))?;
+    let cue_file = entry.cue.as_ref().map(|cue_file| base_dir.join(cue_file));
+    let cue = match &cue_file {
+        Some(cue_file) => {
+            tracing::trace!("reading cue file `{}`", cue_file);
+            let contents = std::fs::read(cue_file)
+                .wrap_err_with(|| format!("Error reading cue file `{}`", cue_file))?;
+            Some(contents)
+        }
+        None => None,
+    };
+
+    let inputs = fingerprint(entry, &source, cue.as_deref())?;
+    let target_file = output_dir.join(&entry.target);
+    // the output may have been changed or removed since the last build
+    if let Ok(output) = std::fs::read(&target_file) {
+        if let Some(previous) = state.up_to_date(&entry.target, &inputs, &output) {
+            return Ok((Status::UpToDate, previous.clone()));
+        }
+    }
+
+    let mut wav = crate::wave::read(&source)
+        .wrap_err_with(|| format!("Error parsing source file `{}`", source_file))?;
+    let options = ApplyOptions {
+        bext_offset: entry.bext_offset,
+        loops_from_cues: entry.loops_from_cues,
+        strip_info: entry.strip_info,
+    };
+    match (&cue_file, &cue) {
+        (Some(cue_file), Some(contents)) => {
+            let format = CueFormat::resolve(entry.format, cue_file);
+            let cue = crate::cue_file::read(format, contents, Some(wav.fmt.samples_per_sec))
+                .wrap_err_with(|| format!("Error parsing cue file `{}`", cue_file))?;
+            apply_cue(&mut wav, cue, options)?;
+        }
+        // the cue points of the source file are kept
+        _ => apply_options(&mut wav, options),
+    }
+    if let Some(codec) = entry.codec {
+        wav = convert(&wav, codec)?;
+    }
+
+    tracing::trace!("writing output file `{}`", target_file);
+    let contents = encode(&target_file, &wav, entry.cue_position)?;
+    if let Some(parent) = target_file.parent() {
+        std::fs::create_dir_all(parent)
+            .wrap_err_with(|| format!("Error creating directory `{}`", parent))?;
+    }
+    std::fs::write(&target_file, &contents)
+        .wrap_err_with(|| format!("Error writing output file `{}`", target_file))?;
+
+    let output = Output {
+        inputs,
+        checksum: data_checksum(&contents),
+        sample_count: wav.sample_count(),
+        cue_count: wav.cue.as_ref().map_or(0, Vec::len),
+    };
+    Ok((Status::Built, output))
+}
+
+/// What happened to an entry during a build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    /// The inputs changed, so the output was written
+    Built,
+    /// The inputs and output were unchanged, so the output was kept
+    UpToDate,
+    /// The output couldn't be built
+    Failed,
+}
+
+/// An entry of the build report.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReportEntry {
+    /// The archive entry, relative to the output directory
+    pub target: Utf8PathBuf,
+    /// The source file, relative to the manifest
+    pub source: Utf8PathBuf,
+    /// The cue file, relative to the manifest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cue: Option<Utf8PathBuf>,
+    /// What happened to the entry
+    pub status: Status,
+    /// The output, unless the entry failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Output>,
+    /// The error, if the entry failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ReportEntry {
+    /// Report the entry with a status and output, or an error.
+    pub fn new(entry: &Entry, result: std::result::Result<(Status, Output), String>) -> Self {
+        let (status, output, error) = match result {
+            Ok((status, output)) => (status, Some(output), None),
+            Err(error) => (Status::Failed, None, Some(error)),
+        };
+        Self {
+            target: entry.target.clone(),
+            source: entry.source.clone(),
+            cue: entry.cue.clone(),
+            status,
+            output,
+            error,
+        }
+    }
+}
+
+/// The report of a build, with every output of the manifest.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Report {
+    /// The entries, in the order of the manifest
+    pub entries: Vec<ReportEntry>,
+}
+
+impl Report {
+    /// The number of entries with the status.
+    pub fn count(&self, status: Status) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status == status)
+            .count()
+    }
+
+    /// Write the report as JSON.
+    pub fn write(&self) -> Result<Vec<u8>> {
+        let mut contents = serde_json::to_vec_pretty(self).wrap_err("Internal error")?;
+        contents.push(b'\n');
+        Ok(contents)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,220 @@
+use super::*;
+
+const MANIFEST: &str = r#"
+[[entry]]
+target = "soundsH/br340000.wav"
+source = "masters/br340000.flac"
+cue = "cues/br340000.txt"
+format = "audacity"
+codec = "ima-adpcm"
+cue_position = "after-data"
+loops_from_cues = true
+
+[[entry]]
+target = "soundsH/br340001.wav"
+source = "masters/br340001.wav"
+"#;
+
+fn entry() -> Entry {
+    read(MANIFEST.as_bytes()).unwrap().entries.remove(1)
+}
+
+#[test]
+fn read_manifest() {
+    let manifest = read(MANIFEST.as_bytes()).unwrap();
+    assert_eq!(manifest.output_dir, "build");
+    assert_eq!(manifest.entries.len(), 2);
+
+    let entry = &manifest.entries[0];
+    assert_eq!(entry.target, "soundsH/br340000.wav");
+    assert_eq!(entry.source, "masters/br340000.flac");
+    assert_eq!(
+        entry.cue.as_deref(),
+        Some(Utf8Path::new("cues/br340000.txt"))
+    );
+    assert_eq!(entry.format, Some(CueFormat::Audacity));
+    assert_eq!(entry.codec, Some(Codec::ImaAdpcm));
+    assert_eq!(entry.cue_position, CuePosition::AfterData);
+    assert!(entry.loops_from_cues);
+    assert!(!entry.strip_info);
+
+    let entry = &manifest.entries[1];
+    assert_eq!(entry.cue, None);
+    assert_eq!(entry.codec, None);
+    assert_eq!(entry.cue_position, CuePosition::Original);
+}
+
+#[test]
+fn read_unknown_field() {
+    let contents = "[[entry]]\ntarget = \"a.wav\"\nsource = \"b.wav\"\ncue_file = \"c.json\"\n";
+    assert!(read(contents.as_bytes()).is_err());
+}
+
+#[test]
+fn read_duplicate_target() {
+    let contents = "[[entry]]\ntarget = \"a.wav\"\nsource = \"b.wav\"\n\
+                    [[entry]]\ntarget = \"a.wav\"\nsource = \"c.wav\"\n";
+    let err = read(contents.as_bytes()).unwrap_err();
+    assert_eq!(err.to_string(), "Target `a.wav` is listed more than once");
+}
+
+#[test]
+fn read_target_outside_output_dir() {
+    for target in ["../a.wav", "/a.wav", "soundsH/../../a.wav", ""] {
+        let contents = format!("[[entry]]\ntarget = \"{}\"\nsource = \"b.wav\"\n", target);
+        let err = read(contents.as_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Target `{}` is not a relative path inside the output directory",
+                target
+            )
+        );
+    }
+}
+
+#[test]
+fn read_target_build_state() {
+    for target in [".zcue-build.json", ".ZCUE-Build.json"] {
+        let contents = format!("[[entry]]\ntarget = \"{}\"\nsource = \"b.wav\"\n", target);
+        let err = read(contents.as_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Target `{}` is the build state file of the output directory",
+                target
+            )
+        );
+    }
+    // only the build state in the output directory itself
+    let contents = "[[entry]]\ntarget = \"soundsH/.zcue-build.json\"\nsource = \"b.wav\"\n";
+    assert!(read(contents.as_bytes()).is_ok());
+}
+
+#[test]
+fn fingerprint_changes_with_inputs() {
+    let entry = entry();
+    let base = fingerprint(&entry, b"source", None).unwrap();
+    assert_eq!(base, fingerprint(&entry, b"source", None).unwrap());
+    assert!(base.starts_with("crc32:"));
+    assert!(base.ends_with(":6:0"));
+
+    assert_ne!(base, fingerprint(&entry, b"sourcf", None).unwrap());
+    assert_ne!(base, fingerprint(&entry, b"source", Some(b"")).unwrap());
+    // moving bytes between the source and cue file changes the fingerprint
+    assert_ne!(
+        fingerprint(&entry, b"sour", Some(b"ce")).unwrap(),
+        fingerprint(&entry, b"sou", Some(b"rce")).unwrap()
+    );
+    let changed = Entry {
+        strip_info: true,
+        ..entry.clone()
+    };
+    assert_ne!(base, fingerprint(&changed, b"source", None).unwrap());
+}
+
+#[test]
+fn build_state_up_to_date() {
+    let output = Output {
+        inputs: "crc32:00000001".to_string(),
+        checksum: crate::wave::data_checksum(b"output"),
+        sample_count: 10,
+        cue_count: 2,
+    };
+    let mut state = BuildState::default();
+    state.outputs.insert("a.wav".into(), output.clone());
+    let state = BuildState::read(&state.write().unwrap());
+
+    let target = Utf8Path::new("a.wav");
+    assert_eq!(
+        state.up_to_date(target, "crc32:00000001", b"output"),
+        Some(&output)
+    );
+    assert_eq!(state.up_to_date(target, "crc32:00000002", b"output"), None);
+    assert_eq!(state.up_to_date(target, "crc32:00000001", b"edited"), None);
+    assert_eq!(
+        state.up_to_date(Utf8Path::new("b.wav"), "crc32:00000001", b"output"),
+        None
+    );
+}
+
+#[test]
+fn build_state_invalid_or_old() {
+    assert!(BuildState::read(b"not json").outputs.is_empty());
+    let old = br#"{"version": 0, "outputs": {"a.wav": {"inputs": "", "checksum": "", "sample_count": 0, "cue_count": 0}}}"#;
+    assert!(BuildState::read(old).outputs.is_empty());
+}
+
+#[test]
+fn build_entry_then_up_to_date() {
+    use crate::wave::{BitsPerSample, Channels, Format, SamplesPerSec};
+    let dir = std::env::temp_dir().join(format!("zcue-build-{}", std::process::id()));
+    let dir = Utf8PathBuf::from_path_buf(dir).unwrap();
+    let output_dir = dir.join("build");
+    std::fs::create_dir_all(&dir).unwrap();
+    let fmt = Format {
+        channels: Channels::One,
+        samples_per_sec: SamplesPerSec::Hz22050,
+        bits_per_sample: BitsPerSample::Eight,
+    };
+    let source = crate::wave::write(&WaveFile::new(fmt, vec![128; 22050])).unwrap();
+    std::fs::write(dir.join("a.wav"), source).unwrap();
+    std::fs::write(dir.join("a.txt"), "0.5\t0.5\tstart\n").unwrap();
+
+    let manifest = read(
+        br#"
+        [[entry]]
+        target = "soundsH/a.wav"
+        source = "a.wav"
+        cue = "a.txt"
+        loops_from_cues = true
+        "#,
+    )
+    .unwrap();
+    let entry = &manifest.entries[0];
+    let (status, output) = build_entry(entry, &dir, &output_dir, &BuildState::default()).unwrap();
+    assert_eq!(status, Status::Built);
+    assert_eq!((output.sample_count, output.cue_count), (22050, 1));
+    let wav = crate::wave::read(&std::fs::read(output_dir.join("soundsH/a.wav")).unwrap()).unwrap();
+    assert_eq!(wav.cue, Some(vec![11025]));
+    assert!(wav.sampler.is_some());
+
+    let mut state = BuildState::default();
+    state.outputs.insert(entry.target.clone(), output.clone());
+    let (status, kept) = build_entry(entry, &dir, &output_dir, &state).unwrap();
+    assert_eq!((status, kept), (Status::UpToDate, output));
+
+    std::fs::remove_file(dir.join("a.txt")).unwrap();
+    let err = build_entry(entry, &dir, &output_dir, &state).unwrap_err();
+    assert!(
+        err.to_string().starts_with("Error reading cue file"),
+        "{}",
+        err
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn report_counts() {
+    let entry = entry();
+    let output = Output {
+        inputs: String::new(),
+        checksum: String::new(),
+        sample_count: 0,
+        cue_count: 0,
+    };
+    let report = Report {
+        entries: vec![
+            ReportEntry::new(&entry, Ok((Status::Built, output.clone()))),
+            ReportEntry::new(&entry, Ok((Status::UpToDate, output))),
+            ReportEntry::new(&entry, Err("Oops".to_string())),
+        ],
+    };
+    assert_eq!(report.count(Status::Built), 1);
+    assert_eq!(report.count(Status::UpToDate), 1);
+    assert_eq!(report.count(Status::Failed), 1);
+
+    let contents = String::from_utf8(report.write().unwrap()).unwrap();
+    assert!(contents.contains(r#""status": "up-to-date""#));
+    assert!(contents.contains(r#""error": "Oops""#));
+}
@@ -0,0 +1,304 @@
+//! The output of commands, as human-readable text or a JSON result object.
+//!
+//! Commands report the files they read and wrote, and the result of each
+//! file of batch commands, through this module instead of printing. In text
+//! mode, these are printed as they happen. In JSON mode, they are collected
+//! with the warnings and the error, if any, into one JSON object that is
+//! printed when the command finishes.
+use color_eyre::eyre::Report;
+use serde::Serialize;
+use std::fmt::{self, Display};
+use std::sync::{Mutex, MutexGuard};
+use tracing::field::{Field, Visit};
+
+/// How the output of commands is printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON result object
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Text,
+    /// Text mode, but only errors are printed
+    Quiet,
+    Json,
+}
+
+/// The result of a file of a batch command.
+#[derive(Debug, Serialize)]
+struct FileResult {
+    path: String,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorObject>,
+}
+
+/// The error of a command.
+#[derive(Debug, Serialize)]
+struct ErrorObject {
+    message: String,
+    /// The kind of wave file read error, `io`, `usage`, or `other`
+    kind: &'static str,
+    /// The offset of the wave file read error, if any
+    offset: Option<u64>,
+    /// The error and its causes, outermost first
+    causes: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ResultObject {
+    command: String,
+    success: bool,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    warnings: Vec<String>,
+    results: Vec<FileResult>,
+    messages: Vec<String>,
+    error: Option<ErrorObject>,
+}
+
+struct State {
+    mode: Mode,
+    /// Whether standard output is used for data, e.g. a wave file written
+    /// to `-`, so output is printed to standard error instead
+    stderr: bool,
+    /// Whether the command printed other output to standard output, so the
+    /// JSON result object is not printed
+    raw: bool,
+    result: ResultObject,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    mode: Mode::Text,
+    stderr: false,
+    raw: false,
+    result: ResultObject {
+        command: String::new(),
+        success: true,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        warnings: Vec::new(),
+        results: Vec::new(),
+        messages: Vec::new(),
+        error: None,
+    },
+});
+
+impl State {
+    fn print(&self, line: fmt::Arguments<'_>) {
+        if self.stderr {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+fn state() -> MutexGuard<'static, State> {
+    // a panic while printing doesn't leave the state inconsistent
+    STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Set how the output of the command is printed.
+pub(crate) fn init(format: OutputFormat, quiet: bool, command: &str) {
+    let mut state = state();
+    state.mode = match (format, quiet) {
+        (OutputFormat::Json, _) => Mode::Json,
+        (OutputFormat::Text, true) => Mode::Quiet,
+        (OutputFormat::Text, false) => Mode::Text,
+    };
+    state.result.command = command.to_string();
+}
+
+/// A file was read.
+pub(crate) fn read(path: impl Display) {
+    let path = path.to_string();
+    let mut state = state();
+    match state.mode {
+        Mode::Text => state.print(format_args!("Read `{}`", path)),
+        Mode::Quiet => {}
+        Mode::Json => state.result.inputs.push(path),
+    }
+}
+
+/// A file was written, e.g. `Wrote` or `Patched`.
+pub(crate) fn wrote_with(verb: &str, path: impl Display) {
+    let path = path.to_string();
+    let mut state = state();
+    match state.mode {
+        Mode::Text => state.print(format_args!("{} `{}`", verb, path)),
+        Mode::Quiet => {}
+        Mode::Json => state.result.outputs.push(path),
+    }
+}
+
+/// A file was written.
+pub(crate) fn wrote(path: impl Display) {
+    wrote_with("Wrote", path);
+}
+
+/// Any other information, e.g. about a wave file.
+pub(crate) fn message(text: String) {
+    let mut state = state();
+    match state.mode {
+        Mode::Text => state.print(format_args!("{}", text)),
+        Mode::Quiet => {}
+        Mode::Json => state.result.messages.push(text),
+    }
+}
+
+/// A warning, collected in JSON mode. In text mode, warnings are logged.
+fn warning(text: String) {
+    let mut state = state();
+    if state.mode == Mode::Json {
+        state.result.warnings.push(text);
+    }
+}
+
+/// Collects the messages of log events as warnings, e.g. from
+/// `cue_from_wav`.
+pub(crate) struct WarningLayer;
+
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for WarningLayer {
+    fn on_event(
+        &self,
+        event: &tracing::Event<'_>,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let mut visitor = MessageVisitor(String::new());
+        event.record(&mut visitor);
+        warning(visitor.0);
+    }
+}
+
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+/// The result of a file of a batch command, e.g. `OK`. Failures are
+/// printed even in quiet mode, with the error and its causes.
+pub(crate) fn result(path: impl Display, status: &str, error: Option<&Report>) {
+    let path = path.to_string();
+    let mut state = state();
+    match (state.mode, error) {
+        (Mode::Json, _) => state.result.results.push(FileResult {
+            path,
+            status: status.to_string(),
+            error: error.map(error_object),
+        }),
+        (Mode::Text | Mode::Quiet, Some(error)) => {
+            let causes: Vec<String> = error.chain().map(ToString::to_string).collect();
+            state.print(format_args!("{} `{}`: {}", status, path, causes.join(": ")))
+        }
+        (Mode::Text, None) => state.print(format_args!("{} `{}`", status, path)),
+        (Mode::Quiet, None) => {}
+    }
+}
+
+/// Standard output is used for data, e.g. a wave file written to `-`, so
+/// print to standard error instead.
+pub(crate) fn redirect() {
+    state().stderr = true;
+}
+
+/// The command printed other output to standard output, e.g. the JSON
+/// Schema, so the JSON result object is not printed.
+pub(crate) fn raw() {
+    state().raw = true;
+}
+
+/// The outermost cause of the type. A library error's own message isn't a
+/// separate cause in the chain, so it is searched, too.
+fn find_cause<E: std::error::Error + 'static>(err: &Report) -> Option<&E> {
+    err.chain().find_map(|cause| {
+        cause.downcast_ref::<E>().or_else(|| {
+            cause
+                .downcast_ref::<zcue::Error>()
+                .and_then(zcue::Error::find)
+        })
+    })
+}
+
+fn error_object(err: &Report) -> ErrorObject {
+    let read_error = find_cause::<zcue::ReadError>(err);
+    let kind = match read_error {
+        Some(read_error) => read_error.kind(),
+        None if err.downcast_ref::<clap::Error>().is_some() => "usage",
+        None if find_cause::<std::io::Error>(err).is_some() => "io",
+        None => "other",
+    };
+    ErrorObject {
+        message: err.to_string(),
+        kind,
+        offset: read_error.and_then(zcue::ReadError::offset),
+        causes: err.chain().map(ToString::to_string).collect(),
+    }
+}
+
+/// Finish the command. In JSON mode, the result object is printed and
+/// errors are reported in it, so the error is only returned in text mode.
+pub(crate) fn finish(result: color_eyre::Result<()>) -> color_eyre::Result<()> {
+    let mut state = state();
+    if state.mode != Mode::Json {
+        return result;
+    }
+    let failed = result.is_err();
+    if let Err(err) = &result {
+        state.result.success = false;
+        state.result.error = Some(error_object(err));
+    }
+    if !state.raw {
+        // Panic safety: the result object only contains strings and numbers
+        state.print(format_args!(
+            "{}",
+            serde_json::to_string(&state.result).unwrap()
+        ));
+    }
+    if failed {
+        drop(state);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Exit with an error parsing the command line. If JSON output was asked
+/// for, the error is reported in the result object like any other error,
+/// except that the help and version, which clap also exits with, are
+/// printed as-is.
+pub(crate) fn usage_error(err: clap::Error, json: bool, command: &str) -> ! {
+    if !json || !err.use_stderr() {
+        err.exit();
+    }
+    let code = err.exit_code();
+    let mut state = state();
+    state.result.command = command.to_string();
+    state.result.success = false;
+    let mut object = error_object(&Report::new(err));
+    // clap renders the error as a line of its usual output
+    let message = object.message.trim_end();
+    let message = message.strip_prefix("error: ").unwrap_or(message);
+    object.message = message.to_string();
+    object.causes = vec![object.message.clone()];
+    state.result.error = Some(object);
+    // Panic safety: the result object only contains strings and numbers
+    state.print(format_args!(
+        "{}",
+        serde_json::to_string(&state.result).unwrap()
+    ));
+    drop(state);
+    std::process::exit(code);
+}
+
+#[cfg(test)]
+mod tests;
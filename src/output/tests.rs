@@ -0,0 +1,78 @@
+use super::*;
+use color_eyre::eyre::{eyre, WrapErr as _};
+use zcue::{ChunkId, ReadError};
+
+#[test]
+fn error_object_of_read_error() {
+    let err = Err::<(), _>(ReadError::MissingChunk {
+        chunk_id: ChunkId::new(*b"fmt "),
+    })
+    .wrap_err("Error parsing input file `a.wav`")
+    .unwrap_err();
+    let object = error_object(&err);
+    assert_eq!(object.message, "Error parsing input file `a.wav`");
+    assert_eq!(object.kind, "missing-chunk");
+    assert_eq!(object.offset, None);
+    assert_eq!(object.causes.len(), 2);
+
+    let err = Report::new(ReadError::Truncated { offset: 12 });
+    let object = error_object(&err);
+    assert_eq!((object.kind, object.offset), ("truncated", Some(12)));
+}
+
+#[test]
+fn error_object_of_library_error() {
+    let err = zcue::Error::from(ReadError::Truncated { offset: 12 });
+    let err = Err::<(), _>(err)
+        .wrap_err("Error updating input file `a.wav`")
+        .unwrap_err();
+    let object = error_object(&err);
+    assert_eq!((object.kind, object.offset), ("truncated", Some(12)));
+    assert_eq!(object.causes.len(), 2);
+}
+
+#[test]
+fn error_object_of_other_errors() {
+    let err = Err::<(), _>(std::io::Error::from(std::io::ErrorKind::NotFound))
+        .wrap_err("Error reading input file `a.wav`")
+        .unwrap_err();
+    assert_eq!(error_object(&err).kind, "io");
+
+    let err = eyre!("`a.wav` is not a known file");
+    let object = error_object(&err);
+    assert_eq!((object.kind, object.offset), ("other", None));
+    assert_eq!(object.causes, vec!["`a.wav` is not a known file"]);
+}
+
+#[test]
+fn error_object_of_usage_error() {
+    use clap::Parser as _;
+    let err = crate::cli::MainArgs::try_parse_from(["zcue", "--no-such-option"]).unwrap_err();
+    let object = error_object(&Report::new(err));
+    assert_eq!((object.kind, object.offset), ("usage", None));
+    assert!(object.message.starts_with("error: unexpected argument"));
+}
+
+#[test]
+fn file_result_with_error_object() {
+    let err = Err::<(), _>(ReadError::Truncated { offset: 12 })
+        .wrap_err("Error parsing input file `a.wav`")
+        .unwrap_err();
+    let result = FileResult {
+        path: "a.wav".to_string(),
+        status: "FAILED".to_string(),
+        error: Some(error_object(&err)),
+    };
+    let json = serde_json::to_value(result).unwrap();
+    assert_eq!(json["error"]["kind"], "truncated");
+    assert_eq!(json["error"]["offset"], 12);
+    assert_eq!(json["error"]["causes"].as_array().unwrap().len(), 2);
+
+    let result = FileResult {
+        path: "b.wav".to_string(),
+        status: "OK".to_string(),
+        error: None,
+    };
+    let json = serde_json::to_value(result).unwrap();
+    assert!(json.get("error").is_none());
+}
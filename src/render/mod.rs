@@ -0,0 +1,261 @@
+//! Render the waveform of a wave file with its cue points as an image.
+//!
+//! Each cue point is drawn as a vertical line, labelled with its label (or
+//! number) and timestamp. A zoom range renders only part of the audio.
+//! Images are written as PNG or SVG.
+use crate::error::bail;
+use crate::wave::{convert, BitsPerSample, Codec, CuePoints, WaveFile};
+use eyre::Result;
+
+mod font;
+mod png;
+mod svg;
+
+/// The supported image formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ImageFormat {
+    /// Portable Network Graphics
+    Png,
+    /// Scalable Vector Graphics
+    Svg,
+}
+
+impl ImageFormat {
+    /// The format implied by a file extension, if any.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        if ext.eq_ignore_ascii_case("png") {
+            Some(Self::Png)
+        } else if ext.eq_ignore_ascii_case("svg") {
+            Some(Self::Svg)
+        } else {
+            None
+        }
+    }
+}
+
+/// The size of the image and the range of the audio to render.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderOptions {
+    /// The width of the image in pixels
+    pub width: u32,
+    /// The height of the image in pixels
+    pub height: u32,
+    /// The start of the range to render in seconds
+    pub start: f32,
+    /// The end of the range to render in seconds, or the end of the audio
+    pub end: Option<f32>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            width: 1200,
+            height: 300,
+            start: 0.0,
+            end: None,
+        }
+    }
+}
+
+/// The largest width or height of an image in pixels.
+pub const MAX_SIZE: u32 = 16384;
+
+/// The height of a line of text in pixels.
+const LINE_HEIGHT: u32 = font::HEIGHT + 3;
+/// The height of the band above the waveform with the cue point labels.
+const LABEL_HEIGHT: u32 = 2 * LINE_HEIGHT + 2;
+/// The smallest height of the waveform in pixels.
+const MIN_WAVEFORM_HEIGHT: u32 = 16;
+
+type Color = [u8; 3];
+
+const BACKGROUND: Color = [0xFF, 0xFF, 0xFF];
+const AXIS: Color = [0xCC, 0xCC, 0xCC];
+const WAVEFORM: Color = [0x1F, 0x77, 0xB4];
+const MARKER: Color = [0xD6, 0x27, 0x28];
+
+/// A shape in image coordinates, with the origin at the top left.
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    Rect {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        color: Color,
+    },
+    /// Text, with `y` the top of the glyphs
+    Text {
+        x: u32,
+        y: u32,
+        text: String,
+        color: Color,
+    },
+}
+
+/// The shapes of an image, drawn in order.
+#[derive(Debug, Clone)]
+struct Scene {
+    width: u32,
+    height: u32,
+    background: Color,
+    shapes: Vec<Shape>,
+}
+
+/// Decode the audio data to samples between -1 and 1, e.g. to draw the
+/// waveform.
+pub fn samples(wav: &WaveFile) -> crate::Result<Vec<f32>> {
+    let pcm = convert(wav, Codec::Pcm)?;
+    let samples = match pcm.fmt.bits_per_sample {
+        BitsPerSample::Eight => pcm
+            .data
+            .iter()
+            .map(|&b| (f32::from(b) - 128.0) / 128.0)
+            .collect(),
+        BitsPerSample::Sixteen => pcm
+            .data
+            .chunks_exact(2)
+            .map(|b| f32::from(i16::from_le_bytes([b[0], b[1]])) / 32768.0)
+            .collect(),
+    };
+    Ok(samples)
+}
+
+/// Format a timestamp for a marker label, e.g. `1.250s`.
+fn format_timestamp(timestamp: f32) -> String {
+    format!("{:.3}s", timestamp)
+}
+
+fn scene(wav: &WaveFile, cue: Option<&CuePoints>, options: &RenderOptions) -> Result<Scene> {
+    let RenderOptions {
+        width,
+        height,
+        start,
+        end,
+    } = *options;
+    if width == 0 || width > MAX_SIZE {
+        bail!("Image width must be between 1 and {}", MAX_SIZE);
+    }
+    let min_height = LABEL_HEIGHT + MIN_WAVEFORM_HEIGHT;
+    if height < min_height || height > MAX_SIZE {
+        bail!(
+            "Image height must be between {} and {}",
+            min_height,
+            MAX_SIZE
+        );
+    }
+
+    let duration = wav.duration();
+    let end = end.unwrap_or(duration).min(duration);
+    if !(start >= 0.0 && start < end) {
+        bail!(
+            "Zoom range {} to {} is empty or outside the audio ({} long)",
+            format_timestamp(start),
+            format_timestamp(end),
+            format_timestamp(duration)
+        );
+    }
+
+    let samples = samples(wav)?;
+    let samples_per_sec = wav.fmt.samples_per_sec.as_u32() as f32;
+    let seconds_per_pixel = (end - start) / width as f32;
+    let mut shapes = Vec::new();
+
+    let wave_top = LABEL_HEIGHT;
+    let wave_height = height - LABEL_HEIGHT;
+    let center = wave_top + wave_height / 2;
+    let amplitude = (wave_height / 2 - 1) as f32;
+    shapes.push(Shape::Rect {
+        x: 0,
+        y: center,
+        width,
+        height: 1,
+        color: AXIS,
+    });
+
+    for x in 0..width {
+        // Cast safety: the sample index is non-negative and saturates
+        let first = ((start + x as f32 * seconds_per_pixel) * samples_per_sec) as usize;
+        let last = ((start + (x + 1) as f32 * seconds_per_pixel) * samples_per_sec) as usize;
+        let Some(column) = samples
+            .get(first..last.max(first + 1).min(samples.len()))
+            .filter(|column| !column.is_empty())
+        else {
+            break;
+        };
+        let (min, max) = column.iter().fold((f32::MAX, f32::MIN), |(min, max), &s| {
+            (min.min(s), max.max(s))
+        });
+        // Cast safety: the offsets are within the waveform
+        let top = center - (max.max(0.0) * amplitude).round() as u32;
+        let bottom = center + (-min.min(0.0) * amplitude).round() as u32;
+        shapes.push(Shape::Rect {
+            x,
+            y: top,
+            width: 1,
+            height: bottom - top + 1,
+            color: WAVEFORM,
+        });
+    }
+
+    if let Some(cue) = cue {
+        let labels = cue.labels.as_deref().unwrap_or_default();
+        for (index, &timestamp) in cue.timestamps.iter().enumerate() {
+            // Cast safety: single precision is plenty to draw the marker
+            let timestamp = timestamp as f32;
+            if timestamp < start || timestamp > end {
+                continue;
+            }
+            // Cast safety: the timestamp is within the range
+            let x = (((timestamp - start) / seconds_per_pixel) as u32).min(width - 1);
+            shapes.push(Shape::Rect {
+                x,
+                y: 0,
+                width: 1,
+                height,
+                color: MARKER,
+            });
+            let label = match labels.get(index) {
+                Some(label) if !label.is_empty() => label.clone(),
+                _ => format!("#{}", index + 1),
+            };
+            for (text, line) in [label, format_timestamp(timestamp)].into_iter().zip(0..) {
+                shapes.push(Shape::Text {
+                    x: x + 2,
+                    y: 1 + line * LINE_HEIGHT,
+                    text,
+                    color: MARKER,
+                });
+            }
+        }
+    }
+
+    Ok(Scene {
+        width,
+        height,
+        background: BACKGROUND,
+        shapes,
+    })
+}
+
+/// Render the waveform of a wave file with cue points as an image.
+///
+/// The cue points are usually those of the wave file, but may be from a
+/// cue file, e.g. to review changes before applying them. Labels are drawn
+/// in upper case in PNG images.
+pub fn render(
+    format: ImageFormat,
+    wav: &WaveFile,
+    cue: Option<&CuePoints>,
+    options: &RenderOptions,
+) -> crate::Result<Vec<u8>> {
+    let scene = scene(wav, cue, options)?;
+    Ok(match format {
+        ImageFormat::Png => png::Image::rasterize(&scene).encode(),
+        ImageFormat::Svg => svg::write(&scene).into_bytes(),
+    })
+}
+
+#[cfg(test)]
+mod tests;
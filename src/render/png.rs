@@ -0,0 +1,166 @@
+//! A minimal PNG encoder.
+//!
+//! Images are 8-bit RGB without filtering, and compressed with stored
+//! (uncompressed) deflate blocks. The files are larger than they could be,
+//! but any PNG decoder reads them.
+use super::font;
+use super::{Color, Scene, Shape};
+use crate::checksum::Crc32;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+pub(crate) const COLOR_TYPE_RGB: u8 = 2;
+/// The maximum length of a stored deflate block.
+const MAX_STORED_LEN: usize = 0xFFFF;
+
+/// An RGB image.
+pub(crate) struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub(crate) fn new(width: u32, height: u32, background: Color) -> Self {
+        // Cast safety: the dimensions are checked by the caller
+        let pixels = background.repeat((width * height) as usize);
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// The color of a pixel.
+    #[cfg(test)]
+    pub(crate) fn pixel(&self, x: u32, y: u32) -> Color {
+        let i = ((y * self.width + x) * 3) as usize;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]]
+    }
+
+    fn set_pixel(&mut self, x: u32, y: u32, color: Color) {
+        if x < self.width && y < self.height {
+            // Cast safety: the pixel is within the image
+            let i = ((y * self.width + x) * 3) as usize;
+            self.pixels[i..i + 3].copy_from_slice(&color);
+        }
+    }
+
+    fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: Color) {
+        let x_end = x.saturating_add(width).min(self.width);
+        let y_end = y.saturating_add(height).min(self.height);
+        for y in y..y_end {
+            for x in x..x_end {
+                self.set_pixel(x, y, color);
+            }
+        }
+    }
+
+    fn draw_text(&mut self, x: u32, y: u32, text: &str, color: Color) {
+        for (c, i) in text.chars().zip(0u32..) {
+            let left = x.saturating_add(i.saturating_mul(font::ADVANCE));
+            if left >= self.width {
+                break;
+            }
+            for (row, dy) in font::glyph(c).into_iter().zip(0..) {
+                for dx in 0..font::WIDTH {
+                    if row & (0x10 >> dx) != 0 {
+                        self.set_pixel(left + dx, y + dy, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draw the shapes of a scene.
+    pub(crate) fn rasterize(scene: &Scene) -> Self {
+        let mut image = Self::new(scene.width, scene.height, scene.background);
+        for shape in &scene.shapes {
+            match shape {
+                Shape::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } => image.fill_rect(*x, *y, *width, *height, *color),
+                Shape::Text { x, y, text, color } => image.draw_text(*x, *y, text, *color),
+            }
+        }
+        image
+    }
+
+    /// Encode the image as a PNG file.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend(self.width.to_be_bytes());
+        ihdr.extend(self.height.to_be_bytes());
+        // bit depth, color type, compression, filter, and interlace method
+        ihdr.extend([8, COLOR_TYPE_RGB, 0, 0, 0]);
+
+        // Cast safety: the dimensions are checked by the caller
+        let stride = self.width as usize * 3;
+        let mut raw = Vec::with_capacity((stride + 1) * self.height as usize);
+        for row in self.pixels.chunks_exact(stride) {
+            // filter type none
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+
+        let mut buf = Vec::new();
+        buf.extend(SIGNATURE);
+        write_chunk(&mut buf, b"IHDR", &ihdr);
+        write_chunk(&mut buf, b"IDAT", &zlib_stored(&raw));
+        write_chunk(&mut buf, b"IEND", &[]);
+        buf
+    }
+}
+
+fn write_chunk(buf: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    // Cast safety: chunks are far smaller than 2 GiB
+    buf.extend((data.len() as u32).to_be_bytes());
+    buf.extend(chunk_type);
+    buf.extend(data);
+    let mut crc = Crc32::new();
+    crc.update(chunk_type);
+    crc.update(data);
+    buf.extend(crc.finish().to_be_bytes());
+}
+
+/// Wrap data in a zlib stream of stored deflate blocks.
+pub(crate) fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let block_count = data.len().div_ceil(MAX_STORED_LEN).max(1);
+    let mut buf = Vec::with_capacity(data.len() + block_count * 5 + 6);
+    // deflate with a 32 KiB window, no preset dictionary, fastest level
+    buf.extend([0x78, 0x01]);
+    let mut blocks = data.chunks(MAX_STORED_LEN).peekable();
+    if blocks.peek().is_none() {
+        buf.extend([1, 0x00, 0x00, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        buf.push(u8::from(is_final));
+        // Cast safety: blocks are at most 0xFFFF bytes
+        let len = block.len() as u16;
+        buf.extend(len.to_le_bytes());
+        buf.extend((!len).to_le_bytes());
+        buf.extend(block);
+    }
+    buf.extend(adler32(data).to_be_bytes());
+    buf
+}
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let mut a = 1u32;
+    let mut b = 0u32;
+    // 5552 is the most bytes that can be summed before b overflows
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= MOD_ADLER;
+        b %= MOD_ADLER;
+    }
+    (b << 16) | a
+}
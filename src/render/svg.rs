@@ -0,0 +1,78 @@
+//! A minimal SVG writer.
+use super::font;
+use super::{Color, Scene, Shape};
+use std::fmt::Write as _;
+
+fn color(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write the shapes of a scene as an SVG image.
+pub(crate) fn write(scene: &Scene) -> String {
+    let mut svg = String::new();
+    // Panic safety: writing to a string never fails
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        scene.width, scene.height
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        color(scene.background)
+    )
+    .unwrap();
+    for shape in &scene.shapes {
+        match shape {
+            Shape::Rect {
+                x,
+                y,
+                width,
+                height,
+                color: fill,
+            } => writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                x,
+                y,
+                width,
+                height,
+                color(*fill)
+            )
+            .unwrap(),
+            // the y coordinate of text is its baseline, not its top
+            Shape::Text {
+                x,
+                y,
+                text,
+                color: fill,
+            } => writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-family="monospace" font-size="{}" fill="{}">{}</text>"#,
+                x,
+                y + font::HEIGHT,
+                font::HEIGHT + 2,
+                color(*fill),
+                escape(text)
+            )
+            .unwrap(),
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
@@ -0,0 +1,185 @@
+use super::*;
+use crate::checksum::crc32;
+use crate::wave::{Channels, Format, SamplesPerSec};
+
+/// One second of audio, half at +0.5 and half at -0.5.
+fn wav() -> WaveFile {
+    let mut data = Vec::new();
+    for i in 0..11025 {
+        let sample: i16 = if i < 5512 { 16384 } else { -16384 };
+        data.extend(sample.to_le_bytes());
+    }
+    let fmt = Format {
+        channels: Channels::One,
+        samples_per_sec: SamplesPerSec::Hz11025,
+        bits_per_sample: BitsPerSample::Sixteen,
+    };
+    WaveFile::new(fmt, data)
+}
+
+fn cue(timestamps: Vec<f64>, labels: Option<Vec<String>>) -> CuePoints {
+    CuePoints {
+        labels,
+        ..CuePoints::new(timestamps)
+    }
+}
+
+fn options(start: f32, end: Option<f32>) -> RenderOptions {
+    RenderOptions {
+        width: 100,
+        height: 60,
+        start,
+        end,
+    }
+}
+
+fn texts(scene: &Scene) -> Vec<(u32, &str)> {
+    scene
+        .shapes
+        .iter()
+        .filter_map(|shape| match shape {
+            Shape::Text { x, text, .. } => Some((*x, text.as_str())),
+            Shape::Rect { .. } => None,
+        })
+        .collect()
+}
+
+/// Inflate a zlib stream of stored deflate blocks.
+fn inflate_stored(buf: &[u8]) -> Vec<u8> {
+    assert_eq!(&buf[..2], &[0x78, 0x01]);
+    let mut data = Vec::new();
+    let mut offset = 2;
+    loop {
+        let header = buf[offset];
+        let len = u16::from_le_bytes([buf[offset + 1], buf[offset + 2]]);
+        let nlen = u16::from_le_bytes([buf[offset + 3], buf[offset + 4]]);
+        assert_eq!(header & 0b110, 0, "block is not stored");
+        assert_eq!(len, !nlen);
+        offset += 5;
+        data.extend(&buf[offset..offset + usize::from(len)]);
+        offset += usize::from(len);
+        if header & 1 == 1 {
+            break;
+        }
+    }
+    let adler = u32::from_be_bytes(buf[offset..offset + 4].try_into().unwrap());
+    assert_eq!(adler, png::adler32(&data));
+    assert_eq!(offset + 4, buf.len());
+    data
+}
+
+#[test]
+fn adler32_known_value() {
+    assert_eq!(png::adler32(b""), 1);
+    assert_eq!(png::adler32(b"Wikipedia"), 0x11E6_0398);
+}
+
+#[test]
+fn zlib_stored_blocks() {
+    let data: Vec<u8> = (0..150_000u32).map(|i| (i % 251) as u8).collect();
+    let buf = png::zlib_stored(&data);
+    assert_eq!(inflate_stored(&buf), data);
+    assert_eq!(inflate_stored(&png::zlib_stored(&[])), Vec::<u8>::new());
+}
+
+#[test]
+fn png_chunks() {
+    let buf = render(ImageFormat::Png, &wav(), None, &options(0.0, None)).unwrap();
+    assert_eq!(&buf[..8], b"\x89PNG\r\n\x1a\n");
+
+    let mut offset = 8;
+    let mut chunks = Vec::new();
+    while offset < buf.len() {
+        let len = u32::from_be_bytes(buf[offset..offset + 4].try_into().unwrap()) as usize;
+        let chunk_type = &buf[offset + 4..offset + 8];
+        let data = &buf[offset + 8..offset + 8 + len];
+        let crc = u32::from_be_bytes(buf[offset + 8 + len..offset + 12 + len].try_into().unwrap());
+        assert_eq!(crc, crc32(&buf[offset + 4..offset + 8 + len]));
+        chunks.push((chunk_type.to_vec(), data.to_vec()));
+        offset += 12 + len;
+    }
+
+    let types: Vec<&[u8]> = chunks.iter().map(|(t, _)| t.as_slice()).collect();
+    assert_eq!(types, vec![&b"IHDR"[..], b"IDAT", b"IEND"]);
+    assert_eq!(
+        chunks[0].1,
+        vec![0, 0, 0, 100, 0, 0, 0, 60, 8, png::COLOR_TYPE_RGB, 0, 0, 0]
+    );
+    let raw = inflate_stored(&chunks[1].1);
+    assert_eq!(raw.len(), (100 * 3 + 1) * 60);
+}
+
+#[test]
+fn waveform_and_markers() {
+    let cue = cue(vec![0.5, 2.0], Some(vec!["Loop".to_string()]));
+    let scene = scene(&wav(), Some(&cue), &options(0.0, None)).unwrap();
+    // the second cue point is past the end of the audio
+    assert_eq!(texts(&scene), vec![(52, "Loop"), (52, "0.500s")]);
+
+    let image = png::Image::rasterize(&scene);
+    let center = LABEL_HEIGHT + (60 - LABEL_HEIGHT) / 2;
+    let amplitude = (60 - LABEL_HEIGHT) / 2 - 1;
+    // positive in the first half, negative in the second half
+    assert_eq!(image.pixel(10, center - amplitude / 2), WAVEFORM);
+    assert_eq!(image.pixel(10, center + amplitude / 2), BACKGROUND);
+    assert_eq!(image.pixel(90, center - amplitude / 2), BACKGROUND);
+    assert_eq!(image.pixel(90, center + amplitude / 2), WAVEFORM);
+    // the marker spans the whole height
+    assert_eq!(image.pixel(50, 0), MARKER);
+    assert_eq!(image.pixel(50, 59), MARKER);
+}
+
+#[test]
+fn zoom_range() {
+    let cue = cue(vec![0.1, 0.3, 0.6], None);
+    let scene = scene(&wav(), Some(&cue), &options(0.25, Some(0.5))).unwrap();
+    assert_eq!(texts(&scene), vec![(22, "#2"), (22, "0.300s")]);
+
+    for (start, end) in [
+        (0.5, Some(0.5)),
+        (-0.1, None),
+        (1.5, None),
+        (0.6, Some(0.2)),
+    ] {
+        assert!(
+            scene_err(start, end).starts_with("Zoom range"),
+            "{} to {:?}",
+            start,
+            end
+        );
+    }
+}
+
+fn scene_err(start: f32, end: Option<f32>) -> String {
+    scene(&wav(), None, &options(start, end))
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn image_size() {
+    let options = RenderOptions {
+        height: 10,
+        ..options(0.0, None)
+    };
+    let err = scene(&wav(), None, &options).unwrap_err();
+    assert_eq!(err.to_string(), "Image height must be between 38 and 16384");
+}
+
+#[test]
+fn svg_text_is_escaped() {
+    let cue = cue(vec![0.25], Some(vec!["<a & b>".to_string()]));
+    let svg = render(ImageFormat::Svg, &wav(), Some(&cue), &options(0.0, None)).unwrap();
+    let svg = String::from_utf8(svg).unwrap();
+    assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="60""#));
+    assert!(svg.contains(">&lt;a &amp; b&gt;</text>"));
+    assert!(svg.contains(">0.250s</text>"));
+    assert!(svg.ends_with("</svg>\n"));
+}
+
+#[test]
+fn font_is_case_insensitive() {
+    assert_eq!(font::glyph('a'), font::glyph('A'));
+    assert_ne!(font::glyph('s'), font::glyph('S'));
+    assert_eq!(font::glyph('~'), font::glyph('?'));
+}
@@ -0,0 +1,245 @@
+//! SubRip (`.srt`) and WebVTT (`.vtt`) subtitles keyed to cue points.
+//!
+//! Each cue starts a subtitle, which is ended by the next cue, or the end
+//! of the audio.
+use crate::error::bail;
+use crate::wave::{CuePoints, CUE_VERSION};
+use eyre::{eyre, Context as _, Result};
+use std::fmt::Write as _;
+
+/// The supported subtitle formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum SubtitleFormat {
+    /// SubRip
+    Srt,
+    /// WebVTT
+    Vtt,
+}
+
+impl SubtitleFormat {
+    /// The format implied by a file extension, if any.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        if ext.eq_ignore_ascii_case("srt") {
+            Some(Self::Srt)
+        } else if ext.eq_ignore_ascii_case("vtt") {
+            Some(Self::Vtt)
+        } else {
+            None
+        }
+    }
+}
+
+/// A single subtitle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subtitle {
+    /// The start time in seconds
+    pub start: f64,
+    /// The end time in seconds
+    pub end: f64,
+    /// The text, with lines separated by `\n`
+    pub text: String,
+}
+
+/// Read subtitle lines, one per cue point. Blank lines are ignored.
+pub fn read_lines(buf: &[u8]) -> crate::Result<Vec<String>> {
+    let contents = std::str::from_utf8(buf).wrap_err("Lines file is not valid UTF-8")?;
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// The time in whole milliseconds, as written to subtitle files.
+fn millis(seconds: f64) -> u64 {
+    // Cast safety: saturating, and the times are not negative
+    (seconds * 1000.0).round() as u64
+}
+
+/// Create subtitles from cue points and subtitle lines, one per cue point.
+///
+/// The duration of the audio ends the last subtitle. Subtitles can't
+/// overlap, so cue points at the same millisecond are rejected.
+pub fn from_cue(
+    cue: &CuePoints,
+    lines: Vec<String>,
+    duration: f64,
+) -> crate::Result<Vec<Subtitle>> {
+    let count = cue.timestamps.len();
+    if lines.len() != count {
+        bail!(
+            "Expected {} subtitle lines (one per cue point), but was {}",
+            count,
+            lines.len()
+        );
+    }
+
+    let mut subtitles = cue
+        .timestamps
+        .iter()
+        .copied()
+        .zip(lines)
+        .zip(1usize..)
+        .map(|((start, text), index)| {
+            let subtitle = Subtitle {
+                start,
+                end: duration,
+                text,
+            };
+            (subtitle, index)
+        })
+        .collect::<Vec<_>>();
+    // cue points may be in any order, but subtitles must be in time order
+    subtitles.sort_by(|(a, _), (b, _)| a.start.total_cmp(&b.start));
+
+    for position in 0..subtitles.len() {
+        let (start, index) = (subtitles[position].0.start, subtitles[position].1);
+        let next = subtitles
+            .get(position + 1)
+            .map(|(next, next_index)| (next.start, *next_index));
+        if let Some((next_start, next_index)) = next {
+            if millis(next_start) == millis(start) {
+                bail!(
+                    "Cue points {} and {} are both at {:.3}s, but subtitles can't overlap",
+                    index.min(next_index),
+                    index.max(next_index),
+                    start
+                );
+            }
+        }
+        let end = next.map_or(duration, |(next_start, _)| next_start);
+        if millis(end) <= millis(start) {
+            bail!(
+                "Cue point at {:.3}s is at or after the end of the audio ({:.3}s)",
+                start,
+                duration
+            );
+        }
+        subtitles[position].0.end = end;
+    }
+    Ok(subtitles
+        .into_iter()
+        .map(|(subtitle, _)| subtitle)
+        .collect())
+}
+
+/// Create cue points from subtitles, labelled with the subtitle text.
+pub fn to_cue(subtitles: &[Subtitle]) -> CuePoints {
+    let timestamps = subtitles.iter().map(|subtitle| subtitle.start).collect();
+    let labels = subtitles
+        .iter()
+        .map(|subtitle| subtitle.text.clone())
+        .collect();
+    CuePoints {
+        version: CUE_VERSION,
+        metadata: None,
+        sample_points: None,
+        timestamps,
+        labels: Some(labels),
+        lengths: None,
+        sampler: None,
+        info: None,
+    }
+}
+
+fn format_time(seconds: f64, separator: char) -> String {
+    let millis = millis(seconds);
+    let (hours, rem) = (millis / 3_600_000, millis % 3_600_000);
+    let (minutes, rem) = (rem / 60_000, rem % 60_000);
+    let (secs, millis) = (rem / 1000, rem % 1000);
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        hours, minutes, secs, separator, millis
+    )
+}
+
+/// Write SubRip or WebVTT subtitles.
+pub fn write(format: SubtitleFormat, subtitles: &[Subtitle]) -> Vec<u8> {
+    let mut contents = String::new();
+    let separator = match format {
+        SubtitleFormat::Srt => ',',
+        SubtitleFormat::Vtt => {
+            contents.push_str("WEBVTT\n\n");
+            '.'
+        }
+    };
+    for (subtitle, index) in subtitles.iter().zip(1u32..) {
+        // Infallible: writing to a string cannot fail
+        let _ = writeln!(
+            contents,
+            "{}\n{} --> {}\n{}\n",
+            index,
+            format_time(subtitle.start, separator),
+            format_time(subtitle.end, separator),
+            subtitle.text,
+        );
+    }
+    contents.into_bytes()
+}
+
+fn parse_time(value: &str, line_no: usize) -> Result<f64> {
+    let invalid = || eyre!("Invalid time `{}` (line {})", value, line_no);
+    let (hms, millis) = value.trim().rsplit_once([',', '.']).ok_or_else(invalid)?;
+    let millis: u32 = millis.parse().map_err(|_| invalid())?;
+    if millis > 999 {
+        return Err(invalid());
+    }
+
+    let mut seconds = 0u32;
+    for part in hms.split(':') {
+        let value: u32 = part.parse().map_err(|_| invalid())?;
+        seconds = seconds
+            .checked_mul(60)
+            .and_then(|s| s.checked_add(value))
+            .ok_or_else(invalid)?;
+    }
+    Ok(f64::from(seconds) + f64::from(millis) / 1000.0)
+}
+
+/// Read SubRip or WebVTT subtitles.
+///
+/// Only the timing and text of each cue is read, keeping its line breaks;
+/// styling, positioning, and WebVTT notes are ignored.
+pub fn read(buf: &[u8]) -> crate::Result<Vec<Subtitle>> {
+    let contents = std::str::from_utf8(buf).wrap_err("Subtitle file is not valid UTF-8")?;
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+
+    let mut subtitles = Vec::new();
+    let mut lines = contents.lines().map(str::trim_end).zip(1usize..).peekable();
+    while let Some((line, line_no)) = lines.next() {
+        // timings identify a cue, anything else (sequence numbers, the WebVTT
+        // header, notes) is skipped
+        let Some((start, rest)) = line.split_once("-->") else {
+            continue;
+        };
+        let start = parse_time(start, line_no)?;
+        // WebVTT cue settings may follow the end time
+        let end = rest.split_whitespace().next().unwrap_or_default();
+        let end = parse_time(end, line_no)?;
+        if end < start {
+            bail!(
+                "Expected end time >= {:.3}, but was {:.3} (line {})",
+                start,
+                end,
+                line_no
+            );
+        }
+
+        let mut text = Vec::new();
+        while let Some((line, _)) = lines.next_if(|(line, _)| !line.is_empty()) {
+            text.push(line);
+        }
+        subtitles.push(Subtitle {
+            start,
+            end,
+            text: text.join("\n"),
+        });
+    }
+    Ok(subtitles)
+}
+
+#[cfg(test)]
+mod tests;
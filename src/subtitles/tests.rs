@@ -0,0 +1,130 @@
+use super::*;
+
+fn cue(timestamps: Vec<f64>) -> CuePoints {
+    CuePoints::new(timestamps)
+}
+
+#[test]
+fn subtitles_from_cue_ends_at_next_cue() {
+    let lines = vec!["b".to_string(), "a".to_string()];
+    let subtitles = from_cue(&cue(vec![1.5, 0.0]), lines, 3.0).unwrap();
+    assert_eq!(
+        subtitles,
+        vec![
+            Subtitle {
+                start: 0.0,
+                end: 1.5,
+                text: "a".to_string()
+            },
+            Subtitle {
+                start: 1.5,
+                end: 3.0,
+                text: "b".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn subtitles_from_cue_line_count_mismatch() {
+    let lines = vec!["a".to_string()];
+    assert!(from_cue(&cue(vec![0.0, 1.0]), lines, 2.0).is_err());
+}
+
+#[test]
+fn subtitles_from_cue_after_end() {
+    let lines = vec!["a".to_string()];
+    assert!(from_cue(&cue(vec![2.0]), lines, 2.0).is_err());
+}
+
+#[test]
+fn subtitles_from_cue_duplicate_timestamps() {
+    let lines = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    let err = from_cue(&cue(vec![1.0, 0.0, 1.0]), lines.clone(), 3.0).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Cue points 1 and 3 are both at 1.000s, but subtitles can't overlap"
+    );
+
+    // the subtitles would be zero-length once written in milliseconds
+    assert!(from_cue(&cue(vec![1.0, 0.0, 1.0002]), lines.clone(), 3.0).is_err());
+    assert!(from_cue(&cue(vec![1.0, 0.0, 2.9998]), lines, 3.0).is_err());
+}
+
+#[test]
+fn subtitles_write_srt() {
+    let subtitles = vec![Subtitle {
+        start: 61.25,
+        end: 3723.5,
+        text: "Hello".to_string(),
+    }];
+    let contents = write(SubtitleFormat::Srt, &subtitles);
+    assert_eq!(contents, b"1\n00:01:01,250 --> 01:02:03,500\nHello\n\n");
+}
+
+#[test]
+fn subtitles_write_vtt() {
+    let subtitles = vec![Subtitle {
+        start: 0.0,
+        end: 1.0,
+        text: "Hello".to_string(),
+    }];
+    let contents = write(SubtitleFormat::Vtt, &subtitles);
+    assert_eq!(
+        contents,
+        b"WEBVTT\n\n1\n00:00:00.000 --> 00:00:01.000\nHello\n\n"
+    );
+}
+
+#[test]
+fn subtitles_read_srt() {
+    let contents = b"1\r\n00:00:00,500 --> 00:00:01,000\r\nMultiple\r\nlines\r\n\r\n2\r\n00:00:01,000 --> 00:00:02,000\r\nSecond\r\n";
+    let subtitles = read(contents).unwrap();
+    assert_eq!(
+        subtitles,
+        vec![
+            Subtitle {
+                start: 0.5,
+                end: 1.0,
+                text: "Multiple\nlines".to_string()
+            },
+            Subtitle {
+                start: 1.0,
+                end: 2.0,
+                text: "Second".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn subtitles_write_and_read_line_breaks() {
+    let subtitles = vec![Subtitle {
+        start: 0.0,
+        end: 1.0,
+        text: "First line\nSecond line".to_string(),
+    }];
+    let contents = write(SubtitleFormat::Srt, &subtitles);
+    assert_eq!(
+        contents,
+        b"1\n00:00:00,000 --> 00:00:01,000\nFirst line\nSecond line\n\n"
+    );
+    assert_eq!(read(&contents).unwrap(), subtitles);
+    let labels = to_cue(&subtitles).labels.unwrap();
+    assert_eq!(labels, vec!["First line\nSecond line"]);
+}
+
+#[test]
+fn subtitles_read_vtt_with_settings() {
+    let contents = b"WEBVTT\n\nNOTE ignored\n\n00:01.500 --> 00:02.000 align:start\nHello\n";
+    let subtitles = read(contents).unwrap();
+    assert_eq!(subtitles.len(), 1);
+    assert_eq!(subtitles[0].start, 1.5);
+    assert_eq!(subtitles[0].end, 2.0);
+    assert_eq!(subtitles[0].text, "Hello");
+}
+
+#[test]
+fn subtitles_read_invalid_time() {
+    assert!(read(b"1\n00:00:xx,000 --> 00:00:01,000\nHello\n").is_err());
+}
@@ -0,0 +1,171 @@
+//! IMA ADPCM (mono), as specified by the IMA Digital Audio Focus and
+//! Technical Working Groups, with the Microsoft block layout.
+use super::super::{AdpcmLayout, Format};
+use crate::error::bail;
+use eyre::Result;
+
+pub(crate) const HEADER_SIZE: u16 = 4;
+const MAX_STEP_INDEX: u8 = 88;
+
+const INDEX_TABLE: [i8; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+const STEP_TABLE: [i16; MAX_STEP_INDEX as usize + 1] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+struct State {
+    predictor: i32,
+    step_index: u8,
+}
+
+impl State {
+    fn decode(&mut self, nibble: u8) -> i16 {
+        let step = STEP_TABLE[self.step_index as usize] as i32;
+        let mut diff = step >> 3;
+        if nibble & 1 != 0 {
+            diff += step >> 2;
+        }
+        if nibble & 2 != 0 {
+            diff += step >> 1;
+        }
+        if nibble & 4 != 0 {
+            diff += step;
+        }
+        if nibble & 8 != 0 {
+            self.predictor -= diff;
+        } else {
+            self.predictor += diff;
+        }
+        self.predictor = self.predictor.clamp(i16::MIN.into(), i16::MAX.into());
+
+        let step_index = self.step_index as i8 + INDEX_TABLE[nibble as usize];
+        // Cast safety: clamped to 0..=88
+        self.step_index = step_index.clamp(0, MAX_STEP_INDEX as i8) as u8;
+        // Cast safety: clamped to i16 above
+        self.predictor as i16
+    }
+
+    fn encode(&mut self, sample: i16) -> u8 {
+        let step = STEP_TABLE[self.step_index as usize] as i32;
+        let mut diff = sample as i32 - self.predictor;
+        let mut nibble = 0;
+        if diff < 0 {
+            nibble = 8;
+            diff = -diff;
+        }
+        if diff >= step {
+            nibble |= 4;
+            diff -= step;
+        }
+        if diff >= step >> 1 {
+            nibble |= 2;
+            diff -= step >> 1;
+        }
+        if diff >= step >> 2 {
+            nibble |= 1;
+        }
+        // keep the predictor in sync with the decoder
+        self.decode(nibble);
+        nibble
+    }
+}
+
+/// The number of samples in a block, if the block is large enough for the
+/// header.
+pub(crate) fn samples_per_block(block_align: u16) -> Option<u32> {
+    let samples = block_align.checked_sub(HEADER_SIZE)? as u32 * 2 + 1;
+    Some(samples)
+}
+
+/// The block layout for the block size.
+pub(crate) fn layout(fmt: &Format, block_align: u16) -> AdpcmLayout {
+    // Panic safety: only called with the default block sizes, which are
+    // large enough, and small enough for the samples per block to fit
+    let samples_per_block = samples_per_block(block_align).unwrap();
+    AdpcmLayout {
+        block_align,
+        samples_per_block: samples_per_block.try_into().unwrap(),
+        avg_bytes_per_sec: fmt.samples_per_sec.as_u32() * block_align as u32 / samples_per_block,
+    }
+}
+
+/// The number of samples in audio data of the given size, assuming the last
+/// block is filled up.
+pub(crate) fn sample_count(layout: &AdpcmLayout, data_size: u64) -> u64 {
+    let block_align = layout.block_align as u64;
+    let header_size = HEADER_SIZE as u64;
+    let remainder = data_size % block_align;
+    let partial = if remainder >= header_size {
+        1 + (remainder - header_size) * 2
+    } else {
+        0
+    };
+    (data_size / block_align)
+        .saturating_mul(layout.samples_per_block.into())
+        .saturating_add(partial)
+}
+
+pub(crate) fn decode(data: &[u8], layout: &AdpcmLayout) -> Result<Vec<i16>> {
+    let mut samples = Vec::with_capacity(sample_count(layout, data.len() as u64) as usize);
+    for (block, index) in data.chunks(layout.block_align.into()).zip(0usize..) {
+        if block.len() < HEADER_SIZE.into() {
+            bail!("Block {} is truncated", index);
+        }
+        let predictor = i16::from_le_bytes([block[0], block[1]]);
+        let step_index = block[2];
+        if step_index > MAX_STEP_INDEX {
+            bail!("Block {} has an invalid step index {}", index, step_index);
+        }
+
+        let mut state = State {
+            predictor: predictor.into(),
+            step_index,
+        };
+        samples.push(predictor);
+        for byte in &block[HEADER_SIZE.into()..] {
+            samples.push(state.decode(byte & 0xF));
+            samples.push(state.decode(byte >> 4));
+        }
+    }
+    Ok(samples)
+}
+
+/// The step index to start encoding with, so the first samples don't have
+/// to ramp up from the smallest step.
+fn initial_step_index(samples: &[i16]) -> u8 {
+    let diff = match samples {
+        [first, second, ..] => (*second as i32 - *first as i32).abs(),
+        _ => 0,
+    };
+    let step_index = STEP_TABLE
+        .iter()
+        .position(|&step| step as i32 >= diff)
+        .unwrap_or(MAX_STEP_INDEX as usize);
+    // Cast safety: the step table has 89 entries
+    step_index as u8
+}
+
+pub(crate) fn encode(samples: &[i16], layout: &AdpcmLayout) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut step_index = initial_step_index(samples);
+    for block in samples.chunks(layout.samples_per_block.into()) {
+        let mut state = State {
+            predictor: block[0].into(),
+            step_index,
+        };
+        data.extend_from_slice(&block[0].to_le_bytes());
+        data.push(step_index);
+        data.push(0);
+        for pair in block[1..].chunks(2) {
+            let low = state.encode(pair[0]);
+            let high = pair.get(1).map_or(0, |&sample| state.encode(sample));
+            data.push(low | (high << 4));
+        }
+        step_index = state.step_index;
+    }
+    data
+}
@@ -0,0 +1,115 @@
+pub(super) mod ima;
+pub(super) mod ms;
+
+use super::{BitsPerSample, Encoding, Format, WaveFile};
+use eyre::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+
+/// The codecs audio data can be converted to with [`convert`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum Codec {
+    /// Uncompressed PCM, 16 bits per sample when decoding ADPCM
+    Pcm,
+    /// IMA ADPCM (format tag 0x0011)
+    ImaAdpcm,
+    /// Microsoft ADPCM (format tag 0x0002)
+    MsAdpcm,
+}
+
+/// The default block size, the same as the Windows codecs use.
+fn default_block_align(fmt: &Format) -> u16 {
+    // Cast safety: the sample rate is at most 44100 Hz, so this is at most 4
+    let multiplier = (fmt.samples_per_sec.as_u32() / 11025).max(1) as u16;
+    256 * fmt.channels.as_u16() * multiplier
+}
+
+/// Decode the audio data of a wave file to 16-bit samples.
+fn decode(wav: &WaveFile) -> Result<Vec<i16>> {
+    let mut samples = match &wav.encoding {
+        Encoding::Pcm => match wav.fmt.bits_per_sample {
+            BitsPerSample::Eight => wav
+                .data
+                .iter()
+                .map(|&sample| (sample as i16 - 128) << 8)
+                .collect(),
+            BitsPerSample::Sixteen => wav
+                .data
+                .chunks_exact(2)
+                .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+                .collect(),
+        },
+        Encoding::ImaAdpcm { layout } => ima::decode(&wav.data, layout)?,
+        Encoding::MsAdpcm {
+            layout,
+            coefficients,
+        } => ms::decode(&wav.data, layout, coefficients)?,
+    };
+    // the last block may not be full
+    samples.truncate(wav.sample_count() as usize);
+    Ok(samples)
+}
+
+/// Convert the audio data of a wave file to another codec.
+///
+/// ADPCM audio data is decoded to 16 bits per sample, and encoded with the
+/// default block size for the sample rate. The cue points are kept, since
+/// the sample positions don't change. If the wave file already uses the
+/// codec, it is returned as-is.
+pub fn convert(wav: &WaveFile, codec: Codec) -> crate::Result<WaveFile> {
+    if wav.encoding.codec() == codec {
+        return Ok(wav.clone());
+    }
+
+    let samples = decode(wav).wrap_err_with(|| format!("Error decoding {}", wav.encoding))?;
+    let sample_count: u32 = samples.len().try_into().wrap_err("Way too many samples")?;
+    let fmt = Format {
+        channels: wav.fmt.channels,
+        samples_per_sec: wav.fmt.samples_per_sec,
+        bits_per_sample: BitsPerSample::Sixteen,
+    };
+
+    let (encoding, data) = match codec {
+        Codec::Pcm => {
+            let data = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+            (Encoding::Pcm, data)
+        }
+        Codec::ImaAdpcm => {
+            let layout = ima::layout(&fmt, default_block_align(&fmt));
+            let data = ima::encode(&samples, &layout);
+            (Encoding::ImaAdpcm { layout }, data)
+        }
+        Codec::MsAdpcm => {
+            let layout = ms::layout(&fmt, default_block_align(&fmt));
+            let coefficients = ms::COEFFICIENTS.to_vec();
+            let data = ms::encode(&samples, &layout, &coefficients);
+            let encoding = Encoding::MsAdpcm {
+                layout,
+                coefficients,
+            };
+            (encoding, data)
+        }
+    };
+    // the fact chunk is required for compressed audio data
+    let fact = (codec != Codec::Pcm).then_some(sample_count);
+
+    Ok(WaveFile {
+        container: wav.container,
+        fmt,
+        encoding,
+        fmt_extension: false,
+        fact,
+        cue: wav.cue.clone(),
+        labels: wav.labels.clone(),
+        sampler: wav.sampler.clone(),
+        info: wav.info.clone(),
+        bext: wav.bext.clone(),
+        ixml: wav.ixml.clone(),
+        data,
+        chunk_order: wav.chunk_order.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,211 @@
+//! Microsoft ADPCM (mono).
+use super::super::{AdpcmLayout, Format};
+use crate::error::bail;
+use eyre::{eyre, Result};
+
+pub(crate) const HEADER_SIZE: u16 = 7;
+const MIN_DELTA: i32 = 16;
+// keeps `delta * ADAPTATION` from overflowing
+const MAX_DELTA: i32 = i32::MAX / 768;
+
+const ADAPTATION: [i32; 16] = [
+    230, 230, 230, 230, 307, 409, 512, 614, 768, 614, 512, 409, 307, 230, 230, 230,
+];
+
+/// The standard predictor coefficient pairs, which every file should have.
+pub(crate) const COEFFICIENTS: [(i16, i16); 7] = [
+    (256, 0),
+    (512, -256),
+    (0, 0),
+    (192, 64),
+    (240, 0),
+    (460, -208),
+    (392, -232),
+];
+
+struct State {
+    coefficient1: i32,
+    coefficient2: i32,
+    delta: i32,
+    sample1: i32,
+    sample2: i32,
+}
+
+impl State {
+    fn new(
+        (coefficient1, coefficient2): (i16, i16),
+        delta: i32,
+        sample1: i16,
+        sample2: i16,
+    ) -> Self {
+        Self {
+            coefficient1: coefficient1.into(),
+            coefficient2: coefficient2.into(),
+            delta,
+            sample1: sample1.into(),
+            sample2: sample2.into(),
+        }
+    }
+
+    fn predict(&self) -> i32 {
+        // the sum of two i16 products can overflow an i32, e.g. with -32768
+        // everywhere, so add in i64
+        let sum = i64::from(self.sample1) * i64::from(self.coefficient1)
+            + i64::from(self.sample2) * i64::from(self.coefficient2);
+        // the reference decoder divides, rounding toward zero, rather than
+        // shifting
+        // Cast safety: the sum is at most 2^31 in magnitude, so divided it
+        // fits in an i32
+        (sum / 256) as i32
+    }
+
+    fn decode(&mut self, nibble: u8) -> i16 {
+        // sign-extend the nibble
+        let signed = (((nibble << 4) as i8) >> 4) as i32;
+        let sample = (self.predict() + signed * self.delta).clamp(i16::MIN.into(), i16::MAX.into());
+        self.sample2 = self.sample1;
+        self.sample1 = sample;
+        self.delta = ((ADAPTATION[nibble as usize] * self.delta) >> 8).clamp(MIN_DELTA, MAX_DELTA);
+        // Cast safety: clamped to i16 above
+        sample as i16
+    }
+
+    fn encode(&mut self, sample: i16) -> u8 {
+        let error = sample as i32 - self.predict();
+        // round to the nearest multiple of delta
+        let half = self.delta / 2;
+        let signed = if error >= 0 {
+            (error + half) / self.delta
+        } else {
+            (error - half) / self.delta
+        };
+        // Cast safety: clamped to a signed nibble
+        let nibble = (signed.clamp(-8, 7) as u8) & 0xF;
+        // keep the predictor in sync with the decoder
+        self.decode(nibble);
+        nibble
+    }
+}
+
+/// The number of samples in a block, if the block is large enough for the
+/// header.
+pub(crate) fn samples_per_block(block_align: u16) -> Option<u32> {
+    let samples = block_align.checked_sub(HEADER_SIZE)? as u32 * 2 + 2;
+    Some(samples)
+}
+
+/// The block layout for the block size.
+pub(crate) fn layout(fmt: &Format, block_align: u16) -> AdpcmLayout {
+    // Panic safety: only called with the default block sizes, which are
+    // large enough, and small enough for the samples per block to fit
+    let samples_per_block = samples_per_block(block_align).unwrap();
+    AdpcmLayout {
+        block_align,
+        samples_per_block: samples_per_block.try_into().unwrap(),
+        avg_bytes_per_sec: fmt.samples_per_sec.as_u32() * block_align as u32 / samples_per_block,
+    }
+}
+
+/// The number of samples in audio data of the given size, assuming the last
+/// block is filled up.
+pub(crate) fn sample_count(layout: &AdpcmLayout, data_size: u64) -> u64 {
+    let block_align = layout.block_align as u64;
+    let header_size = HEADER_SIZE as u64;
+    let remainder = data_size % block_align;
+    let partial = if remainder >= header_size {
+        2 + (remainder - header_size) * 2
+    } else {
+        0
+    };
+    (data_size / block_align)
+        .saturating_mul(layout.samples_per_block.into())
+        .saturating_add(partial)
+}
+
+pub(crate) fn decode(
+    data: &[u8],
+    layout: &AdpcmLayout,
+    coefficients: &[(i16, i16)],
+) -> Result<Vec<i16>> {
+    let mut samples = Vec::with_capacity(sample_count(layout, data.len() as u64) as usize);
+    for (block, index) in data.chunks(layout.block_align.into()).zip(0usize..) {
+        if block.len() < HEADER_SIZE.into() {
+            bail!("Block {} is truncated", index);
+        }
+        let predictor = block[0];
+        let coefficient = coefficients
+            .get(predictor as usize)
+            .copied()
+            .ok_or_else(|| eyre!("Block {} has an invalid predictor {}", index, predictor))?;
+        let delta = i16::from_le_bytes([block[1], block[2]]);
+        let sample1 = i16::from_le_bytes([block[3], block[4]]);
+        let sample2 = i16::from_le_bytes([block[5], block[6]]);
+
+        let mut state = State::new(coefficient, delta.into(), sample1, sample2);
+        samples.push(sample2);
+        samples.push(sample1);
+        for byte in &block[HEADER_SIZE.into()..] {
+            samples.push(state.decode(byte >> 4));
+            samples.push(state.decode(byte & 0xF));
+        }
+    }
+    Ok(samples)
+}
+
+/// Encode the samples of one block after the header with a predictor, and
+/// return the nibbles, the squared error, and the delta after the block.
+fn encode_nibbles(block: &[i16], coefficient: (i16, i16), delta: i32) -> (Vec<u8>, u64, i32) {
+    let sample2 = block[0];
+    let sample1 = block.get(1).copied().unwrap_or(sample2);
+    let mut state = State::new(coefficient, delta, sample1, sample2);
+    let mut error = 0u64;
+    let nibbles = block
+        .iter()
+        .skip(2)
+        .map(|&sample| {
+            let nibble = state.encode(sample);
+            // Cast safety: the absolute difference of two i16 fits in a u32
+            let diff = (sample as i32 - state.sample1).unsigned_abs() as u64;
+            error += diff * diff;
+            nibble
+        })
+        .collect();
+    (nibbles, error, state.delta)
+}
+
+pub(crate) fn encode(
+    samples: &[i16],
+    layout: &AdpcmLayout,
+    coefficients: &[(i16, i16)],
+) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut delta = MIN_DELTA;
+    for block in samples.chunks(layout.samples_per_block.into()) {
+        // pick the predictor with the smallest error
+        // the predictor index is a byte, so only the first 256 pairs are usable
+        let (predictor, (nibbles, _, next_delta)) = coefficients
+            .iter()
+            .take(256)
+            .map(|&coefficient| encode_nibbles(block, coefficient, delta))
+            .enumerate()
+            .min_by_key(|(_, (_, error, _))| *error)
+            // Panic safety: there is always at least one coefficient pair
+            .unwrap();
+
+        let sample2 = block[0];
+        let sample1 = block.get(1).copied().unwrap_or(sample2);
+        // Cast safety: at most 256 pairs are considered above
+        data.push(predictor as u8);
+        // Cast safety: the delta is reset to fit for each block
+        data.extend_from_slice(&(delta as i16).to_le_bytes());
+        data.extend_from_slice(&sample1.to_le_bytes());
+        data.extend_from_slice(&sample2.to_le_bytes());
+        for pair in nibbles.chunks(2) {
+            let high = pair[0];
+            let low = pair.get(1).copied().unwrap_or(0);
+            data.push((high << 4) | low);
+        }
+        delta = next_delta.clamp(MIN_DELTA, i16::MAX.into());
+    }
+    data
+}
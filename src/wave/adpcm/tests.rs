@@ -0,0 +1,184 @@
+use super::*;
+use crate::wave::{read, roundtrip, write, AdpcmLayout, Channels, SamplesPerSec};
+
+fn sine(count: usize) -> Vec<i16> {
+    (0..count)
+        .map(|i| ((i as f32 * 0.05).sin() * 12000.0) as i16)
+        .collect()
+}
+
+fn pcm(samples: &[i16], cue: Option<Vec<u32>>) -> WaveFile {
+    let fmt = Format {
+        channels: Channels::One,
+        samples_per_sec: SamplesPerSec::Hz22050,
+        bits_per_sample: BitsPerSample::Sixteen,
+    };
+    let data = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+    WaveFile {
+        cue,
+        ..WaveFile::new(fmt, data)
+    }
+}
+
+fn max_error(expected: &[i16], actual: &[i16]) -> i32 {
+    assert_eq!(expected.len(), actual.len());
+    expected
+        .iter()
+        .zip(actual)
+        .map(|(&a, &b)| (a as i32 - b as i32).abs())
+        .max()
+        .unwrap_or(0)
+}
+
+const LAYOUT: AdpcmLayout = AdpcmLayout {
+    block_align: 5,
+    samples_per_block: 3,
+    avg_bytes_per_sec: 0,
+};
+
+#[test]
+fn ima_decode_block() {
+    let samples = ima::decode(&[0, 0, 0, 0, 0x77], &LAYOUT).unwrap();
+    assert_eq!(samples, vec![0, 11, 41]);
+}
+
+#[test]
+fn ima_decode_invalid_step_index() {
+    let err = ima::decode(&[0, 0, 89, 0, 0], &LAYOUT).unwrap_err();
+    assert_eq!(err.to_string(), "Block 0 has an invalid step index 89");
+}
+
+#[test]
+fn ms_decode_block() {
+    let layout = AdpcmLayout {
+        block_align: 8,
+        samples_per_block: 4,
+        avg_bytes_per_sec: 0,
+    };
+    let block = [0, 16, 0, 100, 0, 50, 0, 0x1F];
+    let samples = ms::decode(&block, &layout, &ms::COEFFICIENTS).unwrap();
+    assert_eq!(samples, vec![50, 100, 116, 100]);
+}
+
+#[test]
+fn ms_decode_matches_reference() {
+    let layout = AdpcmLayout {
+        block_align: 16,
+        samples_per_block: 20,
+        avg_bytes_per_sec: 0,
+    };
+    #[rustfmt::skip]
+    let data = [
+        6, 0x40, 0x01, 0x18, 0xFC, 0x30, 0xF8, 0x7F, 0x80, 0x19, 0xE2, 0x3B, 0xC4, 0x5D, 0xA6, 0x0F,
+        5, 0x10, 0x00, 0x00, 0x80, 0x00, 0x80, 0x88, 0x77, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE,
+    ];
+    let samples = ms::decode(&data, &layout, &ms::COEFFICIENTS).unwrap();
+    // decoded with symphonia-codec-adpcm 0.5.5
+    #[rustfmt::skip]
+    let expected = [
+        -2000, -1000, 2521, 3999, -1674, -6187, -6099, -15408, -26066, -18764,
+        4574, 9508, -8119, 1172, 32767, 17189, -32768, 32767, 32767, -13877,
+        -32768, -32768, -32384, -31950, -30090, -25693, -20891, -15176, -8294, -176,
+        10012, 25015, 32767, -5463, -32768, -32768, -32768, -32768, -32768, -32768,
+    ];
+    assert_eq!(samples, expected);
+}
+
+#[test]
+fn ms_decode_extreme_coefficients() {
+    let layout = AdpcmLayout {
+        block_align: 8,
+        samples_per_block: 4,
+        avg_bytes_per_sec: 0,
+    };
+    let block = [0, 16, 0, 0x00, 0x80, 0x00, 0x80, 0x00];
+    let samples = ms::decode(&block, &layout, &[(i16::MIN, i16::MIN)]).unwrap();
+    assert_eq!(samples, vec![i16::MIN, i16::MIN, i16::MAX, 128]);
+}
+
+#[test]
+fn ms_decode_truncated() {
+    let layout = ms::layout(&pcm(&[], None).fmt, 512);
+    let err = ms::decode(&[0; 515], &layout, &ms::COEFFICIENTS).unwrap_err();
+    assert_eq!(err.to_string(), "Block 1 is truncated");
+}
+
+#[test]
+fn convert_ima_adpcm() {
+    let samples = sine(2000);
+    let wav = pcm(&samples, Some(vec![0, 1000, 1017]));
+    let encoded = convert(&wav, Codec::ImaAdpcm).unwrap();
+    assert_eq!(encoded.fact, Some(2000));
+    assert_eq!(encoded.sample_count(), 2000);
+
+    let buf = write(&encoded).unwrap();
+    let read_back = read(&buf).unwrap();
+    assert_eq!(read_back.encoding, encoded.encoding);
+    assert_eq!(read_back.cue, Some(vec![0, 1000, 1017]));
+    assert_eq!(roundtrip(&buf).unwrap(), None);
+
+    let decoded = convert(&read_back, Codec::Pcm).unwrap();
+    assert_eq!(decoded.encoding, Encoding::Pcm);
+    assert_eq!(decoded.fact, None);
+    assert_eq!(decoded.cue, Some(vec![0, 1000, 1017]));
+    let decoded = decode(&decoded).unwrap();
+    assert!(max_error(&samples, &decoded) < 1000);
+}
+
+#[test]
+fn convert_ms_adpcm() {
+    let samples = sine(2000);
+    let wav = pcm(&samples, Some(vec![5, 1999]));
+    let encoded = convert(&wav, Codec::MsAdpcm).unwrap();
+    assert_eq!(encoded.sample_count(), 2000);
+
+    let buf = write(&encoded).unwrap();
+    let read_back = read(&buf).unwrap();
+    assert_eq!(read_back.encoding, encoded.encoding);
+    assert_eq!(read_back.cue, Some(vec![5, 1999]));
+    assert_eq!(roundtrip(&buf).unwrap(), None);
+
+    let decoded = decode(&read_back).unwrap();
+    assert!(max_error(&samples, &decoded) < 1000);
+}
+
+#[test]
+fn adpcm_cue_points_map_to_blocks() {
+    let wav = pcm(&sine(2000), Some(vec![1017]));
+    let encoded = convert(&wav, Codec::ImaAdpcm).unwrap();
+    // 512 byte blocks at 22050 Hz, with 1017 samples per block
+    let Encoding::ImaAdpcm { layout } = encoded.encoding else {
+        panic!("unexpected encoding {}", encoded.encoding);
+    };
+    assert_eq!(layout.block_align, 512);
+    assert_eq!(layout.samples_per_block, 1017);
+
+    let buf = write(&encoded).unwrap();
+    let cue = buf.windows(4).position(|w| w == b"cue ").unwrap();
+    let point = &buf[cue + 12..cue + 36];
+    assert_eq!(&point[4..8], &1017u32.to_le_bytes()); // position
+    assert_eq!(&point[16..20], &512u32.to_le_bytes()); // block start
+    assert_eq!(&point[20..24], &0u32.to_le_bytes()); // sample offset
+}
+
+#[test]
+fn adpcm_cue_points_written_as_pcm_are_read() {
+    let wav = pcm(&sine(2000), Some(vec![1500]));
+    let encoded = convert(&wav, Codec::ImaAdpcm).unwrap();
+    let mut buf = write(&encoded).unwrap();
+    let cue = buf.windows(4).position(|w| w == b"cue ").unwrap();
+    buf[cue + 28..cue + 32].copy_from_slice(&0u32.to_le_bytes());
+    buf[cue + 32..cue + 36].copy_from_slice(&1500u32.to_le_bytes());
+    assert_eq!(read(&buf).unwrap().cue, Some(vec![1500]));
+}
+
+#[test]
+fn convert_eight_bit_pcm() {
+    let mut wav = pcm(&[], None);
+    wav.fmt.bits_per_sample = BitsPerSample::Eight;
+    wav.data = vec![0, 128, 255];
+    let encoded = convert(&wav, Codec::ImaAdpcm).unwrap();
+    let decoded = convert(&encoded, Codec::Pcm).unwrap();
+    assert_eq!(decoded.fmt.bits_per_sample, BitsPerSample::Sixteen);
+    assert_eq!(decoded.sample_count(), 3);
+}
@@ -0,0 +1,122 @@
+use super::info::{decode, decode_text};
+use super::SamplesPerSec;
+
+/// The size of the `bext` chunk without the coding history.
+pub(crate) const BEXT_CHUNK_MIN_SIZE: u32 = 602;
+/// The sizes of the fixed-size fields of the `bext` chunk.
+pub(crate) const DESCRIPTION_SIZE: usize = 256;
+pub(crate) const ORIGINATOR_SIZE: usize = 32;
+pub(crate) const ORIGINATOR_REFERENCE_SIZE: usize = 32;
+pub(crate) const ORIGINATION_DATE_SIZE: usize = 10;
+pub(crate) const ORIGINATION_TIME_SIZE: usize = 8;
+pub(crate) const UMID_SIZE: usize = 64;
+pub(crate) const RESERVED_SIZE: usize = 180;
+
+/// The Broadcast Wave Format extension chunk (`bext`), as specified by EBU
+/// Tech 3285.
+///
+/// The text fields are kept as the bytes read, padded with NUL characters in
+/// the fixed-size fields, so the chunk is written back unchanged. They should
+/// be ASCII, but are often in the Windows code page, so the accessors of the
+/// same name decode them as UTF-8, or as Latin-1 if they aren't valid UTF-8.
+/// The loudness fields are only meaningful for version 2 and later, and are
+/// in hundredths of a unit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bext {
+    /// A description of the sound
+    pub description: [u8; DESCRIPTION_SIZE],
+    /// The name of the originator, e.g. the recorder
+    pub originator: [u8; ORIGINATOR_SIZE],
+    /// A reference of the originator
+    pub originator_reference: [u8; ORIGINATOR_REFERENCE_SIZE],
+    /// The date of creation, as `yyyy-mm-dd`
+    pub origination_date: [u8; ORIGINATION_DATE_SIZE],
+    /// The time of creation, as `hh:mm:ss`
+    pub origination_time: [u8; ORIGINATION_TIME_SIZE],
+    /// The first sample of the sound, counted in samples since midnight
+    pub time_reference: u64,
+    /// The version of the chunk
+    pub version: u16,
+    /// The SMPTE unique material identifier (UMID)
+    pub umid: [u8; UMID_SIZE],
+    /// The integrated loudness in LUFS
+    pub loudness_value: i16,
+    /// The loudness range in LU
+    pub loudness_range: i16,
+    /// The maximum true peak level in dBTP
+    pub max_true_peak_level: i16,
+    /// The maximum momentary loudness in LUFS
+    pub max_momentary_loudness: i16,
+    /// The maximum short-term loudness in LUFS
+    pub max_short_term_loudness: i16,
+    /// Reserved for later versions, and should be zero
+    pub reserved: [u8; RESERVED_SIZE],
+    /// The coding history, lines of text describing how the sound was
+    /// recorded and processed
+    pub coding_history: Vec<u8>,
+}
+
+impl Bext {
+    /// The time reference in seconds, i.e. the time of day the sound starts.
+    pub fn time_reference_secs(&self, samples_per_sec: SamplesPerSec) -> f64 {
+        self.time_reference as f64 / samples_per_sec.as_u32() as f64
+    }
+
+    /// A one-line summary of the description, originator, and time
+    /// reference, as printed by `zcue info`.
+    pub fn summary(&self, samples_per_sec: SamplesPerSec) -> String {
+        let secs = self.time_reference_secs(samples_per_sec);
+        format!(
+            "`{}` by `{}`, time reference {} ({:02}:{:02}:{:06.3})",
+            self.description(),
+            self.originator(),
+            self.time_reference,
+            (secs / 3600.0).floor(),
+            (secs / 60.0).floor() % 60.0,
+            secs % 60.0,
+        )
+    }
+
+    /// Encode text as a fixed-size field padded with NUL characters, if it
+    /// fits.
+    pub fn text_field<const N: usize>(text: &str) -> Option<[u8; N]> {
+        let mut field = [0u8; N];
+        field
+            .get_mut(..text.len())?
+            .copy_from_slice(text.as_bytes());
+        Some(field)
+    }
+
+    /// The description, decoded.
+    pub fn description(&self) -> String {
+        decode_text(&self.description)
+    }
+
+    /// The name of the originator, decoded.
+    pub fn originator(&self) -> String {
+        decode_text(&self.originator)
+    }
+
+    /// The reference of the originator, decoded.
+    pub fn originator_reference(&self) -> String {
+        decode_text(&self.originator_reference)
+    }
+
+    /// The date of creation, decoded.
+    pub fn origination_date(&self) -> String {
+        decode_text(&self.origination_date)
+    }
+
+    /// The time of creation, decoded.
+    pub fn origination_time(&self) -> String {
+        decode_text(&self.origination_time)
+    }
+
+    /// The coding history, decoded, including any NUL characters.
+    pub fn coding_history(&self) -> String {
+        decode(&self.coding_history)
+    }
+}
+
+#[cfg(test)]
+mod tests;
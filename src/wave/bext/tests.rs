@@ -0,0 +1,176 @@
+use super::*;
+use crate::cue_file::CueFormat;
+use crate::wave::{
+    apply_cue, cue_from_wav, cue_to_wav, offset_timestamps, read, roundtrip, write, ApplyOptions,
+    BitsPerSample, Channels, CuePoints, Format, Info, WaveFile,
+};
+
+fn bext() -> Bext {
+    Bext {
+        description: Bext::text_field("Cockpit ambience").unwrap(),
+        originator: Bext::text_field("Recorder").unwrap(),
+        originator_reference: Bext::text_field("REF0001").unwrap(),
+        origination_date: Bext::text_field("2024-01-21").unwrap(),
+        origination_time: Bext::text_field("10:00:00").unwrap(),
+        // 10:00:00 at 22050 Hz
+        time_reference: 36_000 * 22050,
+        version: 2,
+        umid: [0x42; UMID_SIZE],
+        loudness_value: -2300,
+        loudness_range: 450,
+        max_true_peak_level: -100,
+        max_momentary_loudness: -1800,
+        max_short_term_loudness: -2000,
+        reserved: [0; RESERVED_SIZE],
+        coding_history: b"A=PCM,F=22050,W=8,M=mono\r\n".to_vec(),
+    }
+}
+
+const IXML: &[u8] = b"<?xml version=\"1.0\"?><BWFXML><PROJECT>zcue</PROJECT></BWFXML>\0";
+
+fn wav(bext: Option<Bext>, ixml: Option<Vec<u8>>) -> WaveFile {
+    let fmt = Format {
+        channels: Channels::One,
+        samples_per_sec: SamplesPerSec::Hz22050,
+        bits_per_sample: BitsPerSample::Eight,
+    };
+    WaveFile {
+        cue: Some(vec![0, 22050]),
+        bext,
+        ixml,
+        ..WaveFile::new(fmt, vec![128; 22051])
+    }
+}
+
+#[test]
+fn write_and_read_bext_and_ixml() {
+    let buf = write(&wav(Some(bext()), Some(IXML.to_vec()))).unwrap();
+    let pos = buf.windows(4).position(|w| w == b"bext").unwrap();
+    let size = BEXT_CHUNK_MIN_SIZE + 26;
+    assert_eq!(&buf[pos + 4..pos + 8], &size.to_le_bytes());
+    // the time reference is split into the low and high parts
+    assert_eq!(
+        &buf[pos + 8 + 338..pos + 8 + 346],
+        &793_800_000u64.to_le_bytes()
+    );
+
+    let read_back = read(&buf).unwrap();
+    assert_eq!(read_back.bext, Some(bext()));
+    assert_eq!(read_back.ixml.as_deref(), Some(IXML));
+    assert_eq!(roundtrip(&buf).unwrap(), None);
+}
+
+#[test]
+fn bext_text_field_too_long() {
+    assert_eq!(Bext::text_field::<8>("10:00:00.000"), None);
+    assert_eq!(Bext::text_field::<4>("ab"), Some(*b"ab\0\0"));
+}
+
+#[test]
+fn bext_text_is_kept_as_read() {
+    let mut bext = bext();
+    // a full description in Latin-1, which would be longer than 256 bytes
+    // as UTF-8
+    bext.description = [b'a'; DESCRIPTION_SIZE];
+    bext.description[0] = 0xC9;
+    bext.originator = *b"J\xf6rg\0garbage after the NUL\0\0\0\0\0\0";
+    bext.reserved[0] = 0x5A;
+    bext.coding_history = b"A=PCM,T=Caf\xe9\r\n".to_vec();
+    let ixml = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><BWFXML>\xe9</BWFXML>".to_vec();
+    let buf = write(&wav(Some(bext.clone()), Some(ixml.clone()))).unwrap();
+
+    let read_back = read(&buf).unwrap();
+    assert_eq!(read_back.bext.as_ref(), Some(&bext));
+    assert_eq!(read_back.ixml, Some(ixml));
+    assert_eq!(write(&read_back).unwrap(), buf);
+    assert_eq!(roundtrip(&buf).unwrap(), None);
+
+    let read_back = read_back.bext.unwrap();
+    assert_eq!(read_back.description().chars().next(), Some('\u{c9}'));
+    assert_eq!(read_back.description().len(), 257);
+    assert_eq!(read_back.originator(), "J\u{f6}rg");
+    assert_eq!(read_back.coding_history(), "A=PCM,T=Caf\u{e9}\r\n");
+}
+
+#[test]
+fn bext_time_reference_offset() {
+    let wav = wav(Some(bext()), None);
+    let offset = bext().time_reference_secs(wav.fmt.samples_per_sec);
+    assert_eq!(offset, 36_000.0);
+
+    let mut cue = cue_from_wav(&wav).unwrap();
+    offset_timestamps(&mut cue, offset).unwrap();
+    assert_eq!(cue.timestamps, vec![36_000.0, 36_001.0]);
+    offset_timestamps(&mut cue, -offset).unwrap();
+    assert_eq!(cue.timestamps, vec![0.0, 1.0]);
+
+    let err = offset_timestamps(&mut cue, -0.5).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Cue point 1 at 0s is before the offset of 0.5s"
+    );
+}
+
+#[test]
+fn bext_summary() {
+    let mut bext = bext();
+    bext.time_reference += 22050 * 61 + 11025;
+    assert_eq!(
+        bext.summary(SamplesPerSec::Hz22050),
+        "`Cockpit ambience` by `Recorder`, time reference 795156075 (10:01:01.500)"
+    );
+}
+
+#[test]
+fn bext_time_of_day_offset_keeps_sample_positions() {
+    let mut bext = bext();
+    // one second before midnight, and not on a whole second
+    bext.time_reference = 86_399 * 22050 + 7;
+    let original = vec![0, 1, 12_345, 22_049];
+    let mut wav = WaveFile {
+        cue: Some(original.clone()),
+        ..wav(Some(bext.clone()), None)
+    };
+    let offset = bext.time_reference_secs(wav.fmt.samples_per_sec);
+
+    // extract with the offset, to a cue file
+    let mut cue = cue_from_wav(&wav).unwrap();
+    offset_timestamps(&mut cue, offset).unwrap();
+    cue.sample_points = None;
+    let contents = crate::cue_file::write(CueFormat::Json, &cue).unwrap();
+
+    // apply with the offset, from the cue file
+    let mut cue = crate::cue_file::read(CueFormat::Json, &contents, None).unwrap();
+    offset_timestamps(&mut cue, -offset).unwrap();
+    wav.cue = None;
+    cue_to_wav(&mut wav, cue).unwrap();
+    assert_eq!(wav.cue, Some(original));
+}
+
+#[test]
+fn apply_cue_with_options() {
+    let mut wav = WaveFile {
+        cue: None,
+        info: Some(Info::default()),
+        ..wav(Some(bext()), None)
+    };
+    let cue = CuePoints::new(vec![36_000.0, 36_000.5]);
+    let options = ApplyOptions {
+        bext_offset: true,
+        loops_from_cues: true,
+        strip_info: true,
+    };
+    apply_cue(&mut wav, cue.clone(), options).unwrap();
+    assert_eq!(wav.cue, Some(vec![0, 11025]));
+    let loops = &wav.sampler.as_ref().unwrap().loops;
+    assert_eq!((loops[0].start, loops[0].end), (0, 11024));
+    assert!(wav.info.is_none());
+
+    // without a bext chunk, there is no offset
+    wav.bext = None;
+    let err = apply_cue(&mut wav, cue, options).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Wave file has no bext chunk with a time reference"
+    );
+}
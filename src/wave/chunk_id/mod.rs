@@ -1,9 +1,11 @@
 use std::fmt::{self, Debug};
 
+/// A RIFF chunk ID (FourCC), e.g. `fmt `.
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ChunkId([u8; 4]);
 
 impl ChunkId {
+    /// Create a chunk ID from its bytes.
     #[inline]
     pub const fn new(inner: [u8; 4]) -> Self {
         Self(inner)
@@ -0,0 +1,261 @@
+use super::ChunkId;
+use std::fmt;
+use std::io;
+
+/// A value read from a wave file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    /// An integer field
+    Number(u64),
+    /// A chunk ID or form type
+    ChunkId(ChunkId),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(value) => fmt::Display::fmt(value, f),
+            Self::ChunkId(chunk_id) => write!(f, "{:#?}", chunk_id),
+        }
+    }
+}
+
+impl From<ChunkId> for Value {
+    #[inline]
+    fn from(value: ChunkId) -> Self {
+        Self::ChunkId(value)
+    }
+}
+
+impl From<u32> for Value {
+    #[inline]
+    fn from(value: u32) -> Self {
+        Self::Number(value.into())
+    }
+}
+
+impl From<u64> for Value {
+    #[inline]
+    fn from(value: u64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<u16> for Value {
+    #[inline]
+    fn from(value: u16) -> Self {
+        Self::Number(value.into())
+    }
+}
+
+impl From<u8> for Value {
+    #[inline]
+    fn from(value: u8) -> Self {
+        Self::Number(value.into())
+    }
+}
+
+/// The value(s) a field was expected to have.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expected {
+    /// Exactly this value
+    Equal(Value),
+    /// Any of these values
+    OneOf(Vec<Value>),
+    /// This value or greater
+    AtLeast(Value),
+    /// This value or less
+    AtMost(Value),
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Equal(value) => write!(f, "== {}", value),
+            Self::AtLeast(value) => write!(f, ">= {}", value),
+            Self::AtMost(value) => write!(f, "<= {}", value),
+            Self::OneOf(values) => {
+                f.write_str("in [")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    fmt::Display::fmt(value, f)?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+/// An error reading a wave file.
+///
+/// Offsets are from the start of the file, in bytes.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ReadError {
+    /// The file ended unexpectedly
+    Truncated {
+        /// The offset of the read that failed
+        offset: u64,
+    },
+    /// Reading the file failed
+    Io {
+        /// The offset of the read that failed
+        offset: u64,
+        /// The underlying error
+        source: io::Error,
+    },
+    /// The file or audio data is too large to read into memory
+    TooLarge,
+    /// A field is invalid, i.e. the file is malformed
+    Invalid {
+        /// The name of the field
+        field: &'static str,
+        /// The value(s) the field was expected to have
+        expected: Expected,
+        /// The value of the field
+        actual: Value,
+        /// The offset of the field
+        offset: u64,
+    },
+    /// A field is valid, but not supported, e.g. the sample rate
+    Unsupported {
+        /// The name of the field
+        field: &'static str,
+        /// The value(s) the field was expected to have
+        expected: Expected,
+        /// The value of the field
+        actual: Value,
+        /// The offset of the field
+        offset: u64,
+    },
+    /// A chunk that may only appear once appeared again
+    DuplicateChunk {
+        /// The chunk ID
+        chunk_id: ChunkId,
+        /// The offset of the chunk header
+        offset: u64,
+    },
+    /// A chunk is not known
+    UnknownChunk {
+        /// The chunk ID
+        chunk_id: ChunkId,
+        /// The offset of the chunk header
+        offset: u64,
+    },
+    /// A required chunk is missing
+    MissingChunk {
+        /// The chunk ID
+        chunk_id: ChunkId,
+    },
+}
+
+impl ReadError {
+    /// The offset of the error, if any.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Self::Truncated { offset }
+            | Self::Io { offset, .. }
+            | Self::Invalid { offset, .. }
+            | Self::Unsupported { offset, .. }
+            | Self::DuplicateChunk { offset, .. }
+            | Self::UnknownChunk { offset, .. } => Some(*offset),
+            Self::TooLarge | Self::MissingChunk { .. } => None,
+        }
+    }
+
+    /// The kind of the error in kebab case, e.g. `invalid`, for
+    /// machine-readable output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Truncated { .. } => "truncated",
+            Self::Io { .. } => "io",
+            Self::TooLarge => "too-large",
+            Self::Invalid { .. } => "invalid",
+            Self::Unsupported { .. } => "unsupported",
+            Self::DuplicateChunk { .. } => "duplicate-chunk",
+            Self::UnknownChunk { .. } => "unknown-chunk",
+            Self::MissingChunk { .. } => "missing-chunk",
+        }
+    }
+
+    pub(crate) fn invalid(
+        field: &'static str,
+        expected: Expected,
+        actual: impl Into<Value>,
+        offset: u64,
+    ) -> Self {
+        Self::Invalid {
+            field,
+            expected,
+            actual: actual.into(),
+            offset,
+        }
+    }
+
+    pub(crate) fn unsupported(
+        field: &'static str,
+        expected: Expected,
+        actual: impl Into<Value>,
+        offset: u64,
+    ) -> Self {
+        Self::Unsupported {
+            field,
+            expected,
+            actual: actual.into(),
+            offset,
+        }
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated { offset } => write!(f, "Unexpected end of file (at {})", offset),
+            Self::Io { offset, .. } => write!(f, "Error reading file (at {})", offset),
+            Self::TooLarge => f.write_str("File is too large to read into memory"),
+            Self::Invalid {
+                field,
+                expected,
+                actual,
+                offset,
+            } => write!(
+                f,
+                "Expected `{}` {}, but was {} (at {})",
+                field, expected, actual, offset
+            ),
+            Self::Unsupported {
+                field,
+                expected,
+                actual,
+                offset,
+            } => write!(
+                f,
+                "Unsupported `{}`: expected {}, but was {} (at {})",
+                field, expected, actual, offset
+            ),
+            Self::DuplicateChunk { chunk_id, offset } => {
+                write!(f, "Duplicate chunk `{}` (at {})", chunk_id, offset)
+            }
+            Self::UnknownChunk { chunk_id, offset } => {
+                write!(f, "Unknown chunk `{}` (at {})", chunk_id, offset)
+            }
+            Self::MissingChunk { chunk_id } => {
+                write!(f, "WAVE file contains no `{}` chunk", chunk_id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
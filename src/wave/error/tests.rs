@@ -0,0 +1,41 @@
+use super::*;
+
+#[test]
+fn read_error_display_invalid() {
+    let err = ReadError::invalid(
+        "RIFF chunk ID",
+        Expected::Equal(ChunkId::new(*b"RIFF").into()),
+        ChunkId::new(*b"RIFX"),
+        0,
+    );
+    assert_eq!(
+        err.to_string(),
+        "Expected `RIFF chunk ID` == \"RIFF\", but was \"RIFX\" (at 0)"
+    );
+    assert_eq!(err.offset(), Some(0));
+    assert_eq!(err.kind(), "invalid");
+}
+
+#[test]
+fn read_error_display_unsupported() {
+    let err = ReadError::unsupported(
+        "FMT bits per sample",
+        Expected::OneOf(vec![8u16.into(), 16u16.into()]),
+        24u16,
+        34,
+    );
+    assert_eq!(
+        err.to_string(),
+        "Unsupported `FMT bits per sample`: expected in [8, 16], but was 24 (at 34)"
+    );
+    assert_eq!(err.kind(), "unsupported");
+}
+
+#[test]
+fn read_error_display_at_least() {
+    let err = ReadError::invalid("CUE chunk size", Expected::AtLeast(28u32.into()), 4u32, 40);
+    assert_eq!(
+        err.to_string(),
+        "Expected `CUE chunk size` >= 28, but was 4 (at 40)"
+    );
+}
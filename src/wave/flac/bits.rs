@@ -0,0 +1,191 @@
+use crate::wave::ReadError;
+
+type Result<T> = std::result::Result<T, ReadError>;
+
+/// Reads bits most significant first, as FLAC frames are coded.
+pub(super) struct BitReader<'a> {
+    buf: &'a [u8],
+    /// The offset of the current byte
+    pos: usize,
+    /// The number of bits of the current byte already read
+    bit: u32,
+}
+
+impl<'a> BitReader<'a> {
+    pub(super) const fn new(buf: &'a [u8], pos: usize) -> Self {
+        Self { buf, pos, bit: 0 }
+    }
+
+    /// The offset of the current byte.
+    #[inline]
+    pub(super) fn offset(&self) -> usize {
+        self.pos
+    }
+
+    /// The bytes from the offset to the current byte, which must be aligned.
+    #[inline]
+    pub(super) fn bytes_since(&self, offset: usize) -> &'a [u8] {
+        &self.buf[offset..self.pos]
+    }
+
+    fn truncated(&self) -> ReadError {
+        ReadError::Truncated {
+            // Cast safety: u64 >= usize
+            offset: self.pos as u64,
+        }
+    }
+
+    /// Read bytes, which must be aligned.
+    pub(super) fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        debug_assert_eq!(self.bit, 0);
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.buf.len());
+        let end = end.ok_or(ReadError::Truncated {
+            // Cast safety: u64 >= usize
+            offset: self.buf.len() as u64,
+        })?;
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    #[inline]
+    pub(super) fn read_bit(&mut self) -> Result<bool> {
+        let byte = *self.buf.get(self.pos).ok_or_else(|| self.truncated())?;
+        let value = (byte >> (7 - self.bit)) & 1;
+        self.bit += 1;
+        if self.bit == 8 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+        Ok(value == 1)
+    }
+
+    /// Read up to 64 bits as an unsigned integer.
+    pub(super) fn read_bits(&mut self, count: u32) -> Result<u64> {
+        let mut value = 0u64;
+        let mut remaining = count;
+        while remaining > 0 {
+            let byte = *self.buf.get(self.pos).ok_or_else(|| self.truncated())?;
+            let available = 8 - self.bit;
+            let take = available.min(remaining);
+            let bits = (byte >> (available - take)) & (0xFFu8 >> (8 - take));
+            value = (value << take) | u64::from(bits);
+            remaining -= take;
+            self.bit += take;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Ok(value)
+    }
+
+    #[inline]
+    pub(super) fn read_u32(&mut self, count: u32) -> Result<u32> {
+        // Cast safety: at most 32 bits are read
+        self.read_bits(count.min(32)).map(|value| value as u32)
+    }
+
+    /// Read up to 32 bits as a two's complement signed integer.
+    pub(super) fn read_signed(&mut self, count: u32) -> Result<i32> {
+        if count == 0 {
+            return Ok(0);
+        }
+        let value = self.read_bits(count.min(32))?;
+        let shift = 64 - count.min(32);
+        // Cast safety: sign extension, and at most 32 bits
+        Ok(((value << shift) as i64 >> shift) as i32)
+    }
+
+    /// Read the number of zero bits before a one bit.
+    pub(super) fn read_unary(&mut self) -> Result<u32> {
+        let mut count = 0u32;
+        while !self.read_bit()? {
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Skip to the next byte, returning the skipped bits.
+    pub(super) fn align(&mut self) -> Result<u32> {
+        if self.bit == 0 {
+            return Ok(0);
+        }
+        self.read_u32(8 - self.bit)
+    }
+}
+
+/// Writes bits most significant first.
+pub(super) struct BitWriter {
+    buf: Vec<u8>,
+    acc: u64,
+    /// The number of bits in the accumulator, less than 8 between writes
+    bits: u32,
+}
+
+impl BitWriter {
+    pub(super) const fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            acc: 0,
+            bits: 0,
+        }
+    }
+
+    /// Write the low bits of the value, up to 32 bits.
+    pub(super) fn write_bits(&mut self, value: u64, count: u32) {
+        debug_assert!(count <= 32);
+        if count == 0 {
+            return;
+        }
+        let mask = (1u64 << count) - 1;
+        self.acc = (self.acc << count) | (value & mask);
+        self.bits += count;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            // Cast safety: truncation to the top byte is intended
+            self.buf.push((self.acc >> self.bits) as u8);
+        }
+        self.acc &= (1u64 << self.bits) - 1;
+    }
+
+    /// Write the low bits of a two's complement signed integer.
+    #[inline]
+    pub(super) fn write_signed(&mut self, value: i32, count: u32) {
+        // Cast safety: two's complement, the high bits are masked
+        self.write_bits(value as u64, count);
+    }
+
+    /// Write zero bits followed by a one bit.
+    pub(super) fn write_unary(&mut self, count: u32) {
+        let mut remaining = count;
+        while remaining >= 32 {
+            self.write_bits(0, 32);
+            remaining -= 32;
+        }
+        self.write_bits(1, remaining + 1);
+    }
+
+    /// Write zero bits to the next byte.
+    pub(super) fn align(&mut self) {
+        if self.bits > 0 {
+            self.write_bits(0, 8 - self.bits);
+        }
+    }
+
+    /// The bytes written, which must be aligned.
+    #[inline]
+    pub(super) fn bytes(&self) -> &[u8] {
+        debug_assert_eq!(self.bits, 0);
+        &self.buf
+    }
+
+    #[inline]
+    pub(super) fn into_bytes(self) -> Vec<u8> {
+        debug_assert_eq!(self.bits, 0);
+        self.buf
+    }
+}
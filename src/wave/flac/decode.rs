@@ -0,0 +1,382 @@
+use super::bits::BitReader;
+use super::{crc16, crc8, FRAME_SYNC};
+use crate::wave::error::{Expected, ReadError, Value};
+
+type Result<T> = std::result::Result<T, ReadError>;
+
+fn offset(read: &BitReader<'_>) -> u64 {
+    // Cast safety: u64 >= usize
+    read.offset() as u64
+}
+
+/// Read the frame or sample number, coded like UTF-8 (but up to 36 bits).
+fn read_coded_number(read: &mut BitReader<'_>) -> Result<u64> {
+    let first = read.read_u32(8)?;
+    let ones = (first << 24).leading_ones();
+    let extra = match ones {
+        0 => 0,
+        2..=7 => ones - 1,
+        _ => {
+            return Err(ReadError::invalid(
+                "FLAC frame number",
+                Expected::AtMost(0xFEu32.into()),
+                first,
+                offset(read) - 1,
+            ));
+        }
+    };
+    let mut value = u64::from(first & (0x7F >> ones));
+    for _ in 0..extra {
+        let byte = read.read_u32(8)?;
+        if byte & 0xC0 != 0x80 {
+            return Err(ReadError::invalid(
+                "FLAC frame number",
+                Expected::Equal(0x80u32.into()),
+                byte & 0xC0,
+                offset(read) - 1,
+            ));
+        }
+        value = (value << 6) | u64::from(byte & 0x3F);
+    }
+    Ok(value)
+}
+
+fn read_residual(
+    read: &mut BitReader<'_>,
+    block_size: usize,
+    order: usize,
+    residual: &mut Vec<i32>,
+) -> Result<()> {
+    let method = read.read_u32(2)?;
+    let (param_bits, escape) = match method {
+        0 => (4, 0xF),
+        1 => (5, 0x1F),
+        _ => {
+            return Err(ReadError::invalid(
+                "FLAC residual coding method",
+                Expected::OneOf(vec![0u32.into(), 1u32.into()]),
+                method,
+                offset(read),
+            ));
+        }
+    };
+    let partition_order = read.read_u32(4)?;
+    let partition_size = block_size >> partition_order;
+    if partition_size << partition_order != block_size || partition_size < order {
+        return Err(ReadError::invalid(
+            "FLAC partition order",
+            Expected::AtMost(Value::Number(block_size.trailing_zeros().into())),
+            partition_order,
+            offset(read),
+        ));
+    }
+
+    for partition in 0..(1usize << partition_order) {
+        // the warm-up samples are part of the first partition
+        let count = if partition == 0 {
+            partition_size - order
+        } else {
+            partition_size
+        };
+        let param = read.read_u32(param_bits)?;
+        if param == escape {
+            let bits = read.read_u32(5)?;
+            for _ in 0..count {
+                residual.push(read.read_signed(bits)?);
+            }
+            continue;
+        }
+        for _ in 0..count {
+            let quotient = read.read_unary()?;
+            let remainder = read.read_u32(param)?;
+            let value = (u64::from(quotient) << param) | u64::from(remainder);
+            // zigzag decoding
+            // Cast safety: valid streams have 32-bit residuals
+            let value = (value >> 1) as i64 ^ -((value & 1) as i64);
+            residual.push(value as i32);
+        }
+    }
+    Ok(())
+}
+
+/// Restore the samples from the warm-up samples and the residual, with the
+/// coefficients of the predictor (most recent sample first).
+fn restore(samples: &mut Vec<i32>, residual: &[i32], coefficients: &[i64], shift: u32) {
+    let order = coefficients.len();
+    for &value in residual {
+        let len = samples.len();
+        let prediction: i64 = coefficients
+            .iter()
+            .zip(samples[len - order..].iter().rev())
+            .map(|(&coefficient, &sample)| coefficient * i64::from(sample))
+            .sum();
+        // Cast safety: valid streams have 32-bit samples
+        samples.push((i64::from(value) + (prediction >> shift)) as i32);
+    }
+}
+
+/// The coefficients of the fixed predictors, most recent sample first.
+pub(super) const FIXED_COEFFICIENTS: [&[i64]; 5] =
+    [&[], &[1], &[2, -1], &[3, -3, 1], &[4, -6, 4, -1]];
+
+fn read_subframe(
+    read: &mut BitReader<'_>,
+    block_size: usize,
+    bits_per_sample: u32,
+) -> Result<Vec<i32>> {
+    let subframe_pos = offset(read);
+    let padding = read.read_bit()?;
+    if padding {
+        return Err(ReadError::invalid(
+            "FLAC subframe padding",
+            Expected::Equal(0u32.into()),
+            1u32,
+            subframe_pos,
+        ));
+    }
+    let subframe_type = read.read_u32(6)?;
+    let wasted_bits = if read.read_bit()? {
+        read.read_unary()? + 1
+    } else {
+        0
+    };
+    if wasted_bits >= bits_per_sample {
+        return Err(ReadError::invalid(
+            "FLAC wasted bits",
+            Expected::AtMost((bits_per_sample - 1).into()),
+            wasted_bits,
+            subframe_pos,
+        ));
+    }
+    let bits = bits_per_sample - wasted_bits;
+
+    let mut samples = Vec::with_capacity(block_size);
+    match subframe_type {
+        // constant
+        0 => {
+            let value = read.read_signed(bits)?;
+            samples.resize(block_size, value);
+        }
+        // verbatim
+        1 => {
+            for _ in 0..block_size {
+                samples.push(read.read_signed(bits)?);
+            }
+        }
+        // fixed predictor
+        0b001000..=0b001100 => {
+            // Cast safety: at most 4
+            let order = (subframe_type & 0b111) as usize;
+            if order > block_size {
+                return Err(ReadError::invalid(
+                    "FLAC predictor order",
+                    // Cast safety: u64 >= usize
+                    Expected::AtMost(Value::Number(block_size as u64)),
+                    subframe_type & 0b111,
+                    subframe_pos,
+                ));
+            }
+            for _ in 0..order {
+                samples.push(read.read_signed(bits)?);
+            }
+            let mut residual = Vec::with_capacity(block_size - order);
+            read_residual(read, block_size, order, &mut residual)?;
+            restore(&mut samples, &residual, FIXED_COEFFICIENTS[order], 0);
+        }
+        // linear predictor
+        0b100000..=0b111111 => {
+            // Cast safety: at most 32
+            let order = (subframe_type & 0b11111) as usize + 1;
+            if order > block_size {
+                return Err(ReadError::invalid(
+                    "FLAC predictor order",
+                    // Cast safety: u64 >= usize
+                    Expected::AtMost(Value::Number(block_size as u64)),
+                    // Cast safety: at most 32
+                    order as u32,
+                    subframe_pos,
+                ));
+            }
+            for _ in 0..order {
+                samples.push(read.read_signed(bits)?);
+            }
+            let precision = read.read_u32(4)?;
+            if precision == 0b1111 {
+                return Err(ReadError::invalid(
+                    "FLAC coefficient precision",
+                    Expected::AtMost(0b1110u32.into()),
+                    precision,
+                    offset(read),
+                ));
+            }
+            let shift = read.read_signed(5)?;
+            if shift < 0 {
+                return Err(ReadError::unsupported(
+                    "FLAC coefficient shift",
+                    Expected::AtLeast(0u32.into()),
+                    // Cast safety: only shown
+                    shift as u32,
+                    offset(read),
+                ));
+            }
+            let mut coefficients = Vec::with_capacity(order);
+            for _ in 0..order {
+                coefficients.push(i64::from(read.read_signed(precision + 1)?));
+            }
+            let mut residual = Vec::with_capacity(block_size - order);
+            read_residual(read, block_size, order, &mut residual)?;
+            // Cast safety: checked to be positive
+            restore(&mut samples, &residual, &coefficients, shift as u32);
+        }
+        _ => {
+            return Err(ReadError::invalid(
+                "FLAC subframe type",
+                Expected::OneOf(vec![0u32.into(), 1u32.into()]),
+                subframe_type,
+                subframe_pos,
+            ));
+        }
+    }
+
+    if wasted_bits > 0 {
+        samples
+            .iter_mut()
+            .for_each(|sample| *sample <<= wasted_bits);
+    }
+    Ok(samples)
+}
+
+/// Decode a mono frame, appending the samples.
+pub(super) fn read_frame(
+    read: &mut BitReader<'_>,
+    bits_per_sample: u32,
+    samples: &mut Vec<i32>,
+) -> Result<()> {
+    let frame_start = read.offset();
+    let frame_pos = offset(read);
+
+    let sync = read.read_u32(14)?;
+    if sync != FRAME_SYNC {
+        return Err(ReadError::invalid(
+            "FLAC frame sync",
+            Expected::Equal(FRAME_SYNC.into()),
+            sync,
+            frame_pos,
+        ));
+    }
+    let _reserved = read.read_bit()?;
+    let _variable_block_size = read.read_bit()?;
+
+    let block_size_code = read.read_u32(4)?;
+    let sample_rate_code = read.read_u32(4)?;
+    let channel_assignment = read.read_u32(4)?;
+    if channel_assignment != 0 {
+        return Err(ReadError::unsupported(
+            "FLAC channel assignment",
+            Expected::Equal(0u32.into()),
+            channel_assignment,
+            frame_pos + 3,
+        ));
+    }
+    let sample_size_code = read.read_u32(3)?;
+    let sample_size = match sample_size_code {
+        0 => bits_per_sample,
+        1 => 8,
+        2 => 12,
+        4 => 16,
+        5 => 20,
+        6 => 24,
+        7 => 32,
+        _ => 0,
+    };
+    if sample_size != bits_per_sample {
+        return Err(ReadError::invalid(
+            "FLAC frame sample size",
+            Expected::Equal(bits_per_sample.into()),
+            sample_size,
+            frame_pos + 3,
+        ));
+    }
+    let _reserved = read.read_bit()?;
+
+    let _number = read_coded_number(read)?;
+    let block_size = match block_size_code {
+        1 => 192,
+        2..=5 => 576 << (block_size_code - 2),
+        6 => read.read_u32(8)? + 1,
+        7 => read.read_u32(16)? + 1,
+        8..=15 => 256 << (block_size_code - 8),
+        _ => {
+            return Err(ReadError::invalid(
+                "FLAC block size",
+                Expected::AtLeast(1u32.into()),
+                block_size_code,
+                frame_pos + 2,
+            ));
+        }
+    };
+    // the sample rate is the same as the stream info
+    match sample_rate_code {
+        12 => {
+            read.read_u32(8)?;
+        }
+        13 | 14 => {
+            read.read_u32(16)?;
+        }
+        15 => {
+            return Err(ReadError::invalid(
+                "FLAC sample rate",
+                Expected::AtMost(14u32.into()),
+                sample_rate_code,
+                frame_pos + 2,
+            ));
+        }
+        _ => {}
+    }
+
+    let expected_crc = crc8(read.bytes_since(frame_start));
+    let crc = read.read_u32(8)?;
+    if crc != u32::from(expected_crc) {
+        return Err(ReadError::invalid(
+            "FLAC frame header CRC-8",
+            Expected::Equal(expected_crc.into()),
+            crc,
+            offset(read) - 1,
+        ));
+    }
+
+    // Cast safety: usize >= u32
+    let subframe = read_subframe(read, block_size as usize, bits_per_sample)?;
+    read.align()?;
+
+    let expected_crc = crc16(read.bytes_since(frame_start));
+    let crc = read.read_u32(16)?;
+    if crc != u32::from(expected_crc) {
+        return Err(ReadError::invalid(
+            "FLAC frame CRC-16",
+            Expected::Equal(expected_crc.into()),
+            crc,
+            offset(read) - 2,
+        ));
+    }
+
+    // predicted samples can be outside the range of the sample size
+    let max = 1i64 << (bits_per_sample - 1);
+    let out_of_range = subframe.iter().find(|&&sample| {
+        let sample = i64::from(sample);
+        sample < -max || sample >= max
+    });
+    if let Some(&sample) = out_of_range {
+        let limit = if sample < 0 { max } else { max - 1 };
+        return Err(ReadError::invalid(
+            "FLAC sample magnitude",
+            // Cast safety: the limit is positive
+            Expected::AtMost((limit as u64).into()),
+            sample.unsigned_abs(),
+            frame_pos,
+        ));
+    }
+
+    samples.extend_from_slice(&subframe);
+    Ok(())
+}
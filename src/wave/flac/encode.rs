@@ -0,0 +1,265 @@
+use super::bits::BitWriter;
+use super::decode::FIXED_COEFFICIENTS;
+use super::{crc16, crc8, FRAME_SYNC};
+
+/// The number of samples per frame, the same as the reference encoder.
+pub(super) const BLOCK_SIZE: usize = 4096;
+const MAX_PARTITION_ORDER: u32 = 8;
+/// The largest Rice parameter, since 31 is the escape code.
+const MAX_RICE_PARAM: u32 = 30;
+/// The largest Rice parameter of the 4-bit coding method, since 15 is the
+/// escape code.
+const MAX_RICE_PARAM_4_BIT: u32 = 14;
+
+/// The Rice coding of a residual.
+struct Rice {
+    partition_order: u32,
+    params: Vec<u32>,
+}
+
+impl Rice {
+    fn param_bits(&self) -> u32 {
+        if self
+            .params
+            .iter()
+            .any(|&param| param > MAX_RICE_PARAM_4_BIT)
+        {
+            5
+        } else {
+            4
+        }
+    }
+}
+
+/// How a subframe is coded.
+enum Subframe {
+    Constant,
+    Verbatim,
+    Fixed { order: usize, rice: Rice },
+}
+
+/// Zigzag encoding, which maps small negative and positive numbers to small
+/// unsigned numbers.
+fn zigzag(value: i64) -> u64 {
+    // Cast safety: two's complement
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn residual(samples: &[i32], order: usize) -> Vec<u64> {
+    let coefficients = FIXED_COEFFICIENTS[order];
+    (order..samples.len())
+        .map(|index| {
+            let prediction: i64 = coefficients
+                .iter()
+                .zip(samples[..index].iter().rev())
+                .map(|(&coefficient, &sample)| coefficient * i64::from(sample))
+                .sum();
+            zigzag(i64::from(samples[index]) - prediction)
+        })
+        .collect()
+}
+
+/// The best Rice parameter of a partition, and its size in bits.
+fn rice_param(values: &[u64]) -> (u32, u64) {
+    if values.is_empty() {
+        return (0, 0);
+    }
+    // Cast safety: u64 >= usize
+    let count = values.len() as u64;
+    let sum: u64 = values.iter().sum();
+    // the best parameter is close to the log2 of the mean
+    let mean = sum / count;
+    let estimate = if mean == 0 {
+        0
+    } else {
+        (63 - mean.leading_zeros()).min(MAX_RICE_PARAM)
+    };
+    (estimate.saturating_sub(1)..=(estimate + 1).min(MAX_RICE_PARAM))
+        .map(|param| {
+            let bits = count * u64::from(param + 1)
+                + values.iter().map(|value| value >> param).sum::<u64>();
+            (param, bits)
+        })
+        .min_by_key(|&(_, bits)| bits)
+        // Panic safety: the range is never empty
+        .unwrap()
+}
+
+/// The best Rice coding of the residual, and its size in bits.
+fn rice(values: &[u64], block_size: usize, order: usize) -> (Rice, u64) {
+    let mut best: Option<(Rice, u64)> = None;
+    for partition_order in 0..=MAX_PARTITION_ORDER {
+        let partition_size = block_size >> partition_order;
+        if partition_size << partition_order != block_size || partition_size < order {
+            break;
+        }
+        let mut params = Vec::with_capacity(1 << partition_order);
+        let mut bits = 0;
+        let mut start = 0;
+        for partition in 0..(1usize << partition_order) {
+            // the warm-up samples are part of the first partition
+            let count = if partition == 0 {
+                partition_size - order
+            } else {
+                partition_size
+            };
+            let (param, partition_bits) = rice_param(&values[start..start + count]);
+            params.push(param);
+            bits += partition_bits;
+            start += count;
+        }
+        let rice = Rice {
+            partition_order,
+            params,
+        };
+        // the coding method and partition order, and the parameters
+        bits += 2 + 4 + u64::from(rice.param_bits()) * (1u64 << partition_order);
+        if best
+            .as_ref()
+            .map_or(true, |(_, best_bits)| bits < *best_bits)
+        {
+            best = Some((rice, bits));
+        }
+    }
+    // Panic safety: partition order 0 is always valid, as the order is at
+    // most the block size
+    best.unwrap()
+}
+
+/// Choose the smallest coding of the subframe.
+fn choose_subframe(samples: &[i32], bits_per_sample: u32) -> Subframe {
+    if samples.windows(2).all(|pair| pair[0] == pair[1]) {
+        return Subframe::Constant;
+    }
+    // Cast safety: u64 >= usize
+    let block_size = samples.len();
+    let mut best = (
+        Subframe::Verbatim,
+        block_size as u64 * u64::from(bits_per_sample),
+    );
+    for order in 0..=block_size.min(FIXED_COEFFICIENTS.len() - 1) {
+        let values = residual(samples, order);
+        let (rice, rice_bits) = rice(&values, block_size, order);
+        let bits = order as u64 * u64::from(bits_per_sample) + rice_bits;
+        if bits < best.1 {
+            best = (Subframe::Fixed { order, rice }, bits);
+        }
+    }
+    best.0
+}
+
+fn write_subframe(write: &mut BitWriter, samples: &[i32], bits_per_sample: u32) {
+    let subframe = choose_subframe(samples, bits_per_sample);
+    let subframe_type = match &subframe {
+        Subframe::Constant => 0b000000,
+        Subframe::Verbatim => 0b000001,
+        // Cast safety: at most 4
+        Subframe::Fixed { order, .. } => 0b001000 | *order as u64,
+    };
+    // the padding bit, the type, and no wasted bits
+    write.write_bits(0, 1);
+    write.write_bits(subframe_type, 6);
+    write.write_bits(0, 1);
+    match subframe {
+        Subframe::Constant => write.write_signed(samples[0], bits_per_sample),
+        Subframe::Verbatim => {
+            for &sample in samples {
+                write.write_signed(sample, bits_per_sample);
+            }
+        }
+        Subframe::Fixed { order, rice } => {
+            for &sample in &samples[..order] {
+                write.write_signed(sample, bits_per_sample);
+            }
+            let param_bits = rice.param_bits();
+            write.write_bits(if param_bits == 5 { 1 } else { 0 }, 2);
+            write.write_bits(rice.partition_order.into(), 4);
+            let values = residual(samples, order);
+            let partition_size = samples.len() >> rice.partition_order;
+            let mut start = 0;
+            for (partition, &param) in rice.params.iter().enumerate() {
+                let count = if partition == 0 {
+                    partition_size - order
+                } else {
+                    partition_size
+                };
+                write.write_bits(param.into(), param_bits);
+                for &value in &values[start..start + count] {
+                    // Cast safety: the parameter is chosen so the quotient
+                    // is small
+                    write.write_unary((value >> param) as u32);
+                    write.write_bits(value, param);
+                }
+                start += count;
+            }
+        }
+    }
+}
+
+/// Write the frame or sample number, coded like UTF-8 (but up to 36 bits).
+fn write_coded_number(write: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        write.write_bits(value, 8);
+        return;
+    }
+    // the first byte of `len` bytes holds `7 - len` bits, and the others 6
+    let bits = 64 - value.leading_zeros();
+    // Panic safety: the frame number is at most 31 bits
+    let len = (2..=7u32).find(|&len| 5 * len + 1 >= bits).unwrap();
+    let prefix = (0xFF00u64 >> len) & 0xFF;
+    write.write_bits(prefix | (value >> (6 * (len - 1))), 8);
+    for index in (0..len - 1).rev() {
+        write.write_bits(0x80 | ((value >> (6 * index)) & 0x3F), 8);
+    }
+}
+
+/// Encode a mono frame with a fixed block size.
+pub(super) fn write_frame(
+    samples: &[i32],
+    number: u64,
+    bits_per_sample: u32,
+    samples_per_sec: u32,
+) -> Vec<u8> {
+    let mut write = BitWriter::new();
+    write.write_bits(FRAME_SYNC.into(), 14);
+    // reserved, and the fixed blocking strategy
+    write.write_bits(0, 2);
+
+    // Cast safety: the block size is at most 4096
+    let block_size = samples.len() as u64;
+    let (block_size_code, block_size_bits) = match block_size {
+        4096 => (12, 0),
+        0..=256 => (6, 8),
+        _ => (7, 16),
+    };
+    let (sample_rate_code, sample_rate_bits) = match samples_per_sec {
+        22050 => (6, 0),
+        44100 => (9, 0),
+        0..=0xFFFF => (13, 16),
+        // from the stream info
+        _ => (0, 0),
+    };
+    write.write_bits(block_size_code, 4);
+    write.write_bits(sample_rate_code, 4);
+    // mono
+    write.write_bits(0, 4);
+    let sample_size_code = match bits_per_sample {
+        8 => 1,
+        16 => 4,
+        // from the stream info
+        _ => 0,
+    };
+    write.write_bits(sample_size_code, 3);
+    write.write_bits(0, 1);
+    write_coded_number(&mut write, number);
+    write.write_bits(block_size - 1, block_size_bits);
+    write.write_bits(samples_per_sec.into(), sample_rate_bits);
+    let crc = crc8(write.bytes());
+    write.write_bits(crc.into(), 8);
+
+    write_subframe(&mut write, samples, bits_per_sample);
+    write.align();
+    let crc = crc16(write.bytes());
+    write.write_bits(crc.into(), 16);
+    write.into_bytes()
+}
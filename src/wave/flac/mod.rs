@@ -0,0 +1,589 @@
+//! FLAC files, which are decoded to and encoded from the wave file model.
+//!
+//! Only mono streams with 8 or 16 bits per sample are supported, the same as
+//! wave files. Cue points are read from `CUEPOINT` Vorbis comments (the
+//! sample offset of each cue point, in order), or the `CUESHEET` metadata
+//! block, or a `CUESHEET` Vorbis comment (an embedded cue sheet). They are
+//! written as both `CUEPOINT` comments and a `CUESHEET` block.
+mod bits;
+mod decode;
+mod encode;
+
+use super::error::{Expected, ReadError};
+use super::{
+    BitsPerSample, Channels, ChunkId, Container, Encoding, Format, Info, SamplesPerSec, WaveFile,
+};
+use crate::error::bail;
+use bits::{BitReader, BitWriter};
+
+type Result<T> = std::result::Result<T, ReadError>;
+
+pub(crate) const FLAC_MAGIC: [u8; 4] = *b"fLaC";
+const FRAME_SYNC: u32 = 0x3FFE;
+const BLOCK_TYPE_STREAMINFO: u32 = 0;
+const BLOCK_TYPE_VORBIS_COMMENT: u32 = 4;
+const BLOCK_TYPE_CUESHEET: u32 = 5;
+const BLOCK_TYPE_INVALID: u32 = 127;
+const STREAMINFO_SIZE: u32 = 34;
+/// The track number of the lead-out track of a cue sheet that isn't for a
+/// CD.
+const LEAD_OUT_TRACK: u8 = 255;
+/// The number of tracks of a cue sheet, excluding the lead-out track.
+const MAX_TRACKS: usize = 254;
+/// CD frames (sectors) per second, the unit of embedded cue sheets.
+const CD_FRAMES_PER_SEC: u64 = 75;
+const CUEPOINT_TAG: &str = "CUEPOINT";
+const CUESHEET_TAG: &str = "CUESHEET";
+
+/// The Vorbis comments that map to `INFO` entries.
+const INFO_TAGS: [(ChunkId, &str); 8] = [
+    (ChunkId::new(*b"INAM"), "TITLE"),
+    (ChunkId::new(*b"IART"), "ARTIST"),
+    (ChunkId::new(*b"IPRD"), "ALBUM"),
+    (ChunkId::new(*b"ICMT"), "COMMENT"),
+    (ChunkId::new(*b"ICOP"), "COPYRIGHT"),
+    (ChunkId::new(*b"ICRD"), "DATE"),
+    (ChunkId::new(*b"IGNR"), "GENRE"),
+    (ChunkId::new(*b"ISFT"), "ENCODER"),
+];
+
+/// The CRC-8 of frame headers (polynomial `x^8 + x^2 + x + 1`).
+fn crc8(bytes: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in bytes {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// The CRC-16 of frames (polynomial `x^16 + x^15 + x^2 + 1`).
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in bytes {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+fn offset(read: &BitReader<'_>) -> u64 {
+    // Cast safety: u64 >= usize
+    read.offset() as u64
+}
+
+struct StreamInfo {
+    samples_per_sec: SamplesPerSec,
+    bits_per_sample: BitsPerSample,
+    /// The total number of samples, or zero if unknown
+    sample_count: u64,
+}
+
+fn read_stream_info(read: &mut BitReader<'_>, size: u32) -> Result<StreamInfo> {
+    if size != STREAMINFO_SIZE {
+        return Err(ReadError::invalid(
+            "FLAC STREAMINFO size",
+            Expected::Equal(STREAMINFO_SIZE.into()),
+            size,
+            offset(read) - 3,
+        ));
+    }
+    // the block and frame sizes are only hints
+    read.read_bytes(10)?;
+
+    let samples_per_sec_pos = offset(read);
+    let samples_per_sec = read.read_u32(20)?;
+    let samples_per_sec = SamplesPerSec::from_u32(samples_per_sec).ok_or_else(|| {
+        let expected = SamplesPerSec::ALL
+            .iter()
+            .map(|hz| hz.as_u32().into())
+            .collect();
+        ReadError::unsupported(
+            "FLAC sample rate",
+            Expected::OneOf(expected),
+            samples_per_sec,
+            samples_per_sec_pos,
+        )
+    })?;
+
+    let channels = read.read_u32(3)? + 1;
+    // Cast safety: at most 8
+    Channels::from_u16(channels as u16).ok_or_else(|| {
+        ReadError::unsupported(
+            "FLAC channels",
+            Expected::Equal(1u32.into()),
+            channels,
+            samples_per_sec_pos + 2,
+        )
+    })?;
+
+    let bits_per_sample = read.read_u32(5)? + 1;
+    // Cast safety: at most 32
+    let bits_per_sample = BitsPerSample::from_u16(bits_per_sample as u16).ok_or_else(|| {
+        ReadError::unsupported(
+            "FLAC bits per sample",
+            Expected::OneOf(vec![8u32.into(), 16u32.into()]),
+            bits_per_sample,
+            samples_per_sec_pos + 2,
+        )
+    })?;
+
+    let sample_count = read.read_bits(36)?;
+    // the MD5 signature isn't checked, since the frames have CRCs
+    read.read_bytes(16)?;
+    Ok(StreamInfo {
+        samples_per_sec,
+        bits_per_sample,
+        sample_count,
+    })
+}
+
+fn read_u32_le(read: &mut BitReader<'_>) -> Result<u32> {
+    read.read_u32(32).map(u32::swap_bytes)
+}
+
+/// Read the Vorbis comments, with the field names in upper case.
+fn read_vorbis_comment(read: &mut BitReader<'_>) -> Result<Vec<(String, String)>> {
+    let vendor_len = read_u32_le(read)?;
+    // Cast safety: usize >= u32
+    let vendor = read.read_bytes(vendor_len as usize)?;
+    tracing::trace!("vendor `{}`", String::from_utf8_lossy(vendor));
+
+    let count = read_u32_le(read)?;
+    let mut comments = Vec::new();
+    for _ in 0..count {
+        let len = read_u32_le(read)?;
+        // Cast safety: usize >= u32
+        let comment = String::from_utf8_lossy(read.read_bytes(len as usize)?);
+        match comment.split_once('=') {
+            Some((name, value)) => {
+                comments.push((name.to_ascii_uppercase(), value.to_string()));
+            }
+            None => tracing::warn!("Ignoring Vorbis comment `{}` without a value...", comment),
+        }
+    }
+    Ok(comments)
+}
+
+/// Read the sample offsets of the tracks of a cue sheet, excluding the
+/// lead-out track.
+fn read_cue_sheet(read: &mut BitReader<'_>) -> Result<Vec<u64>> {
+    // the media catalog number, lead-in, CD flag, and reserved bytes
+    read.read_bytes(128 + 8 + 259)?;
+    let track_count = read.read_u32(8)?;
+    let mut positions = Vec::new();
+    for track in 0..track_count {
+        let track_offset = read.read_bits(64)?;
+        // the track number, ISRC, flags, and reserved bytes
+        read.read_bytes(1 + 12 + 14)?;
+        let index_count = read.read_u32(8)?;
+        // index point 1 is the start of the track, and 0 the pregap
+        let mut start = None;
+        for _ in 0..index_count {
+            let index_offset = read.read_bits(64)?;
+            let number = read.read_u32(8)?;
+            read.read_bytes(3)?;
+            if number == 1 || start.is_none() {
+                start = Some(index_offset);
+            }
+        }
+        // the lead-out track is always last
+        if track + 1 < track_count {
+            positions.extend(start.map(|start| track_offset + start));
+        }
+    }
+    Ok(positions)
+}
+
+/// Parse the `INDEX 01` lines of a cue sheet, with times in minutes,
+/// seconds, and CD frames.
+fn parse_cue_sheet(text: &str, samples_per_sec: SamplesPerSec) -> Vec<u64> {
+    text.lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            if !words.next()?.eq_ignore_ascii_case("INDEX") {
+                return None;
+            }
+            if words.next()?.parse::<u32>().ok()? != 1 {
+                return None;
+            }
+            let mut time = words.next()?.split(':').map(str::parse::<u64>);
+            let (minutes, seconds, frames) =
+                (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+            let frames = (minutes * 60 + seconds) * CD_FRAMES_PER_SEC + frames;
+            Some(frames * u64::from(samples_per_sec.as_u32()) / CD_FRAMES_PER_SEC)
+        })
+        .collect()
+}
+
+/// The cue points from the Vorbis comments or the cue sheet.
+fn cue_points(
+    comments: &[(String, String)],
+    cue_sheet: Option<Vec<u64>>,
+    samples_per_sec: SamplesPerSec,
+) -> Option<Vec<u64>> {
+    let cue_points: Vec<u64> = comments
+        .iter()
+        .filter(|(name, _)| name == CUEPOINT_TAG)
+        .filter_map(|(_, value)| match value.trim().parse() {
+            Ok(position) => Some(position),
+            Err(_) => {
+                tracing::warn!("Ignoring invalid {} comment `{}`...", CUEPOINT_TAG, value);
+                None
+            }
+        })
+        .collect();
+    if !cue_points.is_empty() {
+        return Some(cue_points);
+    }
+    if cue_sheet.is_some() {
+        return cue_sheet;
+    }
+    comments
+        .iter()
+        .find(|(name, _)| name == CUESHEET_TAG)
+        .map(|(_, text)| parse_cue_sheet(text, samples_per_sec))
+}
+
+/// Read a FLAC file, decoding it to a wave file.
+pub(crate) fn read_flac(buf: &[u8]) -> Result<WaveFile> {
+    let mut read = BitReader::new(buf, 0);
+    let magic = read.read_bytes(4)?;
+    if magic != FLAC_MAGIC {
+        return Err(ReadError::invalid(
+            "FLAC magic",
+            Expected::Equal(ChunkId::new(FLAC_MAGIC).into()),
+            // Panic safety: the slice is 4 bytes
+            ChunkId::new(magic.try_into().unwrap()),
+            0,
+        ));
+    }
+
+    let mut stream_info = None;
+    let mut comments = None;
+    let mut cue_sheet = None;
+    let mut comments_pos = 0;
+    loop {
+        let block_pos = offset(&read);
+        let last = read.read_bit()?;
+        let block_type = read.read_u32(7)?;
+        let size = read.read_u32(24)?;
+        tracing::trace!(
+            "reading metadata block {}, size {} at {}",
+            block_type,
+            size,
+            block_pos
+        );
+
+        // the stream info must be first, and appear once
+        let expected_type = stream_info.is_none();
+        if (block_type == BLOCK_TYPE_STREAMINFO) != expected_type
+            || block_type == BLOCK_TYPE_INVALID
+        {
+            let expected = if expected_type {
+                Expected::Equal(BLOCK_TYPE_STREAMINFO.into())
+            } else {
+                Expected::AtLeast(1u32.into())
+            };
+            return Err(ReadError::invalid(
+                "FLAC metadata block type",
+                expected,
+                block_type,
+                block_pos,
+            ));
+        }
+
+        // Cast safety: usize >= u32
+        let block_end = read.offset() + size as usize;
+        let block = buf.get(..block_end).ok_or(ReadError::Truncated {
+            // Cast safety: u64 >= usize
+            offset: buf.len() as u64,
+        })?;
+        let mut block_read = BitReader::new(block, read.offset());
+        match block_type {
+            BLOCK_TYPE_STREAMINFO => {
+                stream_info = Some(read_stream_info(&mut block_read, size)?);
+            }
+            BLOCK_TYPE_VORBIS_COMMENT if comments.is_none() => {
+                comments_pos = block_pos;
+                comments = Some(read_vorbis_comment(&mut block_read)?);
+            }
+            BLOCK_TYPE_CUESHEET if cue_sheet.is_none() => {
+                cue_sheet = Some(read_cue_sheet(&mut block_read)?);
+            }
+            // e.g. padding, seek tables, or pictures
+            _ => tracing::trace!("skipping metadata block {}", block_type),
+        }
+        read = BitReader::new(buf, block_end);
+        if last {
+            break;
+        }
+    }
+    // Panic safety: the first block is the stream info
+    let stream_info = stream_info.unwrap();
+    let comments = comments.unwrap_or_default();
+
+    let bits_per_sample = u32::from(stream_info.bits_per_sample.as_u16());
+    let mut samples = Vec::new();
+    while read.offset() < buf.len() {
+        decode::read_frame(&mut read, bits_per_sample, &mut samples)?;
+    }
+    // Cast safety: u64 >= usize
+    let sample_count = samples.len() as u64;
+    if stream_info.sample_count != 0 && stream_info.sample_count != sample_count {
+        return Err(ReadError::invalid(
+            "FLAC sample count",
+            Expected::Equal(stream_info.sample_count.into()),
+            sample_count,
+            offset(&read),
+        ));
+    }
+
+    let cue = cue_points(&comments, cue_sheet, stream_info.samples_per_sec)
+        .map(|cue| {
+            cue.into_iter()
+                .map(|position| {
+                    u32::try_from(position)
+                        .ok()
+                        .filter(|&position| u64::from(position) <= sample_count)
+                        .ok_or_else(|| {
+                            ReadError::invalid(
+                                "FLAC cue point",
+                                Expected::AtMost(sample_count.into()),
+                                position,
+                                comments_pos,
+                            )
+                        })
+                })
+                .collect::<Result<Vec<u32>>>()
+        })
+        .transpose()?;
+
+    let mut info = Info::new();
+    for (name, value) in &comments {
+        let Some((id, _)) = INFO_TAGS.iter().find(|(_, tag)| tag == name) else {
+            continue;
+        };
+        // there may be several comments with the same name
+        let text = match info.get(*id) {
+            Some(existing) => format!("{}\n{}", existing, value),
+            None => value.clone(),
+        };
+        info.set(*id, text);
+    }
+
+    let data = match stream_info.bits_per_sample {
+        // Cast safety: signed to unsigned 8-bit samples, which are checked to
+        // be in range when decoding the frame
+        BitsPerSample::Eight => samples.iter().map(|&sample| (sample + 128) as u8).collect(),
+        // Cast safety: the samples are checked to be 16-bit when decoding the
+        // frame
+        BitsPerSample::Sixteen => samples
+            .iter()
+            .flat_map(|&sample| (sample as i16).to_le_bytes())
+            .collect(),
+    };
+
+    tracing::trace!("read {} samples", sample_count);
+    Ok(WaveFile {
+        container: Container::Riff,
+        fmt: Format {
+            channels: Channels::One,
+            samples_per_sec: stream_info.samples_per_sec,
+            bits_per_sample: stream_info.bits_per_sample,
+        },
+        encoding: Encoding::Pcm,
+        fmt_extension: false,
+        fact: None,
+        cue,
+        labels: None,
+        sampler: None,
+        info: (!info.is_empty()).then_some(info),
+        bext: None,
+        ixml: None,
+        data,
+        chunk_order: Vec::new(),
+    })
+}
+
+fn write_stream_info(
+    wav: &WaveFile,
+    sample_count: u64,
+    min_frame_size: usize,
+    max_frame_size: usize,
+) -> Vec<u8> {
+    let mut write = BitWriter::new();
+    // Cast safety: the block size is 4096
+    write.write_bits(encode::BLOCK_SIZE as u64, 16);
+    write.write_bits(encode::BLOCK_SIZE as u64, 16);
+    // Cast safety: the frame sizes are far less than 16 MiB, or zero if
+    // unknown
+    write.write_bits(min_frame_size as u64, 24);
+    write.write_bits(max_frame_size as u64, 24);
+    write.write_bits(wav.fmt.samples_per_sec.as_u32().into(), 20);
+    write.write_bits(u64::from(wav.fmt.channels.as_u16()) - 1, 3);
+    write.write_bits(u64::from(wav.fmt.bits_per_sample.as_u16()) - 1, 5);
+    write.write_bits(sample_count >> 32, 4);
+    write.write_bits(sample_count, 32);
+    // the MD5 signature is unknown
+    write.write_bits(0, 32);
+    write.write_bits(0, 32);
+    write.write_bits(0, 32);
+    write.write_bits(0, 32);
+    write.into_bytes()
+}
+
+fn write_vorbis_comment(comments: &[String]) -> Vec<u8> {
+    let vendor = concat!("zcue ", env!("CARGO_PKG_VERSION"));
+    let mut buf = Vec::new();
+    // Cast safety: the comments are short
+    buf.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    buf.extend_from_slice(vendor.as_bytes());
+    buf.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for comment in comments {
+        buf.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        buf.extend_from_slice(comment.as_bytes());
+    }
+    buf
+}
+
+/// Write a cue sheet that isn't for a CD, with a track per cue point.
+fn write_cue_sheet(cue: &[u32], sample_count: u64) -> Vec<u8> {
+    // the media catalog number, lead-in, CD flag, and reserved bytes
+    let mut buf = vec![0u8; 128 + 8 + 259];
+    // Cast safety: at most 254 tracks
+    buf.push(cue.len() as u8 + 1);
+    let tracks = cue
+        .iter()
+        .map(|&position| u64::from(position))
+        .zip(1u8..)
+        .chain(std::iter::once((sample_count, LEAD_OUT_TRACK)));
+    for (track_offset, number) in tracks {
+        buf.extend_from_slice(&track_offset.to_be_bytes());
+        buf.push(number);
+        // the ISRC, flags, and reserved bytes
+        buf.extend_from_slice(&[0u8; 12 + 14]);
+        if number == LEAD_OUT_TRACK {
+            buf.push(0);
+            continue;
+        }
+        // index point 1 at the start of the track
+        buf.push(1);
+        buf.extend_from_slice(&0u64.to_be_bytes());
+        buf.push(1);
+        buf.extend_from_slice(&[0u8; 3]);
+    }
+    buf
+}
+
+/// Encode a wave file with PCM audio data as a FLAC file.
+///
+/// The cue points are written as `CUEPOINT` Vorbis comments, and as a
+/// `CUESHEET` metadata block with a track per cue point (if there are at
+/// most 254). The `INFO` entries with a matching Vorbis comment, such as
+/// `INAM` (`TITLE`) or `IART` (`ARTIST`), are written as Vorbis comments.
+/// FLAC files have no equivalent of the sampler, `bext`, or iXML chunks.
+pub fn write_flac(wav: &WaveFile) -> crate::Result<Vec<u8>> {
+    if wav.encoding != Encoding::Pcm {
+        bail!(
+            "FLAC files can only store PCM audio data, convert the {} audio data first",
+            wav.encoding
+        );
+    }
+    if wav.sampler.is_some() || wav.bext.is_some() || wav.ixml.is_some() {
+        tracing::warn!("FLAC files can't store the sampler, bext, or iXML chunks, dropping...");
+    }
+
+    let bits_per_sample = u32::from(wav.fmt.bits_per_sample.as_u16());
+    let samples: Vec<i32> = match wav.fmt.bits_per_sample {
+        BitsPerSample::Eight => wav.data.iter().map(|&b| i32::from(b) - 128).collect(),
+        BitsPerSample::Sixteen => wav
+            .data
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]).into())
+            .collect(),
+    };
+    // Cast safety: u64 >= usize
+    let sample_count = samples.len() as u64;
+    if sample_count >= 1 << 36 {
+        bail!("Way too many samples");
+    }
+
+    let frames: Vec<Vec<u8>> = samples
+        .chunks(encode::BLOCK_SIZE)
+        .zip(0u64..)
+        .map(|(block, number)| {
+            encode::write_frame(
+                block,
+                number,
+                bits_per_sample,
+                wav.fmt.samples_per_sec.as_u32(),
+            )
+        })
+        .collect();
+    let min_frame_size = frames.iter().map(Vec::len).min().unwrap_or(0);
+    let max_frame_size = frames.iter().map(Vec::len).max().unwrap_or(0);
+
+    let cue = wav.cue.as_deref().unwrap_or_default();
+    let mut comments: Vec<String> = cue
+        .iter()
+        .map(|position| format!("{}={}", CUEPOINT_TAG, position))
+        .collect();
+    for (id, text) in wav.info.iter().flat_map(Info::iter) {
+        match INFO_TAGS.iter().find(|(tag_id, _)| *tag_id == id) {
+            Some((_, tag)) => comments.push(format!("{}={}", tag, text)),
+            None => tracing::warn!(
+                "FLAC files have no Vorbis comment for `{}`, dropping...",
+                id
+            ),
+        }
+    }
+
+    let mut blocks = vec![
+        (
+            BLOCK_TYPE_STREAMINFO,
+            write_stream_info(wav, sample_count, min_frame_size, max_frame_size),
+        ),
+        (BLOCK_TYPE_VORBIS_COMMENT, write_vorbis_comment(&comments)),
+    ];
+    if !cue.is_empty() && cue.len() <= MAX_TRACKS {
+        blocks.push((BLOCK_TYPE_CUESHEET, write_cue_sheet(cue, sample_count)));
+    } else if cue.len() > MAX_TRACKS {
+        tracing::warn!(
+            "More than {} cue points, only writing {} comments...",
+            MAX_TRACKS,
+            CUEPOINT_TAG
+        );
+    }
+
+    let mut buf = FLAC_MAGIC.to_vec();
+    let block_count = blocks.len();
+    for (index, (block_type, block)) in blocks.into_iter().enumerate() {
+        let last = if index + 1 == block_count { 0x80 } else { 0 };
+        // Cast safety: the blocks are far less than 16 MiB
+        let size = (block.len() as u32).to_be_bytes();
+        // Cast safety: the block types are less than 127
+        buf.push(last | block_type as u8);
+        buf.extend_from_slice(&size[1..]);
+        buf.extend_from_slice(&block);
+    }
+    for frame in frames {
+        buf.extend_from_slice(&frame);
+    }
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,399 @@
+use super::*;
+use crate::wave::{read, AdpcmLayout, Value};
+
+fn pcm(bits_per_sample: BitsPerSample, data: Vec<u8>, cue: Option<Vec<u32>>) -> WaveFile {
+    let fmt = Format {
+        channels: Channels::One,
+        samples_per_sec: SamplesPerSec::Hz22050,
+        bits_per_sample,
+    };
+    WaveFile {
+        cue,
+        ..WaveFile::new(fmt, data)
+    }
+}
+
+fn sine(count: usize) -> Vec<u8> {
+    (0..count)
+        .flat_map(|i| (((i as f32 * 0.05).sin() * 12000.0) as i16).to_le_bytes())
+        .collect()
+}
+
+/// A file with only the stream info, and the frames.
+fn stream(bits_per_sample: u32, channels: u32, sample_count: u64, frames: &[u8]) -> Vec<u8> {
+    let mut write = BitWriter::new();
+    write.write_bits(4096, 16);
+    write.write_bits(4096, 16);
+    write.write_bits(0, 24);
+    write.write_bits(0, 24);
+    write.write_bits(22050, 20);
+    write.write_bits(u64::from(channels - 1), 3);
+    write.write_bits(u64::from(bits_per_sample - 1), 5);
+    write.write_bits(sample_count >> 32, 4);
+    write.write_bits(sample_count, 32);
+    for _ in 0..4 {
+        write.write_bits(0, 32);
+    }
+    let mut buf = FLAC_MAGIC.to_vec();
+    buf.extend_from_slice(&[0x80, 0, 0, 34]);
+    buf.extend_from_slice(&write.into_bytes());
+    buf.extend_from_slice(frames);
+    buf
+}
+
+/// A frame of 16-bit samples, with the subframe written by the closure.
+fn frame(block_size: u64, subframe: impl FnOnce(&mut BitWriter)) -> Vec<u8> {
+    let mut write = BitWriter::new();
+    write.write_bits(FRAME_SYNC.into(), 14);
+    write.write_bits(0, 2);
+    // 16-bit block size, and the sample rate from the stream info
+    write.write_bits(7, 4);
+    write.write_bits(0, 4);
+    write.write_bits(0, 4);
+    write.write_bits(4, 3);
+    write.write_bits(0, 1);
+    write.write_bits(0, 8);
+    write.write_bits(block_size - 1, 16);
+    let crc = crc8(write.bytes());
+    write.write_bits(crc.into(), 8);
+    subframe(&mut write);
+    write.align();
+    let crc = crc16(write.bytes());
+    write.write_bits(crc.into(), 16);
+    write.into_bytes()
+}
+
+fn samples(wav: &WaveFile) -> Vec<i16> {
+    wav.data
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect()
+}
+
+#[test]
+fn crc_check_values() {
+    assert_eq!(crc8(b"123456789"), 0xF4);
+    assert_eq!(crc16(b"123456789"), 0xFEE8);
+}
+
+#[test]
+fn roundtrip_16_bit() {
+    // several frames, with a partial last frame
+    let mut wav = pcm(
+        BitsPerSample::Sixteen,
+        sine(10000),
+        Some(vec![0, 5000, 10000]),
+    );
+    let mut info = Info::new();
+    info.set(ChunkId::new(*b"INAM"), "Title");
+    info.set(ChunkId::new(*b"ICMT"), "Comment");
+    wav.info = Some(info.clone());
+
+    let buf = write_flac(&wav).unwrap();
+    assert!(buf.len() < wav.data.len() / 2);
+    let actual = read(&buf).unwrap();
+    assert_eq!(actual.fmt, wav.fmt);
+    assert_eq!(actual.encoding, Encoding::Pcm);
+    assert_eq!(actual.data, wav.data);
+    assert_eq!(actual.cue, wav.cue);
+    assert_eq!(actual.info, Some(info));
+}
+
+#[test]
+fn roundtrip_8_bit() {
+    // constant, noisy, and short frames
+    let mut data = vec![0x80; 4096];
+    let mut seed = 1u32;
+    data.extend((0..4096).map(|_| {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        (seed >> 24) as u8
+    }));
+    data.extend_from_slice(&[0, 255, 1, 254]);
+    let wav = pcm(BitsPerSample::Eight, data, None);
+
+    let actual = read(&write_flac(&wav).unwrap()).unwrap();
+    assert_eq!(actual.fmt, wav.fmt);
+    assert_eq!(actual.data, wav.data);
+    assert_eq!(actual.cue, None);
+    assert_eq!(actual.info, None);
+}
+
+#[test]
+fn roundtrip_empty() {
+    let wav = pcm(BitsPerSample::Sixteen, Vec::new(), Some(vec![0]));
+    let actual = read(&write_flac(&wav).unwrap()).unwrap();
+    assert!(actual.data.is_empty());
+    assert_eq!(actual.cue, Some(vec![0]));
+}
+
+#[test]
+fn write_adpcm_unsupported() {
+    let mut wav = pcm(BitsPerSample::Sixteen, Vec::new(), None);
+    wav.encoding = Encoding::ImaAdpcm {
+        layout: AdpcmLayout {
+            block_align: 512,
+            samples_per_block: 1017,
+            avg_bytes_per_sec: 11100,
+        },
+    };
+    assert!(write_flac(&wav).is_err());
+}
+
+#[test]
+fn cue_sheet_block() {
+    let buf = write_cue_sheet(&[10, 20], 30);
+    let mut read = BitReader::new(&buf, 0);
+    assert_eq!(read_cue_sheet(&mut read).unwrap(), vec![10, 20]);
+    assert_eq!(read.offset(), buf.len());
+}
+
+#[test]
+fn cue_sheet_comment() {
+    let text = "FILE \"a.wav\" WAVE\n  TRACK 01 AUDIO\n    INDEX 01 00:00:00\n  \
+                TRACK 02 AUDIO\n    INDEX 00 00:01:00\n    INDEX 01 00:02:00\n";
+    let comments = vec![(CUESHEET_TAG.to_string(), text.to_string())];
+    assert_eq!(
+        cue_points(&comments, None, SamplesPerSec::Hz22050),
+        Some(vec![0, 44100])
+    );
+}
+
+#[test]
+fn cue_point_comments_first() {
+    let comments = vec![
+        (CUEPOINT_TAG.to_string(), "5".to_string()),
+        (CUEPOINT_TAG.to_string(), "x".to_string()),
+        (CUEPOINT_TAG.to_string(), "7".to_string()),
+    ];
+    let actual = cue_points(&comments, Some(vec![1]), SamplesPerSec::Hz22050);
+    assert_eq!(actual, Some(vec![5, 7]));
+    let actual = cue_points(&[], Some(vec![1]), SamplesPerSec::Hz22050);
+    assert_eq!(actual, Some(vec![1]));
+    assert_eq!(cue_points(&[], None, SamplesPerSec::Hz22050), None);
+}
+
+#[test]
+fn read_linear_predictor() {
+    let buf = stream(
+        16,
+        1,
+        4,
+        &frame(4, |write| {
+            // order 2
+            write.write_bits(0, 1);
+            write.write_bits(0b100001, 6);
+            write.write_bits(0, 1);
+            write.write_signed(100, 16);
+            write.write_signed(110, 16);
+            // precision 4, shift 0, and the coefficients
+            write.write_bits(3, 4);
+            write.write_signed(0, 5);
+            write.write_signed(2, 4);
+            write.write_signed(-1, 4);
+            // Rice parameter 0, and the residual 1, -1
+            write.write_bits(0, 2);
+            write.write_bits(0, 4);
+            write.write_bits(0, 4);
+            write.write_unary(2);
+            write.write_unary(1);
+        }),
+    );
+    let wav = read_flac(&buf).unwrap();
+    assert_eq!(samples(&wav), vec![100, 110, 121, 131]);
+}
+
+#[test]
+fn read_escaped_partition() {
+    let buf = stream(
+        16,
+        1,
+        4,
+        &frame(4, |write| {
+            // order 0
+            write.write_bits(0, 1);
+            write.write_bits(0b001000, 6);
+            write.write_bits(0, 1);
+            // escape code, and 8-bit residual
+            write.write_bits(0, 2);
+            write.write_bits(0, 4);
+            write.write_bits(0xF, 4);
+            write.write_bits(8, 5);
+            for sample in [-3, 5, 7, -128] {
+                write.write_signed(sample, 8);
+            }
+        }),
+    );
+    let wav = read_flac(&buf).unwrap();
+    assert_eq!(samples(&wav), vec![-3, 5, 7, -128]);
+}
+
+#[test]
+fn read_wasted_bits() {
+    let buf = stream(
+        16,
+        1,
+        3,
+        &frame(3, |write| {
+            // verbatim, with 1 wasted bit
+            write.write_bits(0, 1);
+            write.write_bits(0b000001, 6);
+            write.write_bits(1, 1);
+            write.write_unary(0);
+            for sample in [1, -2, 3] {
+                write.write_signed(sample, 15);
+            }
+        }),
+    );
+    let wav = read_flac(&buf).unwrap();
+    assert_eq!(samples(&wav), vec![2, -4, 6]);
+}
+
+#[test]
+fn read_crc_mismatch() {
+    let wav = pcm(BitsPerSample::Sixteen, sine(100), None);
+    let mut buf = write_flac(&wav).unwrap();
+    let len = buf.len();
+    buf[len - 1] ^= 1;
+    match read(&buf) {
+        Err(ReadError::Invalid { field, offset, .. }) => {
+            assert_eq!(field, "FLAC frame CRC-16");
+            assert_eq!(offset, len as u64 - 2);
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn read_sample_count_mismatch() {
+    let buf = stream(
+        16,
+        1,
+        5,
+        &frame(1, |write| {
+            write.write_bits(0, 1);
+            write.write_bits(0b000000, 6);
+            write.write_bits(0, 1);
+            write.write_signed(1, 16);
+        }),
+    );
+    match read_flac(&buf) {
+        Err(ReadError::Invalid { field, actual, .. }) => {
+            assert_eq!(field, "FLAC sample count");
+            assert_eq!(actual, Value::Number(1));
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn read_stereo_unsupported() {
+    let buf = stream(16, 2, 0, &[]);
+    match read_flac(&buf) {
+        Err(ReadError::Unsupported { field, actual, .. }) => {
+            assert_eq!(field, "FLAC channels");
+            assert_eq!(actual, Value::Number(2));
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn read_cue_point_out_of_range() {
+    let wav = pcm(BitsPerSample::Sixteen, sine(100), Some(vec![101]));
+    match read(&write_flac(&wav).unwrap()) {
+        Err(ReadError::Invalid { field, actual, .. }) => {
+            assert_eq!(field, "FLAC cue point");
+            assert_eq!(actual, Value::Number(101));
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn read_sample_out_of_range() {
+    let buf = stream(
+        16,
+        1,
+        3,
+        &frame(3, |write| {
+            // fixed order 1
+            write.write_bits(0, 1);
+            write.write_bits(0b001001, 6);
+            write.write_bits(0, 1);
+            write.write_signed(i16::MAX.into(), 16);
+            // escape code, and 8-bit residual
+            write.write_bits(0, 2);
+            write.write_bits(0, 4);
+            write.write_bits(0xF, 4);
+            write.write_bits(8, 5);
+            for residual in [10, 0] {
+                write.write_signed(residual, 8);
+            }
+        }),
+    );
+    match read_flac(&buf) {
+        Err(ReadError::Invalid {
+            field,
+            expected,
+            actual,
+            offset,
+        }) => {
+            assert_eq!(field, "FLAC sample magnitude");
+            assert_eq!(expected, Expected::AtMost(32767u64.into()));
+            assert_eq!(actual, Value::Number(32777));
+            // the start of the frame
+            assert_eq!(offset, 42);
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+// encoded by the reference encoder (libFLAC 1.3.2), from the claxon test
+// samples (see testdata/README.md)
+const SHORT: &[u8] = include_bytes!("testdata/short.flac");
+const WASTED_BITS: &[u8] = include_bytes!("testdata/wasted_bits.flac");
+
+#[test]
+fn read_reference_encoder() {
+    let wav = read(SHORT).unwrap();
+    assert_eq!(wav.fmt.samples_per_sec, SamplesPerSec::Hz44100);
+    assert_eq!(wav.fmt.bits_per_sample, BitsPerSample::Sixteen);
+    assert_eq!(samples(&wav), vec![2, -3, 5, -7]);
+
+    // the checksum of the samples whose MD5 signature the encoder stored
+    let wav = read(WASTED_BITS).unwrap();
+    assert_eq!(wav.sample_count(), 4410);
+    assert_eq!(crate::wave::data_checksum(&wav.data), "crc32:50f739f6");
+}
+
+#[test]
+fn write_decodes_with_other_decoder() {
+    let reference = read(WASTED_BITS).unwrap();
+    for wav in [
+        pcm(BitsPerSample::Sixteen, sine(10000), Some(vec![0, 5000])),
+        pcm(
+            BitsPerSample::Eight,
+            (0..=255u8).cycle().take(5000).collect(),
+            None,
+        ),
+        reference,
+    ] {
+        let buf = write_flac(&wav).unwrap();
+        let mut reader = claxon::FlacReader::new(buf.as_slice()).unwrap();
+        let info = reader.streaminfo();
+        assert_eq!(info.sample_rate, wav.fmt.samples_per_sec.as_u32());
+        assert_eq!(
+            info.bits_per_sample,
+            u32::from(wav.fmt.bits_per_sample.as_u16())
+        );
+        let decoded = reader
+            .samples()
+            .collect::<std::result::Result<Vec<i32>, _>>()
+            .unwrap();
+        let expected: Vec<i32> = match wav.fmt.bits_per_sample {
+            BitsPerSample::Eight => wav.data.iter().map(|&b| i32::from(b) - 128).collect(),
+            BitsPerSample::Sixteen => samples(&wav).into_iter().map(i32::from).collect(),
+        };
+        assert_eq!(decoded, expected);
+    }
+}
@@ -0,0 +1,196 @@
+use super::ChunkId;
+use serde::de::{self, MapAccess, Visitor};
+use serde::ser::SerializeMap as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// The `INFO` list of a wave file (`LIST` chunk), with metadata such as the
+/// title (`INAM`), artist (`IART`), or comment (`ICMT`).
+///
+/// The entries are kept in the order they were read, and with the bytes
+/// read, so they are written back unchanged. The text should be ASCII, but is
+/// often in the Windows code page, so it is decoded as UTF-8, or as Latin-1
+/// if it isn't valid UTF-8. Text that is set is encoded as UTF-8. In cue
+/// files, the entries are an object of the four-character IDs and their
+/// text, e.g. `{ "INAM": "Title" }`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Info {
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    id: ChunkId,
+    text: String,
+    bytes: Vec<u8>,
+}
+
+impl Info {
+    /// An empty `INFO` list.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    fn entry(&self, id: ChunkId) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// The text of an entry, if present.
+    pub fn get(&self, id: ChunkId) -> Option<&str> {
+        self.entry(id).map(|entry| entry.text.as_str())
+    }
+
+    /// The bytes of an entry, as read, if present.
+    pub fn get_bytes(&self, id: ChunkId) -> Option<&[u8]> {
+        self.entry(id).map(|entry| entry.bytes.as_slice())
+    }
+
+    /// Set the text of an entry, replacing the existing text, or adding the
+    /// entry at the end.
+    pub fn set(&mut self, id: ChunkId, text: impl Into<String>) {
+        let text = text.into();
+        let bytes = text.as_bytes().to_vec();
+        self.insert(Entry { id, text, bytes });
+    }
+
+    /// Set the bytes of an entry, replacing the existing text, or adding the
+    /// entry at the end.
+    pub fn set_bytes(&mut self, id: ChunkId, bytes: Vec<u8>) {
+        let text = decode(&bytes);
+        self.insert(Entry { id, text, bytes });
+    }
+
+    fn insert(&mut self, entry: Entry) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|existing| existing.id == entry.id)
+        {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    /// Remove an entry, returning its text if it was present.
+    pub fn remove(&mut self, id: ChunkId) -> Option<String> {
+        let index = self.entries.iter().position(|entry| entry.id == id)?;
+        Some(self.entries.remove(index).text)
+    }
+
+    /// The entries, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (ChunkId, &str)> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.id, entry.text.as_str()))
+    }
+
+    /// The entries with their bytes, in order.
+    pub fn iter_bytes(&self) -> impl Iterator<Item = (ChunkId, &[u8])> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.id, entry.bytes.as_slice()))
+    }
+
+    /// Keep the bytes of entries in the original list with the same text,
+    /// e.g. when the list was read from a cue file, so text that was decoded
+    /// as Latin-1 isn't encoded as UTF-8.
+    pub(crate) fn keep_bytes(&mut self, original: &Info) {
+        for entry in &mut self.entries {
+            if let Some(existing) = original.entry(entry.id) {
+                if existing.text == entry.text {
+                    entry.bytes.clone_from(&existing.bytes);
+                }
+            }
+        }
+    }
+
+    /// The number of entries.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Decode text, which should be ASCII, but is often in the Windows code page,
+/// so text that isn't valid UTF-8 is decoded as Latin-1.
+pub(crate) fn decode(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|&b| char::from(b)).collect(),
+    }
+}
+
+/// Decode text terminated by a NUL character, or the end of the field.
+pub(crate) fn decode_text(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    decode(&bytes[..end])
+}
+
+fn parse_id(key: &str) -> Option<ChunkId> {
+    let bytes: [u8; 4] = key.as_bytes().try_into().ok()?;
+    bytes
+        .iter()
+        .all(|b| b.is_ascii_graphic() || *b == b' ')
+        .then_some(ChunkId::new(bytes))
+}
+
+impl Serialize for Info {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (id, text) in self.iter() {
+            map.serialize_entry(&id.to_string(), text)?;
+        }
+        map.end()
+    }
+}
+
+struct InfoVisitor;
+
+impl<'de> Visitor<'de> for InfoVisitor {
+    type Value = Info;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a map of four-character IDs to text")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Info, A::Error> {
+        let mut info = Info::new();
+        while let Some((key, text)) = access.next_entry::<String, String>()? {
+            let id = parse_id(&key).ok_or_else(|| {
+                de::Error::custom(format!(
+                    "invalid INFO ID `{}`, expected four ASCII characters",
+                    key
+                ))
+            })?;
+            if text.contains('\0') {
+                return Err(de::Error::custom(format!(
+                    "INFO text for `{}` contains a NUL character",
+                    key
+                )));
+            }
+            if info.get(id).is_some() {
+                return Err(de::Error::custom(format!("duplicate INFO ID `{}`", key)));
+            }
+            info.set(id, text);
+        }
+        Ok(info)
+    }
+}
+
+impl<'de> Deserialize<'de> for Info {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(InfoVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests;
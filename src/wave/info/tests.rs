@@ -0,0 +1,179 @@
+use super::*;
+use crate::wave::{
+    cue_to_wav, read, roundtrip, write, BitsPerSample, Channels, CuePoints, Expected, Format,
+    ReadError, SamplesPerSec, Value, WaveFile,
+};
+
+const INAM: ChunkId = ChunkId::new(*b"INAM");
+const IART: ChunkId = ChunkId::new(*b"IART");
+const ICMT: ChunkId = ChunkId::new(*b"ICMT");
+
+fn info() -> Info {
+    let mut info = Info::new();
+    info.set(INAM, "Cockpit warning");
+    info.set(IART, "Voice actor");
+    info
+}
+
+fn wav(info: Option<Info>) -> WaveFile {
+    let fmt = Format {
+        channels: Channels::One,
+        samples_per_sec: SamplesPerSec::Hz22050,
+        bits_per_sample: BitsPerSample::Eight,
+    };
+    WaveFile {
+        info,
+        ..WaveFile::new(fmt, vec![128; 10])
+    }
+}
+
+fn list_chunk(list_type: &[u8], entries: &[u8]) -> Vec<u8> {
+    let mut buf = b"LIST".to_vec();
+    buf.extend_from_slice(&(4 + entries.len() as u32).to_le_bytes());
+    buf.extend_from_slice(list_type);
+    buf.extend_from_slice(entries);
+    buf
+}
+
+fn with_list(list: &[u8]) -> Vec<u8> {
+    let mut buf = write(&wav(None)).unwrap();
+    buf.extend_from_slice(list);
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    buf
+}
+
+#[test]
+fn info_set_replaces() {
+    let mut info = info();
+    info.set(INAM, "Missile lock");
+    info.set(ICMT, "Comment");
+    let entries: Vec<_> = info.iter().collect();
+    assert_eq!(
+        entries,
+        vec![
+            (INAM, "Missile lock"),
+            (IART, "Voice actor"),
+            (ICMT, "Comment")
+        ]
+    );
+    assert_eq!(info.remove(IART), Some("Voice actor".to_string()));
+    assert_eq!(info.get(IART), None);
+    assert_eq!(info.len(), 2);
+}
+
+#[test]
+fn info_json_keeps_order() {
+    let json = serde_json::to_string(&info()).unwrap();
+    assert_eq!(json, r#"{"INAM":"Cockpit warning","IART":"Voice actor"}"#);
+    let parsed: Info = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, info());
+}
+
+#[test]
+fn info_json_invalid_id() {
+    let err = serde_json::from_str::<Info>(r#"{"NAME!":"x"}"#).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("invalid INFO ID `NAME!`, expected four ASCII characters"));
+    let err = serde_json::from_str::<Info>(r#"{"INAM":"a\u0000b"}"#).unwrap_err();
+    assert!(err.to_string().starts_with("INFO text for `INAM` contains"));
+}
+
+#[test]
+fn write_and_read_info() {
+    let buf = write(&wav(Some(info()))).unwrap();
+    let list = buf.windows(4).position(|w| w == b"LIST").unwrap();
+    // "Cockpit warning\0" is even, "Voice actor\0" is even too
+    assert_eq!(
+        &buf[list + 4..list + 8],
+        &(4u32 + 8 + 16 + 8 + 12).to_le_bytes()
+    );
+    assert_eq!(&buf[list + 8..list + 12], b"INFO");
+    assert_eq!(read(&buf).unwrap().info, Some(info()));
+    assert_eq!(roundtrip(&buf).unwrap(), None);
+}
+
+#[test]
+fn write_info_pads_odd_text() {
+    let mut info = Info::new();
+    info.set(ICMT, "odd");
+    let buf = write(&wav(Some(info.clone()))).unwrap();
+    let list = buf.windows(4).position(|w| w == b"LIST").unwrap();
+    assert_eq!(&buf[list + 12..list + 24], b"ICMT\x04\x00\x00\x00odd\x00");
+    assert_eq!(read(&buf).unwrap().info, Some(info));
+    assert_eq!(roundtrip(&buf).unwrap(), None);
+}
+
+#[test]
+fn read_info_latin1() {
+    let buf = with_list(&list_chunk(b"INFO", b"IART\x05\x00\x00\x00J\xf6rg\x00\x00"));
+    let wav = read(&buf).unwrap();
+    let info = wav.info.as_ref().unwrap();
+    assert_eq!(info.get(IART), Some("J\u{f6}rg"));
+    assert_eq!(info.get_bytes(IART), Some(&b"J\xf6rg"[..]));
+    // the text is written back as Latin-1, not UTF-8
+    let buf = write(&wav).unwrap();
+    assert!(buf.ends_with(b"IART\x05\x00\x00\x00J\xf6rg\x00\x00"));
+}
+
+#[test]
+fn apply_info_keeps_bytes_of_unchanged_text() {
+    let mut wav = read(&with_list(&list_chunk(
+        b"INFO",
+        b"IART\x05\x00\x00\x00J\xf6rg\x00\x00INAM\x02\x00\x00\x00\xe9\x00",
+    )))
+    .unwrap();
+    // as if the cue points were extracted to a cue file and applied again
+    let json = serde_json::to_string(&wav.info).unwrap();
+    let mut info: Info = serde_json::from_str(&json).unwrap();
+    info.set(INAM, "\u{e9}t\u{e9}");
+    let cue = CuePoints {
+        info: Some(info),
+        ..CuePoints::new(Vec::new())
+    };
+    cue_to_wav(&mut wav, cue).unwrap();
+
+    let info = wav.info.unwrap();
+    assert_eq!(info.get_bytes(IART), Some(&b"J\xf6rg"[..]));
+    assert_eq!(info.get_bytes(INAM), Some("\u{e9}t\u{e9}".as_bytes()));
+}
+
+#[test]
+fn read_info_unsupported_list_type() {
+    let buf = with_list(&list_chunk(b"adtl", b""));
+    match read(&buf) {
+        Err(ReadError::Unsupported {
+            field,
+            expected,
+            actual,
+            ..
+        }) => {
+            assert_eq!(field, "LIST type");
+            assert_eq!(
+                expected,
+                Expected::Equal(Value::ChunkId(ChunkId::new(*b"INFO")))
+            );
+            assert_eq!(actual, Value::ChunkId(ChunkId::new(*b"adtl")));
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn read_info_entry_too_large() {
+    let buf = with_list(&list_chunk(b"INFO", b"INAM\x10\x00\x00\x00abc\x00"));
+    match read(&buf) {
+        Err(ReadError::Invalid {
+            field,
+            expected,
+            actual,
+            ..
+        }) => {
+            assert_eq!(field, "INFO entry size");
+            assert_eq!(expected, Expected::AtMost(Value::Number(4)));
+            assert_eq!(actual, Value::Number(16));
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
@@ -1,42 +1,471 @@
+mod adpcm;
+mod bext;
 mod chunk_id;
+mod error;
+mod flac;
+mod info;
 mod parse;
+mod profile;
 mod read;
+mod roundtrip;
+mod sampler;
+mod update;
 mod write;
 
-pub(crate) use chunk_id::ChunkId;
-pub(crate) use parse::{cue_from_wav, cue_to_wav};
-pub(crate) use read::read;
+use crate::checksum::Crc32;
+pub use adpcm::{convert, Codec};
+pub use bext::Bext;
+pub use chunk_id::ChunkId;
+pub use error::{Expected, ReadError, Value};
+use eyre::OptionExt as _;
+pub use flac::write_flac;
+pub use info::Info;
+pub(crate) use parse::apply_options;
+pub use parse::{
+    apply_cue, apply_cue_to_header, cue_from_header, cue_from_wav, cue_to_header, cue_to_wav,
+    offset_timestamps, ApplyOptions,
+};
+pub use profile::{Profile, Violation};
+pub use read::{read, read_header, read_header_with};
+pub use roundtrip::{roundtrip, Mismatch};
+pub use sampler::{SampleLoop, Sampler};
 use serde::{Deserialize, Serialize};
 use std::fmt;
-pub(crate) use write::write;
+use std::io::{self, Read, Seek, SeekFrom};
+pub use update::{update_in_place, UpdateMethod};
+pub use write::{write, write_with};
 
 const RIFF_CHUNK_ID: ChunkId = ChunkId::new(*b"RIFF");
+const RF64_CHUNK_ID: ChunkId = ChunkId::new(*b"RF64");
+const BW64_CHUNK_ID: ChunkId = ChunkId::new(*b"BW64");
+const DS64_CHUNK_ID: ChunkId = ChunkId::new(*b"ds64");
 const FMT_CHUNK_ID: ChunkId = ChunkId::new(*b"fmt ");
 const CUE_CHUNK_ID: ChunkId = ChunkId::new(*b"cue ");
 const DATA_CHUNK_ID: ChunkId = ChunkId::new(*b"data");
 const FORM_TYPE_WAVE: ChunkId = ChunkId::new(*b"WAVE");
-const WAVE_FORMAT_PCM: u16 = 1;
+const FACT_CHUNK_ID: ChunkId = ChunkId::new(*b"fact");
+const SMPL_CHUNK_ID: ChunkId = ChunkId::new(*b"smpl");
+const LIST_CHUNK_ID: ChunkId = ChunkId::new(*b"LIST");
+const LIST_TYPE_INFO: ChunkId = ChunkId::new(*b"INFO");
+const BEXT_CHUNK_ID: ChunkId = ChunkId::new(*b"bext");
+const IXML_CHUNK_ID: ChunkId = ChunkId::new(*b"iXML");
+/// Padding, which is skipped when read, and left for later chunks to grow
+/// into
+const JUNK_CHUNK_ID: ChunkId = ChunkId::new(*b"JUNK");
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+const WAVE_FORMAT_MS_ADPCM: u16 = 0x0002;
+const WAVE_FORMAT_IMA_ADPCM: u16 = 0x0011;
+const FMT_CHUNK_SIZE: u32 = 16;
+const FMT_CHUNK_SIZE_EXTENSION: u32 = 18;
+/// The size of the ds64 chunk without the table.
+const DS64_CHUNK_MIN_SIZE: u32 = 28;
+/// The size of the RIFF header, i.e. the chunk header and the form type.
+const RIFF_HEADER_SIZE: u64 = 12;
 
-pub(crate) struct WaveFile {
-    pub(crate) fmt: Format,
-    pub(crate) cue: Option<Vec<u32>>,
-    pub(crate) data: Vec<u8>,
+/// A wave file, as read by [`read`] or written by [`write`].
+///
+/// New fields may be added as more chunks are supported, so construct a
+/// wave file with [`WaveFile::new`] and then set the fields.
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct WaveFile {
+    /// The container format, RIFF unless the file is RF64 or BW64
+    pub container: Container,
+    /// The format of the audio data, once decoded
+    pub fmt: Format,
+    /// The encoding of the audio data
+    pub encoding: Encoding,
+    /// Whether the FMT chunk of PCM audio data has an (empty) extension
+    /// size, making it 18 bytes instead of 16
+    pub fmt_extension: bool,
+    /// The sample count of the fact chunk, if the file has one
+    pub fact: Option<u32>,
+    /// The cue point sample starts, if the file has a cue chunk
+    pub cue: Option<Vec<u32>>,
+    /// The cue point labels, one per cue point, if the file has any, e.g.
+    /// the names of AIFF markers. They aren't written to wave files
+    pub labels: Option<Vec<String>>,
+    /// The sampler chunk, if the file has one
+    pub sampler: Option<Sampler>,
+    /// The `INFO` list, if the file has one
+    pub info: Option<Info>,
+    /// The Broadcast Wave Format extension chunk, if the file has one
+    pub bext: Option<Bext>,
+    /// The iXML document as read, if the file has one
+    pub ixml: Option<Vec<u8>>,
+    /// The audio data
+    pub data: Vec<u8>,
+    /// The order of the chunks in the file, as read. If empty, the chunks
+    /// are written in the default order
+    pub chunk_order: Vec<ChunkId>,
 }
 
+/// Where to write the cue chunk, relative to the data chunk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum CuePosition {
+    /// Keep the cue chunk where it was in the original file, or before the
+    /// data chunk if there was none
+    #[default]
+    Original,
+    /// Write the cue chunk before the data chunk
+    BeforeData,
+    /// Write the cue chunk after the data chunk
+    AfterData,
+}
+
+/// The order to write chunks in, given the order they were read in and the
+/// optional chunks present.
+///
+/// The FMT chunk is always first. Chunks that aren't present are dropped,
+/// and missing chunks are added before the data chunk, except for the cue
+/// chunk, which goes where the cue position says.
+fn chunk_order(
+    original: &[ChunkId],
+    optional: &[ChunkId],
+    cue_position: CuePosition,
+) -> Vec<ChunkId> {
+    let mut order: Vec<ChunkId> = original
+        .iter()
+        .copied()
+        .filter(|id| *id == DATA_CHUNK_ID || optional.contains(id))
+        .collect();
+    if !order.contains(&DATA_CHUNK_ID) {
+        order.push(DATA_CHUNK_ID);
+    }
+    for &id in optional {
+        if id != CUE_CHUNK_ID && !order.contains(&id) {
+            // Panic safety: the data chunk was added above
+            let data_index = order.iter().position(|&id| id == DATA_CHUNK_ID).unwrap();
+            order.insert(data_index, id);
+        }
+    }
+
+    let cue_index = order.iter().position(|&id| id == CUE_CHUNK_ID);
+    // an existing cue chunk stays where it is
+    let keep_cue = cue_position == CuePosition::Original && cue_index.is_some();
+    if optional.contains(&CUE_CHUNK_ID) && !keep_cue {
+        let after_data = cue_position == CuePosition::AfterData;
+        order.retain(|&id| id != CUE_CHUNK_ID);
+        // Panic safety: the data chunk was added above
+        let data_index = order.iter().position(|&id| id == DATA_CHUNK_ID).unwrap();
+        let cue_index = if after_data {
+            data_index + 1
+        } else {
+            data_index
+        };
+        order.insert(cue_index, CUE_CHUNK_ID);
+    }
+
+    order.insert(0, FMT_CHUNK_ID);
+    order
+}
+
+impl WaveFile {
+    /// A RIFF wave file with PCM audio data and no other chunks.
+    pub fn new(fmt: Format, data: Vec<u8>) -> Self {
+        Self {
+            container: Container::Riff,
+            fmt,
+            encoding: Encoding::Pcm,
+            fmt_extension: false,
+            fact: None,
+            cue: None,
+            labels: None,
+            sampler: None,
+            info: None,
+            bext: None,
+            ixml: None,
+            data,
+            chunk_order: Vec::new(),
+        }
+    }
+
+    /// The number of samples (per channel) of the audio data.
+    pub fn sample_count(&self) -> u64 {
+        // Cast safety: u64 >= usize
+        self.encoding
+            .sample_count(&self.fmt, self.fact, self.data.len() as u64)
+    }
+
+    /// The duration of the audio data in seconds.
+    pub fn duration(&self) -> f32 {
+        self.sample_count() as f32 / self.fmt.samples_per_sec.as_u32() as f32
+    }
+
+    /// The time reference of the `bext` chunk in seconds, which cue points
+    /// can be offset by.
+    ///
+    /// Fails if the wave file has no `bext` chunk.
+    pub fn bext_offset(&self) -> crate::Result<f64> {
+        bext_offset(self.bext.as_ref(), &self.fmt)
+    }
+}
+
+fn bext_offset(bext: Option<&Bext>, fmt: &Format) -> crate::Result<f64> {
+    let bext = bext.ok_or_eyre("Wave file has no bext chunk with a time reference")?;
+    Ok(bext.time_reference_secs(fmt.samples_per_sec))
+}
+
+/// The header and cue points of a wave file, as read by [`read_header`].
+///
+/// The audio data is not read, only its position in the file. New fields
+/// may be added as more chunks are supported.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct WaveHeader {
+    /// The container format, RIFF unless the file is RF64 or BW64
+    pub container: Container,
+    /// The format of the audio data, once decoded
+    pub fmt: Format,
+    /// The encoding of the audio data
+    pub encoding: Encoding,
+    /// The sample count of the fact chunk, if the file has one
+    pub fact: Option<u32>,
+    /// The cue point sample starts, if the file has a cue chunk
+    pub cue: Option<Vec<u32>>,
+    /// The sampler chunk, if the file has one
+    pub sampler: Option<Sampler>,
+    /// The `INFO` list, if the file has one
+    pub info: Option<Info>,
+    /// The Broadcast Wave Format extension chunk, if the file has one
+    pub bext: Option<Bext>,
+    /// The iXML document as read, if the file has one
+    pub ixml: Option<Vec<u8>>,
+    /// The offset of the audio data from the start of the file
+    pub data_offset: u64,
+    /// The size of the audio data in bytes
+    pub data_size: u64,
+    /// The chunks of the file in order, excluding the RIFF chunk and the
+    /// ds64 chunk
+    pub chunks: Vec<ChunkInfo>,
+}
+
+/// The location of a chunk in a wave file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkInfo {
+    /// The chunk ID
+    pub id: ChunkId,
+    /// The offset of the chunk header from the start of the file
+    pub offset: u64,
+    /// The size of the chunk data, excluding the header and any padding.
+    /// For RF64 files, this is the size from the ds64 chunk
+    pub size: u64,
+}
+
+impl ChunkInfo {
+    /// The offset of the end of the chunk, including the header and any
+    /// padding.
+    pub fn end(&self) -> u64 {
+        self.offset + 8 + self.size + (self.size & 1)
+    }
+}
+
+impl WaveHeader {
+    /// The number of samples (per channel) of the audio data.
+    pub fn sample_count(&self) -> u64 {
+        self.encoding
+            .sample_count(&self.fmt, self.fact, self.data_size)
+    }
+
+    /// The duration of the audio data in seconds.
+    pub fn duration(&self) -> f32 {
+        self.sample_count() as f32 / self.fmt.samples_per_sec.as_u32() as f32
+    }
+
+    /// The time reference of the `bext` chunk in seconds, which cue points
+    /// can be offset by.
+    ///
+    /// Fails if the wave file has no `bext` chunk.
+    pub fn bext_offset(&self) -> crate::Result<f64> {
+        bext_offset(self.bext.as_ref(), &self.fmt)
+    }
+
+    /// A one-line summary of the format, duration, cue points, and audio
+    /// data, as printed by `zcue info`.
+    pub fn summary(&self) -> String {
+        let cue_count = self.cue.as_ref().map_or(0, Vec::len);
+        let loop_count = self
+            .sampler
+            .as_ref()
+            .map_or(0, |sampler| sampler.loops.len());
+        format!(
+            "{} {}, {} channel(s), {} Hz, {} bits, {} samples ({:.3}s), {} cue point(s), {} loop(s), data at {} ({} bytes)",
+            self.container,
+            self.encoding,
+            self.fmt.channels,
+            self.fmt.samples_per_sec,
+            self.fmt.bits_per_sample,
+            self.sample_count(),
+            self.duration(),
+            cue_count,
+            loop_count,
+            self.data_offset,
+            self.data_size,
+        )
+    }
+
+    /// Compute the checksum of the audio data, as stored in
+    /// [`Metadata::checksum`], by streaming the audio data from the reader.
+    pub fn data_checksum<R: Read + Seek>(&self, read: &mut R) -> io::Result<String> {
+        read.seek(SeekFrom::Start(self.data_offset))?;
+        let mut crc = Crc32::new();
+        let mut remaining = self.data_size;
+        let mut buf = vec![0u8; 64 * 1024];
+        while remaining > 0 {
+            // Cast safety: at most the buffer size
+            let len = remaining.min(buf.len() as u64) as usize;
+            read.read_exact(&mut buf[..len])?;
+            crc.update(&buf[..len]);
+            remaining -= len as u64;
+        }
+        Ok(format_checksum(crc.finish()))
+    }
+}
+
+/// The encoding of the audio data.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// Uncompressed PCM (format tag 0x0001)
+    #[default]
+    Pcm,
+    /// IMA ADPCM (format tag 0x0011), decoded to 16 bits per sample
+    ImaAdpcm {
+        /// The block layout
+        layout: AdpcmLayout,
+    },
+    /// Microsoft ADPCM (format tag 0x0002), decoded to 16 bits per sample
+    MsAdpcm {
+        /// The block layout
+        layout: AdpcmLayout,
+        /// The predictor coefficient pairs
+        coefficients: Vec<(i16, i16)>,
+    },
+}
+
+/// The block layout of ADPCM audio data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdpcmLayout {
+    /// The size of one block in bytes
+    pub block_align: u16,
+    /// The number of samples (per channel) in one block
+    pub samples_per_block: u16,
+    /// The average bytes per second, as stated in the FMT chunk
+    pub avg_bytes_per_sec: u32,
+}
+
+impl Encoding {
+    /// The codec of the encoding.
+    pub fn codec(&self) -> Codec {
+        match self {
+            Self::Pcm => Codec::Pcm,
+            Self::ImaAdpcm { .. } => Codec::ImaAdpcm,
+            Self::MsAdpcm { .. } => Codec::MsAdpcm,
+        }
+    }
+
+    fn layout(&self) -> Option<&AdpcmLayout> {
+        match self {
+            Self::Pcm => None,
+            Self::ImaAdpcm { layout } | Self::MsAdpcm { layout, .. } => Some(layout),
+        }
+    }
+
+    /// The number of samples (per channel) in audio data of the given size.
+    ///
+    /// For ADPCM, the fact chunk sample count is used if present, since the
+    /// last block may not be full.
+    fn sample_count(&self, fmt: &Format, fact: Option<u32>, data_size: u64) -> u64 {
+        match (self, fact) {
+            (Self::Pcm, _) => {
+                let block_align = fmt.bits_per_sample.block_align(fmt.channels);
+                data_size / block_align as u64
+            }
+            (_, Some(fact)) => fact.into(),
+            (Self::ImaAdpcm { layout }, None) => adpcm::ima::sample_count(layout, data_size),
+            (Self::MsAdpcm { layout, .. }, None) => adpcm::ms::sample_count(layout, data_size),
+        }
+    }
+
+    /// The block start (from the start of the audio data) and sample offset
+    /// (from the start of the block) of a cue point.
+    fn cue_block(&self, position: u32) -> (u32, u32) {
+        match self.layout() {
+            None => (0, position),
+            Some(layout) => {
+                let samples_per_block = layout.samples_per_block as u32;
+                let block = position / samples_per_block;
+                (
+                    block.saturating_mul(layout.block_align as u32),
+                    position % samples_per_block,
+                )
+            }
+        }
+    }
+}
+
+/// The container format of a wave file.
+///
+/// RF64 and BW64 files have a ds64 chunk with 64-bit sizes, so they can be
+/// greater than 4 GiB.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Container {
+    /// RIFF, limited to 4 GiB
+    #[default]
+    Riff,
+    /// RF64, as specified by EBU Tech 3306
+    Rf64,
+    /// BW64, as specified by ITU-R BS.2088
+    Bw64,
+}
+
+impl Container {
+    fn chunk_id(self) -> ChunkId {
+        match self {
+            Self::Riff => RIFF_CHUNK_ID,
+            Self::Rf64 => RF64_CHUNK_ID,
+            Self::Bw64 => BW64_CHUNK_ID,
+        }
+    }
+}
+
+impl fmt::Display for Container {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.chunk_id(), f)
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pcm => f.write_str("PCM"),
+            Self::ImaAdpcm { .. } => f.write_str("IMA ADPCM"),
+            Self::MsAdpcm { .. } => f.write_str("MS ADPCM"),
+        }
+    }
+}
+
+/// The supported number of channels.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub(crate) enum Channels {
+pub enum Channels {
+    /// Mono
     One,
 }
 
 impl Channels {
-    pub(crate) fn from_u16(value: u16) -> Option<Self> {
+    const EXPECTED: &'static str = "1";
+
+    /// Convert the number of channels, if supported.
+    pub fn from_u16(value: u16) -> Option<Self> {
         match value {
             1 => Some(Self::One),
             _ => None,
         }
     }
 
-    pub(crate) fn as_u16(&self) -> u16 {
+    /// The number of channels.
+    pub fn as_u16(&self) -> u16 {
         match self {
             Self::One => 1,
         }
@@ -51,14 +480,42 @@ impl fmt::Display for Channels {
     }
 }
 
+impl Serialize for Channels {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u16(self.as_u16())
+    }
+}
+
+impl<'de> Deserialize<'de> for Channels {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error as _;
+        let value = u16::deserialize(deserializer)?;
+        Self::from_u16(value).ok_or_else(|| {
+            D::Error::invalid_value(serde::de::Unexpected::Unsigned(value as _), &Self::EXPECTED)
+        })
+    }
+}
+
+/// The supported bits per sample.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub(crate) enum BitsPerSample {
+pub enum BitsPerSample {
+    /// 8 bits per sample, unsigned
     Eight,
+    /// 16 bits per sample, signed
     Sixteen,
 }
 
 impl BitsPerSample {
-    pub(crate) fn from_u16(value: u16) -> Option<Self> {
+    const EXPECTED: &'static str = "8 or 16";
+
+    /// Convert the bits per sample, if supported.
+    pub fn from_u16(value: u16) -> Option<Self> {
         match value {
             8 => Some(Self::Eight),
             16 => Some(Self::Sixteen),
@@ -66,14 +523,16 @@ impl BitsPerSample {
         }
     }
 
-    pub(crate) fn as_u16(&self) -> u16 {
+    /// The bits per sample.
+    pub fn as_u16(&self) -> u16 {
         match self {
             Self::Eight => 8,
             Self::Sixteen => 16,
         }
     }
 
-    pub(crate) fn block_align(&self, channels: Channels) -> u16 {
+    /// The size of one sample for all channels in bytes.
+    pub fn block_align(&self, channels: Channels) -> u16 {
         // for two channels, the values would be doubled
         match channels {
             Channels::One => match self {
@@ -93,19 +552,57 @@ impl fmt::Display for BitsPerSample {
     }
 }
 
+impl Serialize for BitsPerSample {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u16(self.as_u16())
+    }
+}
+
+impl<'de> Deserialize<'de> for BitsPerSample {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error as _;
+        let value = u16::deserialize(deserializer)?;
+        Self::from_u16(value).ok_or_else(|| {
+            D::Error::invalid_value(serde::de::Unexpected::Unsigned(value as _), &Self::EXPECTED)
+        })
+    }
+}
+
+/// The supported sample rates.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub(crate) enum SamplesPerSec {
+pub enum SamplesPerSec {
+    /// 11025 Hz
     Hz11025,
+    /// 22000 Hz
     Hz22000,
+    /// 22040 Hz
     Hz22040,
+    /// 22050 Hz
     Hz22050,
+    /// 44100 Hz
     Hz44100,
 }
 
 impl SamplesPerSec {
     const EXPECTED: &'static str = "11025, 22000, 22040, 22050, or 44100";
 
-    pub(crate) fn from_u32(value: u32) -> Option<Self> {
+    /// All supported sample rates, in ascending order.
+    pub const ALL: [Self; 5] = [
+        Self::Hz11025,
+        Self::Hz22000,
+        Self::Hz22040,
+        Self::Hz22050,
+        Self::Hz44100,
+    ];
+
+    /// Convert the sample rate in Hz, if supported.
+    pub fn from_u32(value: u32) -> Option<Self> {
         match value {
             11025 => Some(Self::Hz11025),
             22000 => Some(Self::Hz22000),
@@ -116,7 +613,8 @@ impl SamplesPerSec {
         }
     }
 
-    pub(crate) fn as_u32(&self) -> u32 {
+    /// The sample rate in Hz.
+    pub fn as_u32(&self) -> u32 {
         match self {
             Self::Hz11025 => 11025,
             Self::Hz22000 => 22000,
@@ -161,22 +659,138 @@ impl<'de> Deserialize<'de> for SamplesPerSec {
     }
 }
 
-#[derive(Debug, Clone)]
-pub(crate) struct Format {
-    pub(crate) channels: Channels,
-    pub(crate) samples_per_sec: SamplesPerSec,
-    pub(crate) bits_per_sample: BitsPerSample,
+/// The format of the audio data as PCM.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Format {
+    /// The number of channels
+    pub channels: Channels,
+    /// The sample rate
+    pub samples_per_sec: SamplesPerSec,
+    /// The bits per sample
+    pub bits_per_sample: BitsPerSample,
 }
 
+/// The exact cue point positions in samples.
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub(crate) struct SamplePoints {
-    pub(crate) samples_per_sec: SamplesPerSec,
-    pub(crate) sample_starts: Vec<u32>,
+pub struct SamplePoints {
+    /// The sample rate of the wave file
+    pub samples_per_sec: SamplesPerSec,
+    /// The cue point sample starts
+    pub sample_starts: Vec<u32>,
+}
+
+/// Information about the wave file cue points were extracted from.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Metadata {
+    /// The file name of the wave file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The checksum of the audio data, e.g. `crc32:cbf43926`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// The format of the audio data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<Format>,
+    /// The number of samples of the audio data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_count: Option<u64>,
+}
+
+impl Metadata {
+    /// Describe the wave file, with an optional file name.
+    pub fn from_wav(wav: &WaveFile, source: Option<String>) -> Self {
+        Self {
+            source,
+            checksum: Some(data_checksum(&wav.data)),
+            format: Some(wav.fmt.clone()),
+            sample_count: Some(wav.sample_count()),
+        }
+    }
+
+    /// Describe the wave file from its header, with an optional checksum
+    /// (see [`WaveHeader::data_checksum`]) and file name.
+    pub fn from_header(
+        header: &WaveHeader,
+        checksum: Option<String>,
+        source: Option<String>,
+    ) -> Self {
+        Self {
+            source,
+            checksum,
+            format: Some(header.fmt.clone()),
+            sample_count: Some(header.sample_count()),
+        }
+    }
+}
+
+pub(crate) fn format_checksum(crc: u32) -> String {
+    format!("crc32:{:08x}", crc)
+}
+
+/// The checksum of audio data, as stored in [`Metadata::checksum`].
+pub fn data_checksum(data: &[u8]) -> String {
+    format_checksum(crate::checksum::crc32(data))
+}
+
+/// The version of cue files without a version, as written by zcue 0.1.0.
+pub const CUE_VERSION_LEGACY: u32 = 1;
+/// The current version of cue files.
+///
+/// Version 2 added the version, metadata, labels, and lengths. Version 3
+/// added the sampler chunk and the `INFO` list.
+pub const CUE_VERSION: u32 = 3;
+
+fn cue_version_legacy() -> u32 {
+    CUE_VERSION_LEGACY
 }
 
+/// Cue points independent of a wave file, as stored in cue files.
+///
+/// New fields may be added in later cue file versions, so construct cue
+/// points with [`CuePoints::new`] and then set the fields.
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub(crate) struct CuePoints {
+#[non_exhaustive]
+pub struct CuePoints {
+    /// The version of the cue file, see [`CUE_VERSION`]
+    #[serde(default = "cue_version_legacy")]
+    pub version: u32,
+    /// Information about the wave file the cue points were extracted from
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub(crate) sample_points: Option<SamplePoints>,
-    pub(crate) timestamps: Vec<f32>,
+    pub metadata: Option<Metadata>,
+    /// The exact cue point positions, for information only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_points: Option<SamplePoints>,
+    /// The cue point positions in seconds
+    pub timestamps: Vec<f64>,
+    /// The cue point labels, one per timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    /// Region lengths in seconds, zero for markers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lengths: Option<Vec<f64>>,
+    /// The sampler chunk with the loop points. If missing, the sampler chunk
+    /// of the wave file is kept when applying
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampler: Option<Sampler>,
+    /// The `INFO` list with metadata such as the title and artist. If
+    /// missing, the `INFO` list of the wave file is kept when applying
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<Info>,
+}
+
+impl CuePoints {
+    /// Cue points at the timestamps in seconds, without any other
+    /// information.
+    pub fn new(timestamps: Vec<f64>) -> Self {
+        Self {
+            version: CUE_VERSION,
+            metadata: None,
+            sample_points: None,
+            timestamps,
+            labels: None,
+            lengths: None,
+            sampler: None,
+            info: None,
+        }
+    }
 }
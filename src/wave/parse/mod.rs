@@ -1,20 +1,26 @@
-use super::{BitsPerSample, CuePoints, SamplePoints, WaveFile};
-use color_eyre::eyre::{eyre, OptionExt as _, Result};
+use super::{
+    data_checksum, CuePoints, Format, Info, SamplePoints, Sampler, WaveFile, WaveHeader,
+    CUE_VERSION,
+};
+use crate::error::bail;
+use eyre::{eyre, OptionExt as _, Result};
+use std::io::{Read, Seek};
 use std::num::FpCategory;
 
-pub(crate) fn cue_from_wav(wav: &WaveFile) -> Result<CuePoints> {
-    let samples_per_sec = wav.fmt.samples_per_sec;
-    let sample_starts = wav.cue.clone().ok_or_eyre("File contains no cue points")?;
+fn cue_points(
+    fmt: &Format,
+    cue: Option<&Vec<u32>>,
+    labels: Option<&Vec<String>>,
+    sampler: Option<&Sampler>,
+    info: Option<&Info>,
+    sample_count: u64,
+) -> Result<CuePoints> {
+    let samples_per_sec = fmt.samples_per_sec;
+    let sample_starts = cue.cloned().ok_or_eyre("File contains no cue points")?;
 
     // "validate" sample starts
-    let data_chunk_size = wav.data.len() as u32;
-    let bytes_per_sample = match wav.fmt.bits_per_sample {
-        BitsPerSample::Eight => 1u32,
-        BitsPerSample::Sixteen => 2u32,
-    };
-    let sample_count = data_chunk_size / bytes_per_sample;
     for (sample_start, index) in sample_starts.iter().copied().zip(1..) {
-        if sample_start > sample_count {
+        if u64::from(sample_start) > sample_count {
             tracing::warn!(
                 "Invalid cue point {}: sample start {} > sample count {}",
                 index,
@@ -25,11 +31,11 @@ pub(crate) fn cue_from_wav(wav: &WaveFile) -> Result<CuePoints> {
     }
 
     // convert sample starts to timestamps
-    let hz = samples_per_sec.as_u32() as f32;
+    let hz = f64::from(samples_per_sec.as_u32());
     let timestamps = sample_starts
         .iter()
         .copied()
-        .map(|s| (s as f32) / hz)
+        .map(|s| f64::from(s) / hz)
         .collect();
 
     let sample_points = Some(SamplePoints {
@@ -38,21 +44,87 @@ pub(crate) fn cue_from_wav(wav: &WaveFile) -> Result<CuePoints> {
     });
 
     Ok(CuePoints {
+        version: CUE_VERSION,
+        metadata: None,
         sample_points,
         timestamps,
+        labels: labels.cloned(),
+        lengths: None,
+        sampler: sampler.cloned(),
+        info: info.cloned(),
     })
 }
 
-pub(crate) fn cue_to_wav(wav: &mut WaveFile, cue: CuePoints) -> Result<()> {
-    let samples_per_sec = wav.fmt.samples_per_sec;
-    if wav.cue.is_some() {
-        tracing::warn!("Input file contains cue points, overwriting...");
+/// Convert the cue points of a wave file to timestamps.
+///
+/// Fails if the wave file has no cue chunk.
+pub fn cue_from_wav(wav: &WaveFile) -> crate::Result<CuePoints> {
+    let cue = cue_points(
+        &wav.fmt,
+        wav.cue.as_ref(),
+        wav.labels.as_ref(),
+        wav.sampler.as_ref(),
+        wav.info.as_ref(),
+        wav.sample_count(),
+    )?;
+    Ok(cue)
+}
+
+/// Convert the cue points of a wave file header to timestamps.
+///
+/// Fails if the wave file has no cue chunk.
+pub fn cue_from_header(header: &WaveHeader) -> crate::Result<CuePoints> {
+    let cue = cue_points(
+        &header.fmt,
+        header.cue.as_ref(),
+        None,
+        header.sampler.as_ref(),
+        header.info.as_ref(),
+        header.sample_count(),
+    )?;
+    Ok(cue)
+}
+
+fn check_metadata(
+    cue: &CuePoints,
+    fmt: &Format,
+    checksum: impl FnOnce() -> Result<String>,
+) -> Result<()> {
+    if let Some(metadata) = &cue.metadata {
+        if let Some(expected) = &metadata.checksum {
+            let actual = checksum()?;
+            if *expected != actual {
+                tracing::warn!(
+                    "Cue file was extracted from different audio data (checksum {}, but was {})",
+                    expected,
+                    actual,
+                );
+            }
+        }
+        if let Some(format) = &metadata.format {
+            if format != fmt {
+                tracing::warn!(
+                    "Cue file was extracted from a different format ({:?}, but was {:?})",
+                    format,
+                    fmt,
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn sample_starts(fmt: &Format, cue: &CuePoints) -> Result<Vec<u32>> {
+    if cue.labels.is_some() {
+        tracing::warn!("Cue labels are not stored in wave files, ignoring...");
+    }
+    if cue.lengths.is_some() {
+        tracing::warn!("Cue lengths are not stored in wave files, ignoring...");
     }
 
     // convert timestamps to sample starts
-    let hz = samples_per_sec.as_u32() as f32;
-    let sample_starts = cue
-        .timestamps
+    let hz = f64::from(fmt.samples_per_sec.as_u32());
+    cue.timestamps
         .iter()
         .copied()
         .map(|ts| match ts.classify() {
@@ -63,15 +135,170 @@ pub(crate) fn cue_to_wav(wav: &mut WaveFile, cue: CuePoints) -> Result<()> {
                 let ss = (ts * hz).round();
                 if ss < 0.0 {
                     Err(eyre!("Timestamp is invalid (neg)"))
-                } else if ss > (u32::MAX as f32) {
+                } else if ss > f64::from(u32::MAX) {
                     Err(eyre!("Timestamp is invalid (max)"))
                 } else {
                     Ok(ss as u32)
                 }
             }
         })
-        .collect::<Result<Vec<u32>>>()?;
+        .collect()
+}
+
+/// The `INFO` list of the cue points, with the bytes of unchanged entries
+/// of the wave file, so they are written back as they were read.
+fn replace_info(mut info: Info, original: Option<&Info>) -> Info {
+    if let Some(original) = original {
+        info.keep_bytes(original);
+    }
+    info
+}
+
+/// Replace the cue points of a wave file with the cue point timestamps.
+pub fn cue_to_wav(wav: &mut WaveFile, cue: CuePoints) -> crate::Result<()> {
+    if wav.cue.is_some() {
+        tracing::warn!("Input file contains cue points, overwriting...");
+    }
+    check_metadata(&cue, &wav.fmt, || Ok(data_checksum(&wav.data)))?;
+    wav.cue = Some(sample_starts(&wav.fmt, &cue)?);
+    // the labels were those of the replaced cue points
+    wav.labels = None;
+    if cue.sampler.is_some() {
+        wav.sampler = cue.sampler;
+    }
+    if let Some(info) = cue.info {
+        wav.info = Some(replace_info(info, wav.info.as_ref()));
+    }
+    Ok(())
+}
 
-    wav.cue = Some(sample_starts);
+/// Replace the cue points of a wave file header with the cue point
+/// timestamps.
+///
+/// If the cue points record a checksum, the audio data is streamed from the
+/// reader to verify it.
+pub fn cue_to_header<R: Read + Seek>(
+    header: &mut WaveHeader,
+    cue: CuePoints,
+    read: &mut R,
+) -> crate::Result<()> {
+    if header.cue.is_some() {
+        tracing::warn!("Input file contains cue points, overwriting...");
+    }
+    check_metadata(&cue, &header.fmt, || Ok(header.data_checksum(read)?))?;
+    header.cue = Some(sample_starts(&header.fmt, &cue)?);
+    if cue.sampler.is_some() {
+        header.sampler = cue.sampler;
+    }
+    if let Some(info) = cue.info {
+        header.info = Some(replace_info(info, header.info.as_ref()));
+    }
+    Ok(())
+}
+
+/// Offset the cue point timestamps by a number of seconds, e.g. the `bext`
+/// time reference.
+///
+/// Fails if a timestamp would become negative.
+pub fn offset_timestamps(cue: &mut CuePoints, seconds: f64) -> crate::Result<()> {
+    for (timestamp, index) in cue.timestamps.iter_mut().zip(1..) {
+        let offset = *timestamp + seconds;
+        if offset < 0.0 {
+            bail!(
+                "Cue point {} at {}s is before the offset of {}s",
+                index,
+                timestamp,
+                -seconds
+            );
+        }
+        *timestamp = offset;
+    }
+    Ok(())
+}
+
+/// How [`apply_cue`] and [`apply_cue_to_header`] change a wave file besides
+/// replacing its cue points.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ApplyOptions {
+    /// Offset the cue points by the `bext` time reference, undoing the
+    /// offset of cue points extracted with it
+    pub bext_offset: bool,
+    /// Replace the sample loops with loops between pairs of cue points
+    pub loops_from_cues: bool,
+    /// Remove the `INFO` list
+    pub strip_info: bool,
+}
+
+/// Replace the sample loops with loops between pairs of the cue points, and
+/// remove the `INFO` list, as the options say.
+fn apply_options_parts(
+    sampler: &mut Option<Sampler>,
+    info: &mut Option<Info>,
+    cue: Option<&[u32]>,
+    fmt: &Format,
+    options: ApplyOptions,
+) {
+    if options.loops_from_cues {
+        let loops = Sampler::loops_from_cues(cue.unwrap_or_default());
+        sampler
+            .get_or_insert_with(|| Sampler::new(fmt.samples_per_sec))
+            .loops = loops;
+    }
+    if options.strip_info {
+        *info = None;
+    }
+}
+
+/// Change the sample loops and `INFO` list of a wave file as the options
+/// say, keeping its cue points.
+pub(crate) fn apply_options(wav: &mut WaveFile, options: ApplyOptions) {
+    apply_options_parts(
+        &mut wav.sampler,
+        &mut wav.info,
+        wav.cue.as_deref(),
+        &wav.fmt,
+        options,
+    );
+}
+
+/// Replace the cue points of a wave file with the cue point timestamps,
+/// and change the sample loops and `INFO` list as the options say.
+pub fn apply_cue(
+    wav: &mut WaveFile,
+    mut cue: CuePoints,
+    options: ApplyOptions,
+) -> crate::Result<()> {
+    if options.bext_offset {
+        offset_timestamps(&mut cue, -wav.bext_offset()?)?;
+    }
+    cue_to_wav(wav, cue)?;
+    apply_options(wav, options);
+    Ok(())
+}
+
+/// Replace the cue points of a wave file header with the cue point
+/// timestamps, and change the sample loops and `INFO` list as the options
+/// say.
+///
+/// If the cue points record a checksum, the audio data is streamed from the
+/// reader to verify it.
+pub fn apply_cue_to_header<R: Read + Seek>(
+    header: &mut WaveHeader,
+    mut cue: CuePoints,
+    read: &mut R,
+    options: ApplyOptions,
+) -> crate::Result<()> {
+    if options.bext_offset {
+        offset_timestamps(&mut cue, -header.bext_offset()?)?;
+    }
+    cue_to_header(header, cue, read)?;
+    apply_options_parts(
+        &mut header.sampler,
+        &mut header.info,
+        header.cue.as_deref(),
+        &header.fmt,
+        options,
+    );
     Ok(())
 }
@@ -0,0 +1,236 @@
+//! Profiles, which check that a wave file can be loaded by the games.
+//!
+//! Reading a wave file checks the structure any wave file has. The Zipper
+//! profile adds the rules of the sounds of Zipper's games on top, such as
+//! the sample rates and encodings they use, and that cue points are in
+//! order. The reader checks them when given a profile with
+//! [`read_header_with`](super::read_header_with).
+use super::error::{Expected, Value};
+use super::{
+    BitsPerSample, Container, Encoding, Format, SamplesPerSec, WaveFile, WaveHeader,
+    WAVE_FORMAT_IMA_ADPCM, WAVE_FORMAT_MS_ADPCM, WAVE_FORMAT_PCM,
+};
+use crate::error::bail;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The profiles wave files can be checked against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum Profile {
+    /// The sounds of Zipper's games (Recoil, MechWarrior 3, and Pirate's
+    /// Moon)
+    Zipper,
+    /// Any wave file zcue can read
+    #[default]
+    Generic,
+}
+
+/// The rules of a profile, which the reader checks as it reads the RIFF, FMT
+/// and cue chunks, and [`Profile::check`] checks for wave files built in
+/// memory.
+pub(crate) struct Rules {
+    pub(crate) samples_per_sec: &'static [SamplesPerSec],
+    pub(crate) bits_per_sample: &'static [BitsPerSample],
+    /// Whether ADPCM audio data is allowed, or only PCM
+    pub(crate) adpcm: bool,
+    /// Whether RF64 and BW64 files are allowed, or only RIFF
+    pub(crate) rf64: bool,
+    /// Whether cue points must be within the audio data, in ascending
+    /// order, and unique
+    pub(crate) strict_cue: bool,
+}
+
+/// The sounds of the supported archives (see "Which sounds files are
+/// supported?" in the README). zcue has no record of how the games differ,
+/// so they share one profile.
+const ZIPPER: Rules = Rules {
+    samples_per_sec: &SamplesPerSec::ALL,
+    bits_per_sample: &[BitsPerSample::Eight, BitsPerSample::Sixteen],
+    adpcm: false,
+    rf64: false,
+    strict_cue: true,
+};
+
+/// Any wave file zcue can read.
+const GENERIC: Rules = Rules {
+    samples_per_sec: &SamplesPerSec::ALL,
+    bits_per_sample: &[BitsPerSample::Eight, BitsPerSample::Sixteen],
+    adpcm: true,
+    rf64: true,
+    strict_cue: false,
+};
+
+/// A rule of a profile the wave file breaks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The name of the field
+    pub field: &'static str,
+    /// The value(s) the profile allows
+    pub expected: Expected,
+    /// The value of the field
+    pub actual: Value,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expected `{}` {}, but was {}",
+            self.field, self.expected, self.actual
+        )
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Zipper => "Zipper",
+            Self::Generic => "generic",
+        })
+    }
+}
+
+impl Profile {
+    pub(crate) fn rules(self) -> &'static Rules {
+        match self {
+            Self::Zipper => &ZIPPER,
+            Self::Generic => &GENERIC,
+        }
+    }
+
+    /// Check a wave file against the profile, returning the rules it
+    /// breaks.
+    pub fn check(self, wav: &WaveFile) -> Vec<Violation> {
+        self.check_parts(
+            wav.container,
+            &wav.fmt,
+            &wav.encoding,
+            wav.cue.as_deref(),
+            wav.sample_count(),
+        )
+    }
+
+    /// Check a wave file header against the profile, returning the rules it
+    /// breaks.
+    pub fn check_header(self, header: &WaveHeader) -> Vec<Violation> {
+        self.check_parts(
+            header.container,
+            &header.fmt,
+            &header.encoding,
+            header.cue.as_deref(),
+            header.sample_count(),
+        )
+    }
+
+    /// Check a wave file against the profile, failing with every rule it
+    /// breaks.
+    pub fn validate(self, wav: &WaveFile) -> crate::Result<()> {
+        self.require(self.check(wav))
+    }
+
+    /// Check a wave file header against the profile, failing with every
+    /// rule it breaks.
+    pub fn validate_header(self, header: &WaveHeader) -> crate::Result<()> {
+        self.require(self.check_header(header))
+    }
+
+    fn require(self, violations: Vec<Violation>) -> crate::Result<()> {
+        if violations.is_empty() {
+            return Ok(());
+        }
+        let violations: Vec<String> = violations.iter().map(ToString::to_string).collect();
+        bail!(
+            "Wave file breaks the {} profile: {}",
+            self,
+            violations.join("; ")
+        );
+    }
+
+    fn check_parts(
+        self,
+        container: Container,
+        fmt: &Format,
+        encoding: &Encoding,
+        cue: Option<&[u32]>,
+        sample_count: u64,
+    ) -> Vec<Violation> {
+        let rules = self.rules();
+        let mut violations = Vec::new();
+
+        if !rules.rf64 && container != Container::Riff {
+            violations.push(Violation {
+                field: "RIFF chunk ID",
+                expected: Expected::Equal(Container::Riff.chunk_id().into()),
+                actual: container.chunk_id().into(),
+            });
+        }
+
+        if !rules.adpcm && *encoding != Encoding::Pcm {
+            let format_tag = match encoding {
+                Encoding::Pcm => WAVE_FORMAT_PCM,
+                Encoding::ImaAdpcm { .. } => WAVE_FORMAT_IMA_ADPCM,
+                Encoding::MsAdpcm { .. } => WAVE_FORMAT_MS_ADPCM,
+            };
+            violations.push(Violation {
+                field: "FMT format tag",
+                expected: Expected::Equal(WAVE_FORMAT_PCM.into()),
+                actual: format_tag.into(),
+            });
+        }
+
+        if !rules.samples_per_sec.contains(&fmt.samples_per_sec) {
+            let expected = rules
+                .samples_per_sec
+                .iter()
+                .map(|hz| hz.as_u32().into())
+                .collect();
+            violations.push(Violation {
+                field: "FMT samples per sec",
+                expected: Expected::OneOf(expected),
+                actual: fmt.samples_per_sec.as_u32().into(),
+            });
+        }
+
+        if !rules.bits_per_sample.contains(&fmt.bits_per_sample) {
+            let expected = rules
+                .bits_per_sample
+                .iter()
+                .map(|bits| bits.as_u16().into())
+                .collect();
+            violations.push(Violation {
+                field: "FMT bits per sample",
+                expected: Expected::OneOf(expected),
+                actual: fmt.bits_per_sample.as_u16().into(),
+            });
+        }
+
+        if rules.strict_cue {
+            let cue = cue.unwrap_or_default();
+            for &position in cue {
+                if u64::from(position) > sample_count {
+                    violations.push(Violation {
+                        field: "cue point sample start",
+                        expected: Expected::AtMost(sample_count.into()),
+                        actual: position.into(),
+                    });
+                }
+            }
+            for pair in cue.windows(2) {
+                if pair[1] <= pair[0] {
+                    violations.push(Violation {
+                        field: "cue point sample start",
+                        expected: Expected::AtLeast((u64::from(pair[0]) + 1).into()),
+                        actual: pair[1].into(),
+                    });
+                }
+            }
+        }
+
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests;
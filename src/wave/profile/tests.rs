@@ -0,0 +1,150 @@
+use super::*;
+use crate::wave::{AdpcmLayout, Channels, ReadError};
+
+fn wav(samples_per_sec: SamplesPerSec, cue: Vec<u32>) -> WaveFile {
+    let fmt = Format {
+        channels: Channels::One,
+        samples_per_sec,
+        bits_per_sample: BitsPerSample::Sixteen,
+    };
+    WaveFile {
+        cue: Some(cue),
+        ..WaveFile::new(fmt, vec![0; 200])
+    }
+}
+
+#[test]
+fn profiles_accept_plain_files() {
+    let wav = wav(SamplesPerSec::Hz22050, vec![0, 50, 100]);
+    for profile in [Profile::Zipper, Profile::Generic] {
+        assert_eq!(profile.check(&wav), Vec::new(), "{}", profile);
+    }
+}
+
+#[test]
+fn zipper_profile_allows_the_archive_formats() {
+    for samples_per_sec in SamplesPerSec::ALL {
+        let wav = wav(samples_per_sec, Vec::new());
+        assert_eq!(Profile::Zipper.check(&wav), Vec::new());
+    }
+}
+
+fn read_header_with(wav: &WaveFile, profile: Profile) -> Result<WaveHeader, ReadError> {
+    let buf = crate::wave::write(wav).unwrap();
+    crate::wave::read_header_with(std::io::Cursor::new(buf), profile)
+}
+
+#[test]
+fn read_header_with_profile() {
+    let plain = wav(SamplesPerSec::Hz22050, vec![0, 50, 100]);
+    for profile in [Profile::Zipper, Profile::Generic] {
+        assert!(read_header_with(&plain, profile).is_ok(), "{}", profile);
+    }
+
+    let unordered = wav(SamplesPerSec::Hz22050, vec![50, 10]);
+    assert!(read_header_with(&unordered, Profile::Generic).is_ok());
+    match read_header_with(&unordered, Profile::Zipper) {
+        Err(ReadError::Invalid {
+            field,
+            expected,
+            actual,
+            offset,
+        }) => {
+            assert_eq!(field, "cue point position");
+            assert_eq!(expected, Expected::AtLeast(51u64.into()));
+            assert_eq!(actual, 10u32.into());
+            // the second cue point, after the FMT chunk and cue count
+            assert_eq!(offset, 12 + 24 + 12 + 24 + 4);
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+
+    let mut rf64 = plain.clone();
+    rf64.container = Container::Rf64;
+    match read_header_with(&rf64, Profile::Zipper) {
+        Err(ReadError::Unsupported { field, offset, .. }) => {
+            assert_eq!(field, "RIFF chunk ID");
+            assert_eq!(offset, 0);
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+
+    let adpcm = crate::wave::convert(&plain, crate::wave::Codec::ImaAdpcm).unwrap();
+    assert!(read_header_with(&adpcm, Profile::Generic).is_ok());
+    match read_header_with(&adpcm, Profile::Zipper) {
+        Err(ReadError::Unsupported {
+            field,
+            actual,
+            offset,
+            ..
+        }) => {
+            assert_eq!(field, "FMT format tag");
+            assert_eq!(actual, WAVE_FORMAT_IMA_ADPCM.into());
+            assert_eq!(offset, 20);
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn adpcm_and_rf64() {
+    let mut wav = wav(SamplesPerSec::Hz22050, Vec::new());
+    wav.container = Container::Rf64;
+    wav.encoding = Encoding::ImaAdpcm {
+        layout: AdpcmLayout {
+            block_align: 512,
+            samples_per_block: 1017,
+            avg_bytes_per_sec: 11100,
+        },
+    };
+    assert_eq!(Profile::Generic.check(&wav), Vec::new());
+    let violations: Vec<String> = Profile::Zipper
+        .check(&wav)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        violations,
+        vec![
+            r#"Expected `RIFF chunk ID` == "RIFF", but was "RF64""#,
+            "Expected `FMT format tag` == 1, but was 17",
+        ]
+    );
+}
+
+#[test]
+fn cue_points_in_order_and_in_data() {
+    let wav = wav(SamplesPerSec::Hz22050, vec![50, 50, 10, 101]);
+    assert_eq!(Profile::Generic.check(&wav), Vec::new());
+    assert_eq!(
+        Profile::Zipper.check(&wav),
+        vec![
+            Violation {
+                field: "cue point sample start",
+                expected: Expected::AtMost(100u64.into()),
+                actual: 101u32.into(),
+            },
+            Violation {
+                field: "cue point sample start",
+                expected: Expected::AtLeast(51u64.into()),
+                actual: 50u32.into(),
+            },
+            Violation {
+                field: "cue point sample start",
+                expected: Expected::AtLeast(51u64.into()),
+                actual: 10u32.into(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn validate_fails_with_every_violation() {
+    let wav = wav(SamplesPerSec::Hz22050, vec![50, 101]);
+    assert!(Profile::Generic.validate(&wav).is_ok());
+    let err = Profile::Zipper.validate(&wav).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Wave file breaks the Zipper profile: Expected `cue point sample start` <= 100, but was 101"
+    );
+}
@@ -0,0 +1,584 @@
+//! Reading AIFF and AIFF-C files, which are converted to the wave file model.
+//!
+//! AIFF stores big-endian, signed PCM samples, so 8-bit samples are made
+//! unsigned and 16-bit samples are byte-swapped. Markers (`MARK`) become cue
+//! points labelled with their names, and the sustain and release loops of the
+//! instrument chunk (`INST`) become sample loops.
+use super::reader::CountingReader;
+use super::{read_pad, Result};
+use crate::wave::error::{Expected, ReadError, Value};
+use crate::wave::info::decode_text;
+use crate::wave::{
+    BitsPerSample, Channels, ChunkId, Container, Encoding, Format, Info, SampleLoop, Sampler,
+    SamplesPerSec, WaveFile,
+};
+use std::io::{Cursor, Read};
+
+pub(crate) const FORM_CHUNK_ID: ChunkId = ChunkId::new(*b"FORM");
+const FORM_TYPE_AIFF: ChunkId = ChunkId::new(*b"AIFF");
+const FORM_TYPE_AIFC: ChunkId = ChunkId::new(*b"AIFC");
+const COMM_CHUNK_ID: ChunkId = ChunkId::new(*b"COMM");
+const SSND_CHUNK_ID: ChunkId = ChunkId::new(*b"SSND");
+const MARK_CHUNK_ID: ChunkId = ChunkId::new(*b"MARK");
+const INST_CHUNK_ID: ChunkId = ChunkId::new(*b"INST");
+const NAME_CHUNK_ID: ChunkId = ChunkId::new(*b"NAME");
+const AUTH_CHUNK_ID: ChunkId = ChunkId::new(*b"AUTH");
+const COPYRIGHT_CHUNK_ID: ChunkId = ChunkId::new(*b"(c) ");
+const ANNO_CHUNK_ID: ChunkId = ChunkId::new(*b"ANNO");
+/// Big-endian PCM
+const COMPRESSION_NONE: ChunkId = ChunkId::new(*b"NONE");
+/// Big-endian PCM, as named by some encoders
+const COMPRESSION_TWOS: ChunkId = ChunkId::new(*b"twos");
+/// Little-endian PCM
+const COMPRESSION_SOWT: ChunkId = ChunkId::new(*b"sowt");
+const COMM_CHUNK_SIZE: u32 = 18;
+/// The size of the AIFF-C COMM chunk, without the compression name.
+const COMM_CHUNK_SIZE_AIFC: u32 = 22;
+const INST_CHUNK_SIZE: u32 = 20;
+const PLAY_MODE_NONE: u16 = 0;
+const PLAY_MODE_FORWARD: u16 = 1;
+const PLAY_MODE_FORWARD_BACKWARD: u16 = 2;
+
+/// The common chunk, which describes the sound data.
+struct Comm {
+    channels: Channels,
+    sample_frames: u32,
+    bits_per_sample: BitsPerSample,
+    samples_per_sec: SamplesPerSec,
+    /// AIFF-C `sowt` sound data is little-endian
+    little_endian: bool,
+}
+
+struct Marker {
+    id: u16,
+    /// The sample frame the marker is before
+    position: u32,
+    name: String,
+    /// The offset of the position
+    offset: u64,
+}
+
+struct Loop {
+    play_mode: u16,
+    begin: u16,
+    end: u16,
+    /// The offset of the begin marker ID
+    offset: u64,
+}
+
+/// The instrument chunk.
+struct Inst {
+    base_note: u8,
+    /// The detune in cents, from -50 to +50
+    detune: i8,
+    sustain_loop: Loop,
+    release_loop: Loop,
+}
+
+fn read_chunk_header(read: &mut CountingReader<impl Read>) -> Result<(ChunkId, u32)> {
+    let chunk_id = read.read_chunk_id()?;
+    let chunk_size = read.read_u32_be()?;
+
+    tracing::trace!(
+        "reading chunk `{}`, size {} at {}",
+        chunk_id,
+        chunk_size,
+        read.offset - 8,
+    );
+
+    Ok((chunk_id, chunk_size))
+}
+
+/// Read the FORM chunk, and return whether the file is AIFF-C.
+fn read_form_chunk(read: &mut CountingReader<impl Read>, total_size: u64) -> Result<bool> {
+    let (chunk_id, chunk_size) = read_chunk_header(read)?;
+    if chunk_id != FORM_CHUNK_ID {
+        return Err(ReadError::invalid(
+            "FORM chunk ID",
+            Expected::Equal(FORM_CHUNK_ID.into()),
+            chunk_id,
+            read.prev - 4,
+        ));
+    }
+
+    let expected_size = total_size.saturating_sub(8);
+    if chunk_size as u64 != expected_size {
+        return Err(ReadError::invalid(
+            "FORM chunk size",
+            Expected::Equal(expected_size.into()),
+            chunk_size,
+            read.prev,
+        ));
+    }
+
+    let form_type = read.read_chunk_id()?;
+    match form_type {
+        FORM_TYPE_AIFF => Ok(false),
+        FORM_TYPE_AIFC => Ok(true),
+        _ => Err(ReadError::invalid(
+            "FORM form type",
+            Expected::OneOf(vec![FORM_TYPE_AIFF.into(), FORM_TYPE_AIFC.into()]),
+            form_type,
+            read.prev,
+        )),
+    }
+}
+
+/// Convert an 80-bit IEEE 754 extended precision number, as used for the
+/// sample rate.
+fn extended_to_f64(bytes: [u8; 10]) -> f64 {
+    let sign_exponent = u16::from_be_bytes([bytes[0], bytes[1]]);
+    // Panic safety: the slice is 8 bytes
+    let mantissa = u64::from_be_bytes(bytes[2..].try_into().unwrap());
+    // the mantissa has an explicit integer bit, so the value is
+    // mantissa * 2^(exponent - 16383 - 63)
+    let exponent = i32::from(sign_exponent & 0x7FFF) - 16383 - 63;
+    // Cast safety: sample rates have far fewer than 53 significant bits
+    let value = mantissa as f64 * 2f64.powi(exponent);
+    if sign_exponent & 0x8000 != 0 {
+        -value
+    } else {
+        value
+    }
+}
+
+fn read_comm_chunk(
+    read: &mut CountingReader<impl Read>,
+    chunk_size: u32,
+    aifc: bool,
+) -> Result<Comm> {
+    let chunk_size_pos = read.prev;
+    let min_size = if aifc {
+        COMM_CHUNK_SIZE_AIFC
+    } else {
+        COMM_CHUNK_SIZE
+    };
+    if chunk_size < min_size {
+        return Err(ReadError::invalid(
+            "COMM chunk size",
+            Expected::AtLeast(min_size.into()),
+            chunk_size,
+            chunk_size_pos,
+        ));
+    }
+
+    let channels = read.read_u16_be()?;
+    let channels = Channels::from_u16(channels).ok_or_else(|| {
+        ReadError::unsupported(
+            "COMM channels",
+            Expected::Equal(1u16.into()),
+            channels,
+            read.prev,
+        )
+    })?;
+
+    let sample_frames = read.read_u32_be()?;
+
+    let bits_per_sample = read.read_u16_be()?;
+    let bits_per_sample = BitsPerSample::from_u16(bits_per_sample).ok_or_else(|| {
+        ReadError::unsupported(
+            "COMM sample size",
+            Expected::OneOf(vec![8u16.into(), 16u16.into()]),
+            bits_per_sample,
+            read.prev,
+        )
+    })?;
+
+    let mut sample_rate = [0; 10];
+    read.read_exact(&mut sample_rate)?;
+    let sample_rate = extended_to_f64(sample_rate);
+    // Cast safety: fractional and out of range sample rates are rejected
+    let samples_per_sec = (sample_rate.fract() == 0.0
+        && (0.0..=u32::MAX as f64).contains(&sample_rate))
+    .then(|| SamplesPerSec::from_u32(sample_rate as u32))
+    .flatten()
+    .ok_or_else(|| {
+        let expected = SamplesPerSec::ALL
+            .iter()
+            .map(|hz| hz.as_u32().into())
+            .collect();
+        // Cast safety: saturating, the integer part is close enough to
+        // show
+        ReadError::unsupported(
+            "COMM sample rate",
+            Expected::OneOf(expected),
+            sample_rate as u64,
+            read.prev,
+        )
+    })?;
+
+    let little_endian = if aifc {
+        let compression_type = read.read_chunk_id()?;
+        match compression_type {
+            COMPRESSION_NONE | COMPRESSION_TWOS => false,
+            COMPRESSION_SOWT => true,
+            _ => {
+                return Err(ReadError::unsupported(
+                    "COMM compression type",
+                    Expected::OneOf(vec![
+                        COMPRESSION_NONE.into(),
+                        COMPRESSION_TWOS.into(),
+                        COMPRESSION_SOWT.into(),
+                    ]),
+                    compression_type,
+                    read.prev,
+                ));
+            }
+        }
+    } else {
+        false
+    };
+
+    // the compression name, or anything else after the fields
+    // Cast safety: usize >= u32
+    let mut rest = vec![0u8; (chunk_size - min_size) as usize];
+    read.read_exact(&mut rest)?;
+    read_pad(read, chunk_size.into(), "COMM padding")?;
+
+    Ok(Comm {
+        channels,
+        sample_frames,
+        bits_per_sample,
+        samples_per_sec,
+        little_endian,
+    })
+}
+
+fn read_ssnd_chunk(read: &mut CountingReader<impl Read>, chunk_size: u32) -> Result<Vec<u8>> {
+    let chunk_size_pos = read.prev;
+    if chunk_size < 8 {
+        return Err(ReadError::invalid(
+            "SSND chunk size",
+            Expected::AtLeast(8u32.into()),
+            chunk_size,
+            chunk_size_pos,
+        ));
+    }
+
+    let offset = read.read_u32_be()?;
+    if offset > chunk_size - 8 {
+        return Err(ReadError::invalid(
+            "SSND offset",
+            Expected::AtMost((chunk_size - 8).into()),
+            offset,
+            read.prev,
+        ));
+    }
+    // the block size is only a hint for streaming
+    let _block_size = read.read_u32_be()?;
+
+    // Cast safety: usize >= u32
+    let mut skipped = vec![0u8; offset as usize];
+    read.read_exact(&mut skipped)?;
+    let mut data = vec![0u8; (chunk_size - 8 - offset) as usize];
+    read.read_exact(&mut data)?;
+    read_pad(read, chunk_size.into(), "SSND padding")?;
+    Ok(data)
+}
+
+fn read_mark_chunk(read: &mut CountingReader<impl Read>, chunk_size: u32) -> Result<Vec<Marker>> {
+    let chunk_size_pos = read.prev;
+    let chunk_start = read.offset;
+
+    let marker_count = read.read_u16_be()?;
+    let mut markers = Vec::with_capacity(marker_count.into());
+    for _ in 0..marker_count {
+        let id = read.read_u16_be()?;
+        let position = read.read_u32_be()?;
+        let offset = read.prev;
+        // the name is a Pascal string, padded to an even length
+        let name_len = read.read_u8()?;
+        let mut name = vec![0u8; usize::from(name_len) | 1];
+        read.read_exact(&mut name)?;
+        let name = decode_text(&name[..name_len.into()]);
+        tracing::trace!("marker {} `{}` at {}", id, name, position);
+        markers.push(Marker {
+            id,
+            position,
+            name,
+            offset,
+        });
+    }
+
+    let size = read.offset - chunk_start;
+    if size != u64::from(chunk_size) {
+        return Err(ReadError::invalid(
+            "MARK chunk size",
+            Expected::Equal(Value::Number(size)),
+            chunk_size,
+            chunk_size_pos,
+        ));
+    }
+    read_pad(read, chunk_size.into(), "MARK padding")?;
+    Ok(markers)
+}
+
+fn read_loop(read: &mut CountingReader<impl Read>) -> Result<Loop> {
+    let play_mode = read.read_u16_be()?;
+    if play_mode > PLAY_MODE_FORWARD_BACKWARD {
+        return Err(ReadError::unsupported(
+            "INST play mode",
+            Expected::OneOf(vec![
+                PLAY_MODE_NONE.into(),
+                PLAY_MODE_FORWARD.into(),
+                PLAY_MODE_FORWARD_BACKWARD.into(),
+            ]),
+            play_mode,
+            read.prev,
+        ));
+    }
+    let begin = read.read_u16_be()?;
+    let offset = read.prev;
+    let end = read.read_u16_be()?;
+    Ok(Loop {
+        play_mode,
+        begin,
+        end,
+        offset,
+    })
+}
+
+fn read_inst_chunk(read: &mut CountingReader<impl Read>, chunk_size: u32) -> Result<Inst> {
+    if chunk_size != INST_CHUNK_SIZE {
+        return Err(ReadError::invalid(
+            "INST chunk size",
+            Expected::Equal(INST_CHUNK_SIZE.into()),
+            chunk_size,
+            read.prev,
+        ));
+    }
+
+    let base_note = read.read_u8()?;
+    // Cast safety: the detune is signed
+    let detune = read.read_u8()? as i8;
+    // the note and velocity ranges, and the gain, don't map to the sampler
+    // chunk
+    let mut ranges = [0u8; 6];
+    read.read_exact(&mut ranges)?;
+    let sustain_loop = read_loop(read)?;
+    let release_loop = read_loop(read)?;
+    Ok(Inst {
+        base_note,
+        detune,
+        sustain_loop,
+        release_loop,
+    })
+}
+
+fn read_text_chunk(read: &mut CountingReader<impl Read>, chunk_size: u32) -> Result<String> {
+    // Cast safety: usize >= u32
+    let mut text = vec![0u8; chunk_size as usize];
+    read.read_exact(&mut text)?;
+    read_pad(read, chunk_size.into(), "text padding")?;
+    Ok(decode_text(&text))
+}
+
+/// Convert the instrument chunk to a sampler chunk, with the loops between
+/// the cue points of the markers.
+fn inst_to_sampler(
+    inst: &Inst,
+    markers: &[Marker],
+    cue: &[u32],
+    samples_per_sec: SamplesPerSec,
+) -> Result<Sampler> {
+    let mut sampler = Sampler::new(samples_per_sec);
+    // the pitch fraction is always upwards, so negative detunes are a
+    // fraction above the note below
+    let (note, cents) = if inst.detune < 0 {
+        (
+            inst.base_note.saturating_sub(1),
+            100 + i32::from(inst.detune),
+        )
+    } else {
+        (inst.base_note, i32::from(inst.detune))
+    };
+    sampler.midi_unity_note = note.into();
+    // Cast safety: the cents are at most 127, and clamped
+    let fraction = ((cents as u64) << 32) / 100;
+    sampler.midi_pitch_fraction = fraction.min(u32::MAX.into()) as u32;
+
+    for inst_loop in [&inst.sustain_loop, &inst.release_loop] {
+        let loop_type = match inst_loop.play_mode {
+            PLAY_MODE_FORWARD => 0,
+            PLAY_MODE_FORWARD_BACKWARD => 1,
+            _ => continue,
+        };
+        let find = |id: u16, offset: u64| {
+            markers
+                .iter()
+                .find(|marker| marker.id == id)
+                .map(|marker| marker.position)
+                .ok_or_else(|| {
+                    let expected = markers.iter().map(|marker| marker.id.into()).collect();
+                    ReadError::invalid("INST loop marker", Expected::OneOf(expected), id, offset)
+                })
+        };
+        let start = find(inst_loop.begin, inst_loop.offset)?;
+        let end = find(inst_loop.end, inst_loop.offset + 2)?;
+        // Cast safety: there are at most 65535 markers
+        let cue_point_id = cue
+            .iter()
+            .position(|&pos| pos == start)
+            .map_or(0, |index| index as u32 + 1);
+        sampler.loops.push(SampleLoop {
+            cue_point_id,
+            loop_type,
+            start,
+            // the end marker is after the last sample of the loop
+            end: end.saturating_sub(1),
+            fraction: 0,
+            play_count: 0,
+        });
+    }
+    Ok(sampler)
+}
+
+/// Read an AIFF or AIFF-C file, converting it to a wave file.
+pub(crate) fn read_aiff(buf: &[u8]) -> Result<WaveFile> {
+    // Cast safety: u64 >= usize
+    let total_size = buf.len() as u64;
+    let mut read = CountingReader::new(Cursor::new(buf));
+    let aifc = read_form_chunk(&mut read, total_size)?;
+
+    let mut comm = None;
+    let mut ssnd = None;
+    let mut markers = None;
+    let mut inst = None;
+    let mut info = Info::new();
+    while read.offset < total_size {
+        let chunk_start_pos = read.offset;
+        let (chunk_id, chunk_size) = read_chunk_header(&mut read)?;
+        let duplicate = match chunk_id {
+            COMM_CHUNK_ID => comm.is_some(),
+            SSND_CHUNK_ID => ssnd.is_some(),
+            MARK_CHUNK_ID => markers.is_some(),
+            INST_CHUNK_ID => inst.is_some(),
+            _ => false,
+        };
+        if duplicate {
+            return Err(ReadError::DuplicateChunk {
+                chunk_id,
+                offset: chunk_start_pos,
+            });
+        }
+        match chunk_id {
+            COMM_CHUNK_ID => comm = Some(read_comm_chunk(&mut read, chunk_size, aifc)?),
+            SSND_CHUNK_ID => {
+                let data = read_ssnd_chunk(&mut read, chunk_size)?;
+                ssnd = Some((data, chunk_start_pos + 4));
+            }
+            MARK_CHUNK_ID => markers = Some(read_mark_chunk(&mut read, chunk_size)?),
+            INST_CHUNK_ID => inst = Some(read_inst_chunk(&mut read, chunk_size)?),
+            NAME_CHUNK_ID | AUTH_CHUNK_ID | COPYRIGHT_CHUNK_ID | ANNO_CHUNK_ID => {
+                let text = read_text_chunk(&mut read, chunk_size)?;
+                let id = match chunk_id {
+                    NAME_CHUNK_ID => ChunkId::new(*b"INAM"),
+                    AUTH_CHUNK_ID => ChunkId::new(*b"IART"),
+                    COPYRIGHT_CHUNK_ID => ChunkId::new(*b"ICOP"),
+                    _ => ChunkId::new(*b"ICMT"),
+                };
+                // there may be several annotations
+                let text = match info.get(id) {
+                    Some(existing) => format!("{}\n{}", existing, text),
+                    None => text,
+                };
+                info.set(id, text);
+            }
+            _ => {
+                // e.g. the format version, or application-specific chunks
+                tracing::warn!("Ignoring AIFF chunk `{}`...", chunk_id);
+                let size = u64::from(chunk_size);
+                read.skip(size + (size & 1))?;
+            }
+        }
+    }
+
+    if read.offset != total_size {
+        return Err(ReadError::invalid(
+            "bytes read",
+            Expected::Equal(total_size.into()),
+            read.offset,
+            read.offset,
+        ));
+    }
+
+    let has_markers = markers.is_some();
+    let comm = comm.ok_or(ReadError::MissingChunk {
+        chunk_id: COMM_CHUNK_ID,
+    })?;
+    let (mut data, ssnd_size_pos) = ssnd.ok_or(ReadError::MissingChunk {
+        chunk_id: SSND_CHUNK_ID,
+    })?;
+
+    let fmt = Format {
+        channels: comm.channels,
+        samples_per_sec: comm.samples_per_sec,
+        bits_per_sample: comm.bits_per_sample,
+    };
+    let block_align = fmt.bits_per_sample.block_align(fmt.channels);
+    let data_size = u64::from(comm.sample_frames) * u64::from(block_align);
+    // Cast safety: u64 >= usize
+    if (data.len() as u64) < data_size {
+        return Err(ReadError::invalid(
+            "SSND chunk size",
+            Expected::AtLeast(data_size.into()),
+            data.len() as u64,
+            ssnd_size_pos,
+        ));
+    }
+    // Cast safety: less than the data length
+    data.truncate(data_size as usize);
+    match fmt.bits_per_sample {
+        // signed to unsigned
+        BitsPerSample::Eight => data.iter_mut().for_each(|b| *b ^= 0x80),
+        BitsPerSample::Sixteen if !comm.little_endian => data
+            .chunks_exact_mut(2)
+            .for_each(|sample| sample.swap(0, 1)),
+        BitsPerSample::Sixteen => {}
+    }
+
+    let markers = markers.unwrap_or_default();
+    if let Some(marker) = markers
+        .iter()
+        .find(|marker| marker.position > comm.sample_frames)
+    {
+        return Err(ReadError::invalid(
+            "MARK position",
+            Expected::AtMost(comm.sample_frames.into()),
+            marker.position,
+            marker.offset,
+        ));
+    }
+    // the cue points are in order, regardless of the marker IDs
+    let mut sorted: Vec<&Marker> = markers.iter().collect();
+    sorted.sort_by_key(|marker| marker.position);
+    let cue = has_markers.then(|| sorted.iter().map(|marker| marker.position).collect());
+    // the names are optional, so only keep them if any are set
+    let labels = sorted
+        .iter()
+        .any(|marker| !marker.name.is_empty())
+        .then(|| sorted.iter().map(|marker| marker.name.clone()).collect());
+    let sampler = inst
+        .map(|inst| {
+            let cue = cue.as_deref().unwrap_or_default();
+            inst_to_sampler(&inst, &markers, cue, fmt.samples_per_sec)
+        })
+        .transpose()?;
+
+    tracing::trace!("read {} bytes", read.offset);
+    Ok(WaveFile {
+        container: Container::Riff,
+        fmt,
+        encoding: Encoding::Pcm,
+        fmt_extension: false,
+        fact: None,
+        cue,
+        labels,
+        sampler,
+        info: (!info.is_empty()).then_some(info),
+        bext: None,
+        ixml: None,
+        data,
+        chunk_order: Vec::new(),
+    })
+}
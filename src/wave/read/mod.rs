@@ -1,11 +1,22 @@
+mod aiff;
 mod reader;
 
+use super::bext::{
+    BEXT_CHUNK_MIN_SIZE, DESCRIPTION_SIZE, ORIGINATION_DATE_SIZE, ORIGINATION_TIME_SIZE,
+    ORIGINATOR_REFERENCE_SIZE, ORIGINATOR_SIZE, RESERVED_SIZE, UMID_SIZE,
+};
+use super::error::{Expected, ReadError};
+use super::flac::{read_flac, FLAC_MAGIC};
+use super::profile::Rules;
 use super::*;
-use color_eyre::eyre::{bail, eyre, Context, Result};
 use reader::CountingReader;
-use std::io::{self, Cursor, Read};
+use std::io::{Cursor, Read, Seek, SeekFrom};
 
-fn read_chunk_header(read: &mut CountingReader<impl Read>) -> io::Result<(ChunkId, u32)> {
+type Result<T> = std::result::Result<T, ReadError>;
+
+const FLAC_CHUNK_ID: ChunkId = ChunkId::new(FLAC_MAGIC);
+
+fn read_chunk_header(read: &mut CountingReader<impl Read>) -> Result<(ChunkId, u32)> {
     let chunk_id = read.read_chunk_id()?;
     let chunk_size = read.read_u32()?;
 
@@ -19,16 +30,41 @@ fn read_chunk_header(read: &mut CountingReader<impl Read>) -> io::Result<(ChunkI
     Ok((chunk_id, chunk_size))
 }
 
-fn read_riff_chunk(read: &mut CountingReader<impl Read>, total_size: u32) -> Result<()> {
+fn read_riff_chunk(
+    read: &mut CountingReader<impl Read>,
+    total_size: u64,
+) -> Result<(Container, u32)> {
     let (chunk_id, chunk_size) = read_chunk_header(read)?;
-    if chunk_id != RIFF_CHUNK_ID {
-        bail!(
-            "Expected `RIFF chunk ID` == {:#?}, but was {:#?} (at {})",
-            RIFF_CHUNK_ID,
-            chunk_id,
-            read.prev - 4,
-        );
-    }
+    let container = match chunk_id {
+        RIFF_CHUNK_ID => Container::Riff,
+        RF64_CHUNK_ID => Container::Rf64,
+        BW64_CHUNK_ID => Container::Bw64,
+        // AIFF and FLAC files are converted when reading them into memory
+        aiff::FORM_CHUNK_ID | FLAC_CHUNK_ID => {
+            return Err(ReadError::unsupported(
+                "RIFF chunk ID",
+                Expected::OneOf(vec![
+                    RIFF_CHUNK_ID.into(),
+                    RF64_CHUNK_ID.into(),
+                    BW64_CHUNK_ID.into(),
+                ]),
+                chunk_id,
+                read.prev - 4,
+            ));
+        }
+        _ => {
+            return Err(ReadError::invalid(
+                "RIFF chunk ID",
+                Expected::OneOf(vec![
+                    RIFF_CHUNK_ID.into(),
+                    RF64_CHUNK_ID.into(),
+                    BW64_CHUNK_ID.into(),
+                ]),
+                chunk_id,
+                read.prev - 4,
+            ));
+        }
+    };
 
     let expected_size = total_size.saturating_sub(8);
     tracing::trace!(
@@ -37,77 +73,194 @@ fn read_riff_chunk(read: &mut CountingReader<impl Read>, total_size: u32) -> Res
         chunk_size,
         expected_size,
     );
-    if chunk_size != expected_size {
-        bail!(
-            "Expected `RIFF chunk size` == {:#?}, but was {:#?} (at {})",
+    // the size of RF64 files is in the ds64 chunk, and checked there
+    if container == Container::Riff && chunk_size as u64 != expected_size {
+        return Err(ReadError::invalid(
+            "RIFF chunk size",
+            Expected::Equal(expected_size.into()),
             chunk_size,
-            expected_size,
             read.prev,
-        );
+        ));
     }
 
     let form_type = read.read_chunk_id()?;
     if form_type != FORM_TYPE_WAVE {
-        bail!(
-            "Expected `RIFF form type` == {:#?}, but was {:#?} (at {})",
-            FORM_TYPE_WAVE,
+        return Err(ReadError::invalid(
+            "RIFF form type",
+            Expected::Equal(FORM_TYPE_WAVE.into()),
             form_type,
             read.prev,
-        );
+        ));
     }
 
-    Ok(())
+    Ok((container, chunk_size))
+}
+
+/// The 64-bit sizes of an RF64 file.
+struct Ds64 {
+    data_size: u64,
+    /// The sizes of other chunks greater than 4 GiB
+    table: Vec<(ChunkId, u64)>,
+}
+
+impl Ds64 {
+    /// The size of a chunk with a 32-bit size of `0xFFFFFFFF`.
+    fn chunk_size(&self, chunk_id: ChunkId) -> Option<u64> {
+        if chunk_id == DATA_CHUNK_ID {
+            return Some(self.data_size);
+        }
+        self.table
+            .iter()
+            .find(|(id, _)| *id == chunk_id)
+            .map(|(_, size)| *size)
+    }
+}
+
+fn read_ds64_chunk(
+    read: &mut CountingReader<impl Read>,
+    total_size: u64,
+    riff_size: u32,
+) -> Result<Ds64> {
+    let (chunk_id, chunk_size) = read_chunk_header(read)?;
+    if chunk_id != DS64_CHUNK_ID {
+        return Err(ReadError::invalid(
+            "DS64 chunk ID",
+            Expected::Equal(DS64_CHUNK_ID.into()),
+            chunk_id,
+            read.prev - 4,
+        ));
+    }
+    let chunk_size_pos = read.prev;
+    if chunk_size < DS64_CHUNK_MIN_SIZE {
+        return Err(ReadError::invalid(
+            "DS64 chunk size",
+            Expected::AtLeast(DS64_CHUNK_MIN_SIZE.into()),
+            chunk_size,
+            chunk_size_pos,
+        ));
+    }
+
+    let expected_size = total_size.saturating_sub(8);
+    let ds64_riff_size = read.read_u64()?;
+    // some writers also set the 32-bit size, if it fits
+    if ds64_riff_size != expected_size
+        || (riff_size != u32::MAX && riff_size as u64 != expected_size)
+    {
+        return Err(ReadError::invalid(
+            "DS64 RIFF size",
+            Expected::Equal(expected_size.into()),
+            ds64_riff_size,
+            read.prev,
+        ));
+    }
+    let data_size = read.read_u64()?;
+    // the sample count is the same as the fact chunk, and not needed
+    let _sample_count = read.read_u64()?;
+
+    let table_length = read.read_u32()?;
+    let expected_size = DS64_CHUNK_MIN_SIZE as u64 + table_length as u64 * 12;
+    if (chunk_size as u64) < expected_size {
+        return Err(ReadError::Invalid {
+            field: "DS64 chunk size",
+            expected: Expected::AtLeast(Value::Number(expected_size)),
+            actual: chunk_size.into(),
+            offset: chunk_size_pos,
+        });
+    }
+    let table = (0..table_length)
+        .map(|_| Ok((read.read_chunk_id()?, read.read_u64()?)))
+        .collect::<Result<_>>()?;
+    // Cast safety: usize >= u32
+    let mut rest = vec![0u8; (chunk_size as u64 - expected_size) as usize];
+    read.read_exact(&mut rest)?;
+    read_pad(read, chunk_size.into(), "DS64 padding")?;
+
+    Ok(Ds64 { data_size, table })
 }
 
-fn read_fmt_chunk(read: &mut CountingReader<impl Read>) -> Result<Format> {
+fn read_fmt_chunk(
+    read: &mut CountingReader<impl Read>,
+    rules: &Rules,
+) -> Result<(Format, Encoding)> {
     let (chunk_id, chunk_size) = read_chunk_header(read)?;
     if chunk_id != FMT_CHUNK_ID {
-        bail!(
-            "Expected `FMT chunk ID` == {:#?}, but was {:#?} (at {})",
-            FMT_CHUNK_ID,
+        return Err(ReadError::invalid(
+            "FMT chunk ID",
+            Expected::Equal(FMT_CHUNK_ID.into()),
             chunk_id,
             read.prev - 4,
-        );
+        ));
     }
 
     let chunk_size_pos = read.prev;
 
     let format_tag = read.read_u16()?;
-    if format_tag != WAVE_FORMAT_PCM {
-        bail!(
-            "Expected `FMT format tag` == {:#?}, but was {:#?} (at {})",
-            WAVE_FORMAT_PCM,
-            format_tag,
-            read.prev,
-        );
-    }
+    let expected_chunk_size = match format_tag {
+        // 18 is an invalid WAVEFORMATEX without size
+        WAVE_FORMAT_PCM => {
+            Expected::OneOf(vec![FMT_CHUNK_SIZE.into(), FMT_CHUNK_SIZE_EXTENSION.into()])
+        }
+        // the extension is checked once the extension size is known
+        WAVE_FORMAT_IMA_ADPCM | WAVE_FORMAT_MS_ADPCM if rules.adpcm => {
+            Expected::AtLeast(20u32.into())
+        }
+        WAVE_FORMAT_IMA_ADPCM | WAVE_FORMAT_MS_ADPCM => {
+            return Err(ReadError::unsupported(
+                "FMT format tag",
+                Expected::Equal(WAVE_FORMAT_PCM.into()),
+                format_tag,
+                read.prev,
+            ));
+        }
+        _ => {
+            return Err(ReadError::unsupported(
+                "FMT format tag",
+                Expected::OneOf(vec![
+                    WAVE_FORMAT_PCM.into(),
+                    WAVE_FORMAT_MS_ADPCM.into(),
+                    WAVE_FORMAT_IMA_ADPCM.into(),
+                ]),
+                format_tag,
+                read.prev,
+            ));
+        }
+    };
 
-    // this is only valid for PCM files
-    // 18 is an invalid WAVEFORMATEX without size
-    if !(chunk_size == 16 || chunk_size == 18) {
-        bail!(
-            "Expected `FMT chunk size` == {:#?} or {:#?}, but was {:#?} (at {})",
-            16,
-            18,
+    let chunk_size_valid = match &expected_chunk_size {
+        Expected::OneOf(sizes) => sizes.contains(&chunk_size.into()),
+        _ => chunk_size >= 20,
+    };
+    if !chunk_size_valid {
+        return Err(ReadError::invalid(
+            "FMT chunk size",
+            expected_chunk_size,
             chunk_size,
             chunk_size_pos,
-        );
+        ));
     }
 
     let channels = read.read_u16()?;
     let channels = Channels::from_u16(channels).ok_or_else(|| {
-        eyre!(
-            "Expected `FMT channels` == {:#?}, but was {:#?} (at {})",
-            1,
+        ReadError::unsupported(
+            "FMT channels",
+            Expected::Equal(1u16.into()),
             channels,
             read.prev,
         )
     })?;
 
     let samples_per_sec = read.read_u32()?;
-    let samples_per_sec = SamplesPerSec::from_u32(samples_per_sec).ok_or_else(|| {
-        eyre!(
-            "Unknown samples per second {} (at {})",
+    let allowed =
+        SamplesPerSec::from_u32(samples_per_sec).filter(|hz| rules.samples_per_sec.contains(hz));
+    let samples_per_sec = allowed.ok_or_else(|| {
+        let expected = rules
+            .samples_per_sec
+            .iter()
+            .map(|hz| hz.as_u32().into())
+            .collect();
+        ReadError::unsupported(
+            "FMT samples per sec",
+            Expected::OneOf(expected),
             samples_per_sec,
             read.prev,
         )
@@ -121,12 +274,34 @@ fn read_fmt_chunk(read: &mut CountingReader<impl Read>) -> Result<Format> {
     let block_align = read.read_u16()?;
     let block_align_pos = read.prev;
 
+    if format_tag != WAVE_FORMAT_PCM {
+        let fmt = Format {
+            channels,
+            samples_per_sec,
+            // ADPCM is decoded to 16 bits per sample
+            bits_per_sample: BitsPerSample::Sixteen,
+        };
+        let layout = AdpcmLayout {
+            block_align,
+            samples_per_block: 0,
+            avg_bytes_per_sec,
+        };
+        let encoding = read_adpcm_fmt(read, format_tag, chunk_size, layout, block_align_pos)?;
+        return Ok((fmt, encoding));
+    }
+
     let bits_per_sample = read.read_u16()?;
-    let bits_per_sample = BitsPerSample::from_u16(bits_per_sample).ok_or_else(|| {
-        eyre!(
-            "Expected `FMT bits per sample` == {:#?} or {:#?}, but was {:#?} (at {})",
-            8,
-            16,
+    let allowed = BitsPerSample::from_u16(bits_per_sample)
+        .filter(|bits| rules.bits_per_sample.contains(bits));
+    let bits_per_sample = allowed.ok_or_else(|| {
+        let expected = rules
+            .bits_per_sample
+            .iter()
+            .map(|bits| bits.as_u16().into())
+            .collect();
+        ReadError::unsupported(
+            "FMT bits per sample",
+            Expected::OneOf(expected),
             bits_per_sample,
             read.prev,
         )
@@ -134,211 +309,748 @@ fn read_fmt_chunk(read: &mut CountingReader<impl Read>) -> Result<Format> {
 
     let expected_block_align = bits_per_sample.block_align(channels);
     if block_align != expected_block_align {
-        bail!(
-            "Expected `FMT block align` == {:#?}, but was {:#?} (at {})",
-            expected_block_align,
+        return Err(ReadError::invalid(
+            "FMT block align",
+            Expected::Equal(expected_block_align.into()),
             block_align,
             block_align_pos,
-        );
+        ));
     }
 
     // this might not work for 2 channels? but should
     let expected_avg_bytes_per_sec = samples_per_sec.as_u32() * block_align as u32;
     if avg_bytes_per_sec != expected_avg_bytes_per_sec {
-        bail!(
-            "Expected `FMT avg bytes per sec` == {:#?}, but was {:#?} (at {})",
-            expected_avg_bytes_per_sec,
+        return Err(ReadError::invalid(
+            "FMT avg bytes per sec",
+            Expected::Equal(expected_avg_bytes_per_sec.into()),
             avg_bytes_per_sec,
             avg_bytes_per_sec_pos,
-        );
+        ));
     }
 
     // 18 is an invalid WAVEFORMATEX without size
-    if chunk_size == 18 {
+    if chunk_size == FMT_CHUNK_SIZE_EXTENSION {
         let extension_size = read.read_u16()?;
         if extension_size != 0 {
-            bail!(
-                "Expected `FMT extension size` == {:#?}, but was {:#?} (at {})",
-                0,
+            return Err(ReadError::invalid(
+                "FMT extension size",
+                Expected::Equal(0u16.into()),
                 extension_size,
                 read.prev,
-            );
+            ));
         }
     }
 
-    Ok(Format {
+    let fmt = Format {
         channels,
         samples_per_sec,
         bits_per_sample,
+    };
+    Ok((fmt, Encoding::Pcm))
+}
+
+/// Read the rest of an ADPCM FMT chunk, after the block align.
+fn read_adpcm_fmt(
+    read: &mut CountingReader<impl Read>,
+    format_tag: u16,
+    chunk_size: u32,
+    mut layout: AdpcmLayout,
+    block_align_pos: u64,
+) -> Result<Encoding> {
+    let bits_per_sample = read.read_u16()?;
+    if bits_per_sample != 4 {
+        return Err(ReadError::unsupported(
+            "FMT bits per sample",
+            Expected::Equal(4u16.into()),
+            bits_per_sample,
+            read.prev,
+        ));
+    }
+
+    let extension_size = read.read_u16()?;
+    let extension_size_valid = match format_tag {
+        WAVE_FORMAT_IMA_ADPCM => extension_size == 2,
+        // at least one coefficient pair
+        _ => extension_size >= 8,
+    };
+    if !extension_size_valid || chunk_size != 18 + extension_size as u32 {
+        let expected = match format_tag {
+            WAVE_FORMAT_IMA_ADPCM => Expected::Equal(2u16.into()),
+            _ => Expected::Equal((chunk_size - 18).into()),
+        };
+        return Err(ReadError::invalid(
+            "FMT extension size",
+            expected,
+            extension_size,
+            read.prev,
+        ));
+    }
+
+    let (header_size, expected_samples_per_block) = match format_tag {
+        WAVE_FORMAT_IMA_ADPCM => (
+            adpcm::ima::HEADER_SIZE,
+            adpcm::ima::samples_per_block(layout.block_align),
+        ),
+        _ => (
+            adpcm::ms::HEADER_SIZE,
+            adpcm::ms::samples_per_block(layout.block_align),
+        ),
+    };
+    let expected_samples_per_block = expected_samples_per_block.ok_or_else(|| {
+        ReadError::invalid(
+            "FMT block align",
+            Expected::AtLeast(header_size.into()),
+            layout.block_align,
+            block_align_pos,
+        )
+    })?;
+
+    layout.samples_per_block = read.read_u16()?;
+    if layout.samples_per_block as u32 != expected_samples_per_block {
+        return Err(ReadError::invalid(
+            "FMT samples per block",
+            Expected::Equal(expected_samples_per_block.into()),
+            layout.samples_per_block,
+            read.prev,
+        ));
+    }
+
+    if format_tag == WAVE_FORMAT_IMA_ADPCM {
+        return Ok(Encoding::ImaAdpcm { layout });
+    }
+
+    let coefficient_count = read.read_u16()?;
+    let expected_coefficient_count = (extension_size - 4) / 4;
+    if coefficient_count != expected_coefficient_count || extension_size % 4 != 0 {
+        return Err(ReadError::invalid(
+            "FMT coefficient count",
+            Expected::Equal(expected_coefficient_count.into()),
+            coefficient_count,
+            read.prev,
+        ));
+    }
+    let coefficients = (0..coefficient_count)
+        .map(|_| Ok((read.read_u16()? as i16, read.read_u16()? as i16)))
+        .collect::<Result<_>>()?;
+    Ok(Encoding::MsAdpcm {
+        layout,
+        coefficients,
     })
 }
 
-fn read_cue_point(read: &mut CountingReader<impl Read>, index: u32) -> Result<u32> {
+fn read_cue_point(
+    read: &mut CountingReader<impl Read>,
+    index: u32,
+    encoding: &Encoding,
+    previous: Option<u32>,
+    rules: &Rules,
+) -> Result<u32> {
     tracing::trace!("reading cue point {} at {}", index, read.offset);
 
     let id = read.read_u32()?;
     if id != index {
-        bail!(
-            "Expected `cue point id` == {:#?}, but was {:#?} (at {})",
-            index,
+        return Err(ReadError::invalid(
+            "cue point id",
+            Expected::Equal(index.into()),
             id,
             read.prev,
-        );
+        ));
     }
 
     let position = read.read_u32()?;
+    match previous {
+        Some(previous) if rules.strict_cue && position <= previous => {
+            return Err(ReadError::invalid(
+                "cue point position",
+                Expected::AtLeast((u64::from(previous) + 1).into()),
+                position,
+                read.prev,
+            ));
+        }
+        _ => {}
+    }
 
     let data_chunk_id = read.read_chunk_id()?;
     if data_chunk_id != DATA_CHUNK_ID {
-        bail!(
-            "Expected `cue point data chunk id` == {:#?}, but was {:#?} (at {})",
-            DATA_CHUNK_ID,
+        return Err(ReadError::invalid(
+            "cue point data chunk id",
+            Expected::Equal(DATA_CHUNK_ID.into()),
             data_chunk_id,
             read.prev,
-        );
+        ));
     }
 
     let chunk_start = read.read_u32()?;
     if chunk_start != 0 {
-        bail!(
-            "Expected `cue point chunk start` == {:#?}, but was {:#?} (at {})",
-            0,
+        return Err(ReadError::invalid(
+            "cue point chunk start",
+            Expected::Equal(0u32.into()),
             chunk_start,
             read.prev,
-        );
+        ));
     }
 
     let block_start = read.read_u32()?;
-    if block_start != 0 {
-        bail!(
-            "Expected `cue point block start` == {:#?}, but was {:#?} (at {})",
-            0,
+    let block_start_pos = read.prev;
+    let sample_start = read.read_u32()?;
+
+    // for compressed audio data, the cue point should point to the block,
+    // but some tools write the position the same as for PCM
+    let (expected_block_start, expected_sample_start) =
+        if block_start == 0 && sample_start == position {
+            (0, position)
+        } else {
+            encoding.cue_block(position)
+        };
+
+    if block_start != expected_block_start {
+        return Err(ReadError::invalid(
+            "cue point block start",
+            Expected::Equal(expected_block_start.into()),
             block_start,
-            read.prev,
-        );
+            block_start_pos,
+        ));
     }
 
-    let sample_start = read.read_u32()?;
-    if sample_start != position {
-        bail!(
-            "Expected `cue point sample start` == {:#?}, but was {:#?} (at {})",
-            position,
+    if sample_start != expected_sample_start {
+        return Err(ReadError::invalid(
+            "cue point sample start",
+            Expected::Equal(expected_sample_start.into()),
             sample_start,
             read.prev,
-        );
+        ));
     }
 
     tracing::trace!("cue point {} is {}", index, position);
     Ok(position)
 }
 
+fn read_fact_chunk(read: &mut CountingReader<impl Read>, chunk_size: u32) -> Result<u32> {
+    if chunk_size != 4 {
+        return Err(ReadError::invalid(
+            "FACT chunk size",
+            Expected::Equal(4u32.into()),
+            chunk_size,
+            read.prev,
+        ));
+    }
+    read.read_u32()
+}
+
 const CUE_CHUNK_MIN_SIZE: u32 = 4 + 24;
 
-fn read_cue_chunk(read: &mut CountingReader<impl Read>, chunk_size: u32) -> Result<Vec<u32>> {
+fn read_cue_chunk(
+    read: &mut CountingReader<impl Read>,
+    chunk_size: u32,
+    encoding: &Encoding,
+    rules: &Rules,
+) -> Result<Vec<u32>> {
     let chunk_size_pos = read.prev;
 
     if chunk_size < CUE_CHUNK_MIN_SIZE {
-        bail!(
-            "Expected `CUE chunk size` >= {}, but was {} (at {})",
-            CUE_CHUNK_MIN_SIZE,
+        return Err(ReadError::invalid(
+            "CUE chunk size",
+            Expected::AtLeast(CUE_CHUNK_MIN_SIZE.into()),
             chunk_size,
             chunk_size_pos,
-        );
+        ));
     }
 
     let cue_point_count = read.read_u32()?;
-    let expected_size = 4 + cue_point_count * 24;
+    let expected_size = 4 + cue_point_count as u64 * 24;
+
+    if chunk_size as u64 != expected_size {
+        return Err(ReadError::Invalid {
+            field: "CUE chunk size",
+            expected: Expected::Equal(Value::Number(expected_size)),
+            actual: chunk_size.into(),
+            offset: chunk_size_pos,
+        });
+    }
+
+    let mut cue = Vec::new();
+    for index in 1..=cue_point_count {
+        let position = read_cue_point(read, index, encoding, cue.last().copied(), rules)?;
+        cue.push(position);
+    }
+    Ok(cue)
+}
+
+const SMPL_CHUNK_MIN_SIZE: u32 = 36;
+
+fn read_sample_loop(read: &mut CountingReader<impl Read>) -> Result<SampleLoop> {
+    Ok(SampleLoop {
+        cue_point_id: read.read_u32()?,
+        loop_type: read.read_u32()?,
+        start: read.read_u32()?,
+        end: read.read_u32()?,
+        fraction: read.read_u32()?,
+        play_count: read.read_u32()?,
+    })
+}
 
-    if chunk_size != expected_size {
-        bail!(
-            "Expected `CUE chunk size` == {}, but was {} (at {})",
+fn read_smpl_chunk(read: &mut CountingReader<impl Read>, chunk_size: u32) -> Result<Sampler> {
+    let chunk_size_pos = read.prev;
+
+    if chunk_size < SMPL_CHUNK_MIN_SIZE {
+        return Err(ReadError::invalid(
+            "SMPL chunk size",
+            Expected::AtLeast(SMPL_CHUNK_MIN_SIZE.into()),
             chunk_size,
-            expected_size,
             chunk_size_pos,
-        );
+        ));
+    }
+
+    let manufacturer = read.read_u32()?;
+    let product = read.read_u32()?;
+    let sample_period = read.read_u32()?;
+    let midi_unity_note = read.read_u32()?;
+    let midi_pitch_fraction = read.read_u32()?;
+    let smpte_format = read.read_u32()?;
+    let smpte_offset = read.read_u32()?;
+    let loop_count = read.read_u32()?;
+    let sampler_data_size = read.read_u32()?;
+
+    let expected_size =
+        SMPL_CHUNK_MIN_SIZE as u64 + loop_count as u64 * 24 + sampler_data_size as u64;
+    if chunk_size as u64 != expected_size {
+        return Err(ReadError::Invalid {
+            field: "SMPL chunk size",
+            expected: Expected::Equal(Value::Number(expected_size)),
+            actual: chunk_size.into(),
+            offset: chunk_size_pos,
+        });
+    }
+
+    let loops = (0..loop_count)
+        .map(|_| read_sample_loop(read))
+        .collect::<Result<_>>()?;
+    // Cast safety: usize >= u32
+    let mut sampler_data = vec![0u8; sampler_data_size as usize];
+    read.read_exact(&mut sampler_data)?;
+    read_pad(read, chunk_size.into(), "SMPL padding")?;
+
+    Ok(Sampler {
+        manufacturer,
+        product,
+        sample_period,
+        midi_unity_note,
+        midi_pitch_fraction,
+        smpte_format,
+        smpte_offset,
+        loops,
+        sampler_data,
+    })
+}
+
+fn read_list_chunk(read: &mut CountingReader<impl Read>, chunk_size: u32) -> Result<Info> {
+    let chunk_size_pos = read.prev;
+
+    if chunk_size < 4 {
+        return Err(ReadError::invalid(
+            "LIST chunk size",
+            Expected::AtLeast(4u32.into()),
+            chunk_size,
+            chunk_size_pos,
+        ));
+    }
+    // the end of the list, without padding
+    let end = read.offset + chunk_size as u64;
+
+    let list_type = read.read_chunk_id()?;
+    if list_type != LIST_TYPE_INFO {
+        return Err(ReadError::Unsupported {
+            field: "LIST type",
+            expected: Expected::Equal(LIST_TYPE_INFO.into()),
+            actual: list_type.into(),
+            offset: read.prev,
+        });
+    }
+
+    let mut info = Info::new();
+    while read.offset < end {
+        let entry_start_pos = read.offset;
+        let (entry_id, entry_size) = read_chunk_header(read)?;
+        let max_size = end.saturating_sub(read.offset);
+        if entry_size as u64 + (entry_size & 1) as u64 > max_size {
+            return Err(ReadError::Invalid {
+                field: "INFO entry size",
+                expected: Expected::AtMost(Value::Number(max_size)),
+                actual: entry_size.into(),
+                offset: read.prev,
+            });
+        }
+        if info.get(entry_id).is_some() {
+            return Err(ReadError::DuplicateChunk {
+                chunk_id: entry_id,
+                offset: entry_start_pos,
+            });
+        }
+        // Cast safety: usize >= u32
+        let mut text = vec![0u8; entry_size as usize];
+        read.read_exact(&mut text)?;
+        read_pad(read, entry_size.into(), "INFO padding")?;
+        // the text is terminated by a NUL character, or the end of the entry
+        let end = text.iter().position(|&b| b == 0).unwrap_or(text.len());
+        text.truncate(end);
+        info.set_bytes(entry_id, text);
+    }
+
+    read_pad(read, chunk_size.into(), "LIST padding")?;
+    Ok(info)
+}
+
+fn read_field<const N: usize>(read: &mut CountingReader<impl Read>) -> Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    read.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_bext_chunk(read: &mut CountingReader<impl Read>, chunk_size: u32) -> Result<Bext> {
+    if chunk_size < BEXT_CHUNK_MIN_SIZE {
+        return Err(ReadError::invalid(
+            "BEXT chunk size",
+            Expected::AtLeast(BEXT_CHUNK_MIN_SIZE.into()),
+            chunk_size,
+            read.prev,
+        ));
     }
 
-    (1..=cue_point_count)
-        .map(|index| read_cue_point(read, index))
-        .collect()
+    let description = read_field::<DESCRIPTION_SIZE>(read)?;
+    let originator = read_field::<ORIGINATOR_SIZE>(read)?;
+    let originator_reference = read_field::<ORIGINATOR_REFERENCE_SIZE>(read)?;
+    let origination_date = read_field::<ORIGINATION_DATE_SIZE>(read)?;
+    let origination_time = read_field::<ORIGINATION_TIME_SIZE>(read)?;
+    let time_reference_low = read.read_u32()?;
+    let time_reference_high = read.read_u32()?;
+    let version = read.read_u16()?;
+    let umid = read_field::<UMID_SIZE>(read)?;
+    // Cast safety: the loudness fields are signed
+    let loudness_value = read.read_u16()? as i16;
+    let loudness_range = read.read_u16()? as i16;
+    let max_true_peak_level = read.read_u16()? as i16;
+    let max_momentary_loudness = read.read_u16()? as i16;
+    let max_short_term_loudness = read.read_u16()? as i16;
+    let reserved = read_field::<RESERVED_SIZE>(read)?;
+
+    // Cast safety: usize >= u32
+    let mut coding_history = vec![0u8; (chunk_size - BEXT_CHUNK_MIN_SIZE) as usize];
+    read.read_exact(&mut coding_history)?;
+    read_pad(read, chunk_size.into(), "BEXT padding")?;
+
+    Ok(Bext {
+        description,
+        originator,
+        originator_reference,
+        origination_date,
+        origination_time,
+        time_reference: (time_reference_high as u64) << 32 | time_reference_low as u64,
+        version,
+        umid,
+        loudness_value,
+        loudness_range,
+        max_true_peak_level,
+        max_momentary_loudness,
+        max_short_term_loudness,
+        reserved,
+        // the coding history is kept as-is, including any NUL characters
+        coding_history,
+    })
 }
 
-fn read_data_chunk(read: &mut CountingReader<impl Read>, chunk_size: u32) -> Result<Vec<u8>> {
+fn read_ixml_chunk(read: &mut CountingReader<impl Read>, chunk_size: u32) -> Result<Vec<u8>> {
     // Cast safety: usize >= u32
     let mut buf = vec![0u8; chunk_size as usize];
     read.read_exact(&mut buf)?;
+    read_pad(read, chunk_size.into(), "IXML padding")?;
+    // the document is kept as-is, including any NUL characters
+    Ok(buf)
+}
 
+fn read_pad(
+    read: &mut CountingReader<impl Read>,
+    chunk_size: u64,
+    field: &'static str,
+) -> Result<()> {
     if (chunk_size & 1) == 1 {
         let pad = read.read_u8()?;
         if pad != 0 {
-            bail!(
-                "Expected `DATA padding` == {}, but was {} (at {})",
-                0,
+            return Err(ReadError::invalid(
+                field,
+                Expected::Equal(0u8.into()),
                 pad,
                 read.prev,
-            );
+            ));
         }
     }
+    Ok(())
+}
 
+fn read_data_chunk(read: &mut CountingReader<impl Read>, chunk_size: u64) -> Result<Vec<u8>> {
+    let len = chunk_size.try_into().map_err(|_| ReadError::TooLarge)?;
+    let mut buf = vec![0u8; len];
+    read.read_exact(&mut buf)?;
+    read_pad(read, chunk_size, "DATA padding")?;
     Ok(buf)
 }
 
-fn read_wav_file(read: &mut CountingReader<impl Read>, total_size: u32) -> Result<WaveFile> {
+fn skip_chunk(
+    read: &mut CountingReader<impl Read + Seek>,
+    chunk_size: u64,
+    total_size: u64,
+    field: &'static str,
+) -> Result<()> {
+    // seeking past the end of the file is allowed, so check explicitly
+    match read.offset.checked_add(chunk_size) {
+        Some(end) if end <= total_size => {}
+        _ => return Err(ReadError::Truncated { offset: total_size }),
+    }
+    read.skip(chunk_size)?;
+    read_pad(read, chunk_size, field)
+}
+
+/// Read the chunks of a wave file, and either read or skip the audio data.
+fn read_wav_file(
+    read: &mut CountingReader<impl Read + Seek>,
+    total_size: u64,
+    read_data: bool,
+    rules: &Rules,
+) -> Result<(WaveHeader, Option<Vec<u8>>)> {
     // the RIFF chunk must be first
-    read_riff_chunk(read, total_size)?;
+    let (container, riff_size) = read_riff_chunk(read, total_size)?;
+    if !rules.rf64 && container != Container::Riff {
+        return Err(ReadError::unsupported(
+            "RIFF chunk ID",
+            Expected::Equal(RIFF_CHUNK_ID.into()),
+            container.chunk_id(),
+            0,
+        ));
+    }
+    // for RF64, the ds64 chunk must be second
+    let ds64 = match container {
+        Container::Riff => None,
+        Container::Rf64 | Container::Bw64 => Some(read_ds64_chunk(read, total_size, riff_size)?),
+    };
 
-    // the FMT chunk must be second
-    let fmt = read_fmt_chunk(read)?;
+    // the FMT chunk must be next
+    let fmt_start_pos = read.offset;
+    let (fmt, encoding) = read_fmt_chunk(read, rules)?;
+    let mut chunks = vec![ChunkInfo {
+        id: FMT_CHUNK_ID,
+        offset: fmt_start_pos,
+        size: read.offset - fmt_start_pos - 8,
+    }];
 
+    let mut fact = None;
     let mut cue = None;
+    let mut sampler = None;
+    let mut info = None;
+    let mut bext = None;
+    let mut ixml = None;
+    let mut data_chunk = None;
     let mut data = None;
-    loop {
+    while read.offset < total_size {
         let chunk_start_pos = read.offset;
         let (chunk_id, chunk_size) = read_chunk_header(read)?;
+        let size = match &ds64 {
+            Some(ds64) if chunk_size == u32::MAX => {
+                ds64.chunk_size(chunk_id).unwrap_or(chunk_size.into())
+            }
+            _ => chunk_size.into(),
+        };
+        if chunk_id != DATA_CHUNK_ID && size != chunk_size as u64 {
+            return Err(ReadError::unsupported(
+                "chunk size",
+                Expected::AtMost(u32::MAX.into()),
+                size,
+                read.prev,
+            ));
+        }
+        chunks.push(ChunkInfo {
+            id: chunk_id,
+            offset: chunk_start_pos,
+            size,
+        });
         match chunk_id {
             FMT_CHUNK_ID => {
-                bail!("Duplicate FMT chunk (at {})", chunk_start_pos);
+                return Err(ReadError::DuplicateChunk {
+                    chunk_id,
+                    offset: chunk_start_pos,
+                });
             }
             CUE_CHUNK_ID => {
                 if cue.is_some() {
-                    bail!("Duplicate CUE chunk (at {})", chunk_start_pos);
+                    return Err(ReadError::DuplicateChunk {
+                        chunk_id,
+                        offset: chunk_start_pos,
+                    });
+                }
+                cue = Some(read_cue_chunk(read, chunk_size, &encoding, rules)?);
+            }
+            FACT_CHUNK_ID => {
+                if fact.is_some() {
+                    return Err(ReadError::DuplicateChunk {
+                        chunk_id,
+                        offset: chunk_start_pos,
+                    });
                 }
-                cue = Some(read_cue_chunk(read, chunk_size)?);
+                fact = Some(read_fact_chunk(read, chunk_size)?);
+            }
+            SMPL_CHUNK_ID => {
+                if sampler.is_some() {
+                    return Err(ReadError::DuplicateChunk {
+                        chunk_id,
+                        offset: chunk_start_pos,
+                    });
+                }
+                sampler = Some(read_smpl_chunk(read, chunk_size)?);
+            }
+            LIST_CHUNK_ID => {
+                if info.is_some() {
+                    return Err(ReadError::DuplicateChunk {
+                        chunk_id,
+                        offset: chunk_start_pos,
+                    });
+                }
+                info = Some(read_list_chunk(read, chunk_size)?);
+            }
+            BEXT_CHUNK_ID => {
+                if bext.is_some() {
+                    return Err(ReadError::DuplicateChunk {
+                        chunk_id,
+                        offset: chunk_start_pos,
+                    });
+                }
+                bext = Some(read_bext_chunk(read, chunk_size)?);
+            }
+            IXML_CHUNK_ID => {
+                if ixml.is_some() {
+                    return Err(ReadError::DuplicateChunk {
+                        chunk_id,
+                        offset: chunk_start_pos,
+                    });
+                }
+                ixml = Some(read_ixml_chunk(read, chunk_size)?);
             }
             DATA_CHUNK_ID => {
-                if data.is_some() {
-                    bail!("Duplicate DATA chunk (at {})", chunk_start_pos);
+                if data_chunk.is_some() {
+                    return Err(ReadError::DuplicateChunk {
+                        chunk_id,
+                        offset: chunk_start_pos,
+                    });
+                }
+                data_chunk = Some((read.offset, size));
+                if read_data {
+                    data = Some(read_data_chunk(read, size)?);
+                } else {
+                    skip_chunk(read, size, total_size, "DATA padding")?;
                 }
-                data = Some(read_data_chunk(read, chunk_size)?);
-                break;
             }
+            JUNK_CHUNK_ID => skip_chunk(read, size, total_size, "JUNK padding")?,
             _ => {
-                bail!("Unknown chunk `{}` (at {})", chunk_id, chunk_start_pos);
+                return Err(ReadError::UnknownChunk {
+                    chunk_id,
+                    offset: chunk_start_pos,
+                });
             }
         }
     }
 
-    let data = data.ok_or_else(|| eyre!("WAVE file contains no DATA chunk"))?;
+    let (data_offset, data_size) = data_chunk.ok_or(ReadError::MissingChunk {
+        chunk_id: DATA_CHUNK_ID,
+    })?;
 
     if read.offset != total_size {
-        bail!(
-            "Expected `bytes read` == {}, but was {} (at {})",
-            total_size,
+        return Err(ReadError::invalid(
+            "bytes read",
+            Expected::Equal(total_size.into()),
             read.offset,
             read.offset,
-        )
+        ));
     }
 
     tracing::trace!("read {} bytes", read.offset);
-    Ok(WaveFile { fmt, cue, data })
+    let header = WaveHeader {
+        container,
+        fmt,
+        encoding,
+        fact,
+        cue,
+        sampler,
+        info,
+        bext,
+        ixml,
+        data_offset,
+        data_size,
+        chunks,
+    };
+    Ok((header, data))
 }
 
-pub(crate) fn read(buf: &[u8]) -> Result<WaveFile> {
-    let total_size = buf
-        .len()
-        .try_into()
-        .wrap_err("File size is greater than 4 GiB")?;
+/// Read and validate a wave file.
+///
+/// AIFF and AIFF-C files are converted to a wave file, with the markers as
+/// cue points. FLAC files are decoded to a wave file, with the cue points
+/// from the Vorbis comments or the cue sheet.
+pub fn read(buf: &[u8]) -> Result<WaveFile> {
+    if buf.starts_with(aiff::FORM_CHUNK_ID.as_ref()) {
+        return aiff::read_aiff(buf);
+    }
+    if buf.starts_with(&FLAC_MAGIC) {
+        return read_flac(buf);
+    }
+    // Cast safety: u64 >= usize
+    let total_size = buf.len() as u64;
     let cursor = Cursor::new(buf);
     let mut read = CountingReader::new(cursor);
-    read_wav_file(&mut read, total_size)
+    let (header, data) = read_wav_file(&mut read, total_size, true, Profile::Generic.rules())?;
+    Ok(WaveFile {
+        container: header.container,
+        fmt: header.fmt,
+        // the FMT chunk is always first
+        fmt_extension: header.encoding == Encoding::Pcm
+            && header.chunks[0].size == u64::from(FMT_CHUNK_SIZE_EXTENSION),
+        encoding: header.encoding,
+        fact: header.fact,
+        cue: header.cue,
+        labels: None,
+        sampler: header.sampler,
+        info: header.info,
+        bext: header.bext,
+        ixml: header.ixml,
+        // Panic safety: the data is always read
+        data: data.unwrap(),
+        chunk_order: header.chunks.iter().map(|chunk| chunk.id).collect(),
+    })
+}
+
+/// Read and validate the header and cue points of a wave file, skipping
+/// over the audio data.
+///
+/// The entire reader is read from the start, so the audio data is still
+/// validated to be of the correct size. The reader should be buffered.
+pub fn read_header<R: Read + Seek>(read: R) -> Result<WaveHeader> {
+    read_header_with(read, Profile::Generic)
 }
+
+/// Read and validate the header and cue points of a wave file like
+/// [`read_header`], and also check the container, FMT chunk, and the order
+/// of the cue points against the rules of a profile.
+///
+/// Whether the cue points are within the audio data is only known once it
+/// has been read, so check that with [`Profile::check_header`].
+pub fn read_header_with<R: Read + Seek>(mut read: R, profile: Profile) -> Result<WaveHeader> {
+    let map_err = |e| ReadError::Io {
+        offset: 0,
+        source: e,
+    };
+    let total_size = read.seek(SeekFrom::End(0)).map_err(map_err)?;
+    read.rewind().map_err(map_err)?;
+
+    let mut read = CountingReader::new(read);
+    let (header, _) = read_wav_file(&mut read, total_size, false, profile.rules())?;
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests;
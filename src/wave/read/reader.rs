@@ -1,10 +1,12 @@
-use super::ChunkId;
-use std::io::{Read, Result};
+use super::{ChunkId, ReadError};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+
+type Result<T> = std::result::Result<T, ReadError>;
 
 pub(crate) struct CountingReader<R: Read> {
     inner: R,
-    pub(crate) offset: u32,
-    pub(crate) prev: u32,
+    pub(crate) offset: u64,
+    pub(crate) prev: u64,
 }
 
 impl<R: Read> CountingReader<R> {
@@ -17,11 +19,24 @@ impl<R: Read> CountingReader<R> {
         }
     }
 
+    fn map_err(&self, e: io::Error) -> ReadError {
+        match e.kind() {
+            ErrorKind::UnexpectedEof => ReadError::Truncated {
+                offset: self.offset,
+            },
+            _ => ReadError::Io {
+                offset: self.offset,
+                source: e,
+            },
+        }
+    }
+
     #[inline]
     pub(crate) fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
-        self.inner.read_exact(buf)?;
+        self.inner.read_exact(buf).map_err(|e| self.map_err(e))?;
         self.prev = self.offset;
-        self.offset += buf.len() as u32;
+        // Cast safety: u64 >= usize
+        self.offset += buf.len() as u64;
         Ok(())
     }
 
@@ -32,6 +47,13 @@ impl<R: Read> CountingReader<R> {
         Ok(ChunkId::new(buf))
     }
 
+    #[inline]
+    pub(crate) fn read_u64(&mut self) -> Result<u64> {
+        let mut buf = [0; 8];
+        self.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
     #[inline]
     pub(crate) fn read_u32(&mut self) -> Result<u32> {
         let mut buf = [0; 4];
@@ -46,6 +68,20 @@ impl<R: Read> CountingReader<R> {
         Ok(u16::from_le_bytes(buf))
     }
 
+    #[inline]
+    pub(crate) fn read_u32_be(&mut self) -> Result<u32> {
+        let mut buf = [0; 4];
+        self.read_exact(&mut buf)?;
+        Ok(u32::from_be_bytes(buf))
+    }
+
+    #[inline]
+    pub(crate) fn read_u16_be(&mut self) -> Result<u16> {
+        let mut buf = [0; 2];
+        self.read_exact(&mut buf)?;
+        Ok(u16::from_be_bytes(buf))
+    }
+
     #[inline]
     pub(crate) fn read_u8(&mut self) -> Result<u8> {
         let mut buf = [0; 1];
@@ -53,3 +89,17 @@ impl<R: Read> CountingReader<R> {
         Ok(u8::from_le_bytes(buf))
     }
 }
+
+impl<R: Read + Seek> CountingReader<R> {
+    /// Skip over bytes, without checking the end of the file.
+    #[inline]
+    pub(crate) fn skip(&mut self, count: u64) -> Result<()> {
+        let end = self.offset.saturating_add(count);
+        self.inner
+            .seek(SeekFrom::Start(end))
+            .map_err(|e| self.map_err(e))?;
+        self.prev = self.offset;
+        self.offset += count;
+        Ok(())
+    }
+}
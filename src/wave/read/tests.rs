@@ -0,0 +1,478 @@
+use super::*;
+
+fn wav(fmt: &[u8], chunks: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(b"RIFF");
+    let size = 4 + 8 + fmt.len() + chunks.len();
+    buf.extend_from_slice(&(size as u32).to_le_bytes());
+    buf.extend_from_slice(b"WAVE");
+    buf.extend_from_slice(b"fmt ");
+    buf.extend_from_slice(&(fmt.len() as u32).to_le_bytes());
+    buf.extend_from_slice(fmt);
+    buf.extend_from_slice(chunks);
+    buf
+}
+
+/// An RF64 or BW64 file, with the data chunk size in the ds64 chunk.
+fn rf64(form: &[u8; 4], fmt: &[u8], data: &[u8]) -> Vec<u8> {
+    let size = 4 + 8 + 28 + 8 + fmt.len() + 8 + data.len();
+    let mut buf = Vec::new();
+    buf.extend_from_slice(form);
+    buf.extend_from_slice(&u32::MAX.to_le_bytes());
+    buf.extend_from_slice(b"WAVE");
+    buf.extend_from_slice(b"ds64");
+    buf.extend_from_slice(&28u32.to_le_bytes());
+    buf.extend_from_slice(&(size as u64).to_le_bytes());
+    buf.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    buf.extend_from_slice(&0u64.to_le_bytes());
+    buf.extend_from_slice(&0u32.to_le_bytes());
+    buf.extend_from_slice(b"fmt ");
+    buf.extend_from_slice(&(fmt.len() as u32).to_le_bytes());
+    buf.extend_from_slice(fmt);
+    buf.extend_from_slice(b"data");
+    buf.extend_from_slice(&u32::MAX.to_le_bytes());
+    buf.extend_from_slice(data);
+    buf
+}
+
+fn fmt_pcm(samples_per_sec: u32, bits_per_sample: u16) -> Vec<u8> {
+    let block_align = bits_per_sample / 8;
+    let mut fmt = Vec::new();
+    fmt.extend_from_slice(&WAVE_FORMAT_PCM.to_le_bytes());
+    fmt.extend_from_slice(&1u16.to_le_bytes());
+    fmt.extend_from_slice(&samples_per_sec.to_le_bytes());
+    fmt.extend_from_slice(&(samples_per_sec * block_align as u32).to_le_bytes());
+    fmt.extend_from_slice(&block_align.to_le_bytes());
+    fmt.extend_from_slice(&bits_per_sample.to_le_bytes());
+    fmt
+}
+
+fn cue_chunk(positions: &[u32]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(b"cue ");
+    buf.extend_from_slice(&(4 + positions.len() as u32 * 24).to_le_bytes());
+    buf.extend_from_slice(&(positions.len() as u32).to_le_bytes());
+    for (position, index) in positions.iter().zip(1u32..) {
+        buf.extend_from_slice(&index.to_le_bytes());
+        buf.extend_from_slice(&position.to_le_bytes());
+        buf.extend_from_slice(b"data");
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&position.to_le_bytes());
+    }
+    buf
+}
+
+fn data_chunk(data: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(b"data");
+    buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buf.extend_from_slice(data);
+    if data.len() & 1 == 1 {
+        buf.push(0);
+    }
+    buf
+}
+
+const DATA: &[u8] = b"data\x04\x00\x00\x00\x01\x02\x03\x04";
+
+#[test]
+fn read_pcm() {
+    let buf = wav(&fmt_pcm(22050, 16), DATA);
+    let wav = read(&buf).unwrap();
+    assert_eq!(wav.fmt.samples_per_sec, SamplesPerSec::Hz22050);
+    assert_eq!(wav.fmt.bits_per_sample, BitsPerSample::Sixteen);
+    assert_eq!(wav.cue, None);
+    assert_eq!(wav.data, vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn read_unsupported_sample_rate() {
+    let buf = wav(&fmt_pcm(48000, 16), DATA);
+    match read(&buf) {
+        Err(ReadError::Unsupported {
+            field,
+            actual,
+            offset,
+            ..
+        }) => {
+            assert_eq!(field, "FMT samples per sec");
+            assert_eq!(actual, Value::Number(48000));
+            assert_eq!(offset, 24);
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn read_truncated() {
+    let mut buf = wav(&fmt_pcm(22050, 16), DATA);
+    buf.truncate(buf.len() - 2);
+    // fix up the RIFF size, so the truncation is detected in the data chunk
+    let size = (buf.len() - 8) as u32;
+    buf[4..8].copy_from_slice(&size.to_le_bytes());
+    match read(&buf) {
+        Err(ReadError::Truncated { offset }) => assert_eq!(offset, 44),
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn read_invalid_riff_size() {
+    let mut buf = wav(&fmt_pcm(22050, 16), DATA);
+    buf.push(0);
+    match read(&buf) {
+        Err(ReadError::Invalid {
+            field,
+            expected,
+            actual,
+            offset,
+        }) => {
+            assert_eq!(field, "RIFF chunk size");
+            assert_eq!(expected, Expected::Equal(Value::Number(41)));
+            assert_eq!(actual, Value::Number(40));
+            assert_eq!(offset, 4);
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn read_rf64() {
+    let buf = rf64(b"RF64", &fmt_pcm(22050, 16), &[1, 2, 3, 4]);
+    let wav = read(&buf).unwrap();
+    assert_eq!(wav.container, Container::Rf64);
+    assert_eq!(wav.data, vec![1, 2, 3, 4]);
+
+    let header = read_header(Cursor::new(&buf)).unwrap();
+    assert_eq!(header.data_offset, 80);
+    assert_eq!(header.data_size, 4);
+    assert_eq!(header.chunks[1].size, 4);
+}
+
+#[test]
+fn read_bw64() {
+    let buf = rf64(b"BW64", &fmt_pcm(22050, 16), &[1, 2, 3, 4]);
+    let wav = read(&buf).unwrap();
+    assert_eq!(wav.container, Container::Bw64);
+    assert_eq!(wav.data, vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn read_rf64_invalid_ds64_riff_size() {
+    let mut buf = rf64(b"RF64", &fmt_pcm(22050, 16), &[1, 2, 3, 4]);
+    buf.extend_from_slice(&[0, 0]);
+    match read(&buf) {
+        Err(ReadError::Invalid { field, offset, .. }) => {
+            assert_eq!(field, "DS64 RIFF size");
+            assert_eq!(offset, 20);
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn read_unknown_chunk() {
+    let mut chunks = b"junk\x00\x00\x00\x00".to_vec();
+    chunks.extend_from_slice(DATA);
+    let buf = wav(&fmt_pcm(22050, 16), &chunks);
+    match read(&buf) {
+        Err(ReadError::UnknownChunk { chunk_id, offset }) => {
+            assert_eq!(chunk_id, ChunkId::new(*b"junk"));
+            assert_eq!(offset, 36);
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn read_junk_chunk() {
+    let mut chunks = b"JUNK\x03\x00\x00\x00\xFF\xFF\xFF\x00".to_vec();
+    chunks.extend_from_slice(&cue_chunk(&[2]));
+    chunks.extend_from_slice(DATA);
+    let buf = wav(&fmt_pcm(22050, 16), &chunks);
+    let wav = read(&buf).unwrap();
+    assert_eq!(wav.cue, Some(vec![2]));
+    assert_eq!(
+        wav.chunk_order,
+        vec![FMT_CHUNK_ID, JUNK_CHUNK_ID, CUE_CHUNK_ID, DATA_CHUNK_ID]
+    );
+    // the padding isn't written back
+    let mut chunks = cue_chunk(&[2]);
+    chunks.extend_from_slice(DATA);
+    assert_eq!(
+        write(&wav).unwrap(),
+        self::wav(&fmt_pcm(22050, 16), &chunks)
+    );
+}
+
+#[test]
+fn read_header_skips_data() {
+    let mut chunks = cue_chunk(&[2]);
+    chunks.extend_from_slice(&data_chunk(&[1, 2, 3, 4, 5]));
+    let buf = wav(&fmt_pcm(11025, 8), &chunks);
+    let header = read_header(Cursor::new(&buf)).unwrap();
+    assert_eq!(header.fmt.samples_per_sec, SamplesPerSec::Hz11025);
+    assert_eq!(header.cue, Some(vec![2]));
+    assert_eq!(header.data_offset, 44 + 36);
+    assert_eq!(header.data_size, 5);
+    assert_eq!(header.sample_count(), 5);
+    assert_eq!(&buf[80..85], &[1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn read_header_summary() {
+    let mut chunks = cue_chunk(&[2]);
+    chunks.extend_from_slice(&data_chunk(&[1, 2, 3, 4, 5]));
+    let buf = wav(&fmt_pcm(11025, 8), &chunks);
+    let header = read_header(Cursor::new(&buf)).unwrap();
+    assert_eq!(
+        header.summary(),
+        "RIFF PCM, 1 channel(s), 11025 Hz, 8 bits, 5 samples (0.000s), 1 cue point(s), 0 loop(s), data at 80 (5 bytes)"
+    );
+}
+
+#[test]
+fn read_header_truncated_data() {
+    let mut buf = wav(&fmt_pcm(22050, 16), DATA);
+    // claim more data than there is
+    buf[40] = 8;
+    buf.extend_from_slice(&[0, 0]);
+    let size = (buf.len() - 8) as u32;
+    buf[4..8].copy_from_slice(&size.to_le_bytes());
+    match read_header(Cursor::new(&buf)) {
+        Err(ReadError::Truncated { offset }) => assert_eq!(offset, 50),
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn read_cue_after_data() {
+    let mut chunks = data_chunk(&[1, 2, 3]);
+    chunks.extend_from_slice(&cue_chunk(&[1]));
+    let buf = wav(&fmt_pcm(11025, 8), &chunks);
+    let file = read(&buf).unwrap();
+    assert_eq!(file.cue, Some(vec![1]));
+    assert_eq!(file.data, vec![1, 2, 3]);
+    assert_eq!(
+        file.chunk_order,
+        vec![FMT_CHUNK_ID, DATA_CHUNK_ID, CUE_CHUNK_ID]
+    );
+
+    // the original order is kept unless another position is requested
+    assert_eq!(write(&file).unwrap(), buf);
+    let mut chunks = cue_chunk(&[1]);
+    chunks.extend_from_slice(&data_chunk(&[1, 2, 3]));
+    let before = wav(&fmt_pcm(11025, 8), &chunks);
+    assert_eq!(write_with(&file, CuePosition::BeforeData).unwrap(), before);
+    let moved = read(&before).unwrap();
+    assert_eq!(write_with(&moved, CuePosition::AfterData).unwrap(), buf);
+}
+
+#[test]
+fn read_ima_adpcm_invalid_samples_per_block() {
+    let mut fmt = Vec::new();
+    fmt.extend_from_slice(&WAVE_FORMAT_IMA_ADPCM.to_le_bytes());
+    fmt.extend_from_slice(&1u16.to_le_bytes());
+    fmt.extend_from_slice(&22050u32.to_le_bytes());
+    fmt.extend_from_slice(&11100u32.to_le_bytes());
+    fmt.extend_from_slice(&512u16.to_le_bytes());
+    fmt.extend_from_slice(&4u16.to_le_bytes());
+    fmt.extend_from_slice(&2u16.to_le_bytes());
+    fmt.extend_from_slice(&1016u16.to_le_bytes());
+    let buf = wav(&fmt, DATA);
+    match read(&buf) {
+        Err(ReadError::Invalid {
+            field,
+            expected,
+            actual,
+            offset,
+        }) => {
+            assert_eq!(field, "FMT samples per block");
+            assert_eq!(expected, Expected::Equal(Value::Number(1017)));
+            assert_eq!(actual, Value::Number(1016));
+            assert_eq!(offset, 38);
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+fn extended(rate: u32) -> [u8; 10] {
+    let zeros = rate.leading_zeros();
+    let exponent = (16383 + 31 - zeros) as u16;
+    let mantissa = (rate as u64) << (32 + zeros);
+    let mut buf = [0; 10];
+    buf[..2].copy_from_slice(&exponent.to_be_bytes());
+    buf[2..].copy_from_slice(&mantissa.to_be_bytes());
+    buf
+}
+
+fn aiff_chunk(chunk_id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut buf = chunk_id.to_vec();
+    buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    buf.extend_from_slice(data);
+    if data.len() & 1 == 1 {
+        buf.push(0);
+    }
+    buf
+}
+
+/// An AIFF or AIFF-C (if `compression` is given) file with one channel.
+fn aiff(
+    rate: u32,
+    bits: u16,
+    compression: Option<&[u8; 4]>,
+    samples: &[u8],
+    chunks: &[u8],
+) -> Vec<u8> {
+    let frames = samples.len() as u32 / (bits as u32 / 8);
+    let mut comm = Vec::new();
+    comm.extend_from_slice(&1u16.to_be_bytes());
+    comm.extend_from_slice(&frames.to_be_bytes());
+    comm.extend_from_slice(&bits.to_be_bytes());
+    comm.extend_from_slice(&extended(rate));
+    if let Some(compression) = compression {
+        comm.extend_from_slice(compression);
+        comm.extend_from_slice(b"\x00\x00");
+    }
+    let mut ssnd = vec![0; 8];
+    ssnd.extend_from_slice(samples);
+
+    let mut body = if compression.is_some() {
+        b"AIFC"
+    } else {
+        b"AIFF"
+    }
+    .to_vec();
+    body.extend_from_slice(&aiff_chunk(b"COMM", &comm));
+    body.extend_from_slice(chunks);
+    body.extend_from_slice(&aiff_chunk(b"SSND", &ssnd));
+    let mut buf = b"FORM".to_vec();
+    buf.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    buf.extend_from_slice(&body);
+    buf
+}
+
+fn mark_chunk(markers: &[(u16, u32, &str)]) -> Vec<u8> {
+    let mut data = (markers.len() as u16).to_be_bytes().to_vec();
+    for (id, position, name) in markers {
+        data.extend_from_slice(&id.to_be_bytes());
+        data.extend_from_slice(&position.to_be_bytes());
+        data.push(name.len() as u8);
+        data.extend_from_slice(name.as_bytes());
+        if name.len() & 1 == 0 {
+            data.push(0);
+        }
+    }
+    aiff_chunk(b"MARK", &data)
+}
+
+#[test]
+fn read_aiff_markers_and_loops() {
+    let mut chunks = mark_chunk(&[(7, 3, "end"), (5, 1, "start")]);
+    let mut inst = vec![61, (-25i8) as u8, 0, 127, 1, 127, 0, 0];
+    // a forward sustain loop, and no release loop
+    inst.extend_from_slice(&[0, 1, 0, 5, 0, 7]);
+    inst.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    chunks.extend_from_slice(&aiff_chunk(b"INST", &inst));
+    chunks.extend_from_slice(&aiff_chunk(b"NAME", b"Missile lock"));
+    let buf = aiff(22050, 16, None, &[0, 1, 0, 2, 0, 3, 0, 4], &chunks);
+
+    let wav = read(&buf).unwrap();
+    assert_eq!(wav.container, Container::Riff);
+    assert_eq!(wav.fmt.samples_per_sec, SamplesPerSec::Hz22050);
+    assert_eq!(wav.fmt.bits_per_sample, BitsPerSample::Sixteen);
+    assert_eq!(wav.data, vec![1, 0, 2, 0, 3, 0, 4, 0]);
+    assert_eq!(wav.cue, Some(vec![1, 3]));
+    // the names follow the markers into the cue points
+    let labels = crate::wave::cue_from_wav(&wav).unwrap().labels;
+    assert_eq!(labels, Some(vec!["start".to_string(), "end".to_string()]));
+
+    let sampler = wav.sampler.unwrap();
+    assert_eq!(sampler.midi_unity_note, 60);
+    assert_eq!(sampler.midi_pitch_fraction, 0xC000_0000);
+    assert_eq!(sampler.loops.len(), 1);
+    assert_eq!(sampler.loops[0].cue_point_id, 1);
+    assert_eq!(sampler.loops[0].loop_type, 0);
+    assert_eq!(sampler.loops[0].start, 1);
+    assert_eq!(sampler.loops[0].end, 2);
+
+    let info = wav.info.unwrap();
+    assert_eq!(info.get(ChunkId::new(*b"INAM")), Some("Missile lock"));
+}
+
+#[test]
+fn read_aiff_8_bit() {
+    let buf = aiff(11025, 8, None, &[0x80, 0xFF, 0x00, 0x7F], &[]);
+    let wav = read(&buf).unwrap();
+    assert_eq!(wav.fmt.bits_per_sample, BitsPerSample::Eight);
+    assert_eq!(wav.data, vec![0x00, 0x7F, 0x80, 0xFF]);
+    assert_eq!(wav.cue, None);
+    assert_eq!(wav.sampler, None);
+}
+
+#[test]
+fn read_aiff_unnamed_markers() {
+    let chunks = mark_chunk(&[(1, 1, ""), (2, 0, "")]);
+    let wav = read(&aiff(11025, 8, None, &[0, 0], &chunks)).unwrap();
+    assert_eq!(wav.cue, Some(vec![0, 1]));
+    assert_eq!(wav.labels, None);
+
+    let chunks = mark_chunk(&[(1, 1, "loop"), (2, 0, "")]);
+    let wav = read(&aiff(11025, 8, None, &[0, 0], &chunks)).unwrap();
+    assert_eq!(wav.labels, Some(vec![String::new(), "loop".to_string()]));
+}
+
+#[test]
+fn read_aifc_sowt() {
+    let buf = aiff(44100, 16, Some(b"sowt"), &[1, 0, 2, 0], &[]);
+    let wav = read(&buf).unwrap();
+    assert_eq!(wav.fmt.samples_per_sec, SamplesPerSec::Hz44100);
+    assert_eq!(wav.data, vec![1, 0, 2, 0]);
+}
+
+#[test]
+fn read_aifc_twos() {
+    let buf = aiff(22050, 16, Some(b"twos"), &[0, 1, 0, 2], &[]);
+    let wav = read(&buf).unwrap();
+    assert_eq!(wav.data, vec![1, 0, 2, 0]);
+
+    let buf = aiff(22050, 8, Some(b"twos"), &[0x80, 0x7F], &[]);
+    assert_eq!(read(&buf).unwrap().data, vec![0x00, 0xFF]);
+}
+
+#[test]
+fn read_aifc_unsupported_compression() {
+    let buf = aiff(44100, 16, Some(b"ima4"), &[1, 0, 2, 0], &[]);
+    match read(&buf) {
+        Err(ReadError::Unsupported { field, offset, .. }) => {
+            assert_eq!(field, "COMM compression type");
+            assert_eq!(offset, 38);
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn read_aiff_unsupported_sample_rate() {
+    let buf = aiff(48000, 16, None, &[0, 1], &[]);
+    match read(&buf) {
+        Err(ReadError::Unsupported { field, actual, .. }) => {
+            assert_eq!(field, "COMM sample rate");
+            assert_eq!(actual, Value::Number(48000));
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn read_header_aiff_unsupported() {
+    let buf = aiff(22050, 16, None, &[0, 1], &[]);
+    match read_header(Cursor::new(&buf)) {
+        Err(ReadError::Unsupported { field, offset, .. }) => {
+            assert_eq!(field, "RIFF chunk ID");
+            assert_eq!(offset, 0);
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
@@ -0,0 +1,58 @@
+use super::{read, read_header, write, ChunkId, WaveHeader, RIFF_CHUNK_ID};
+use crate::Result;
+use eyre::Context as _;
+use std::io::Cursor;
+
+/// The first difference between a wave file and the same file after being
+/// read and written again, as found by [`roundtrip`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The offset of the first differing byte, or the length of the shorter
+    /// file if one is a prefix of the other
+    pub offset: u64,
+    /// The chunk of the original file the offset is in. The RIFF chunk ID is
+    /// used for the RIFF header (including the ds64 chunk), and for offsets
+    /// past the end of the file
+    pub chunk_id: ChunkId,
+    /// The size of the original file
+    pub original_size: u64,
+    /// The size of the written file
+    pub written_size: u64,
+}
+
+/// Read a wave file and write it again, and check that the bytes are
+/// unchanged.
+///
+/// Returns the first difference, if any. Files that cannot be read or
+/// written are an error.
+pub fn roundtrip(buf: &[u8]) -> Result<Option<Mismatch>> {
+    let header = read_header(Cursor::new(buf))?;
+    let wav = read(buf)?;
+    let written = write(&wav).wrap_err("Error constructing file")?;
+    Ok(first_mismatch(&header, buf, &written))
+}
+
+fn first_mismatch(header: &WaveHeader, original: &[u8], written: &[u8]) -> Option<Mismatch> {
+    let offset = match original.iter().zip(written).position(|(a, b)| a != b) {
+        Some(offset) => offset,
+        None if original.len() == written.len() => return None,
+        None => original.len().min(written.len()),
+    };
+
+    // Cast safety: u64 >= usize
+    let offset = offset as u64;
+    let chunk_id = header
+        .chunks
+        .iter()
+        .find(|chunk| chunk.offset <= offset && offset < chunk.end())
+        .map_or(RIFF_CHUNK_ID, |chunk| chunk.id);
+    Some(Mismatch {
+        offset,
+        chunk_id,
+        original_size: original.len() as u64,
+        written_size: written.len() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,94 @@
+use super::*;
+use crate::wave::{Container, CUE_CHUNK_ID, DATA_CHUNK_ID, FMT_CHUNK_ID};
+
+/// An 8-bit 11025 Hz wave file, with an 18-byte FMT chunk if `fmt_extension`.
+fn wav(fmt_extension: bool, chunks: &[&[u8]]) -> Vec<u8> {
+    let mut fmt = Vec::new();
+    fmt.extend_from_slice(&1u16.to_le_bytes());
+    fmt.extend_from_slice(&1u16.to_le_bytes());
+    fmt.extend_from_slice(&11025u32.to_le_bytes());
+    fmt.extend_from_slice(&11025u32.to_le_bytes());
+    fmt.extend_from_slice(&1u16.to_le_bytes());
+    fmt.extend_from_slice(&8u16.to_le_bytes());
+    if fmt_extension {
+        fmt.extend_from_slice(&0u16.to_le_bytes());
+    }
+
+    let mut body = b"WAVEfmt ".to_vec();
+    body.extend_from_slice(&(fmt.len() as u32).to_le_bytes());
+    body.extend_from_slice(&fmt);
+    for chunk in chunks {
+        body.extend_from_slice(chunk);
+    }
+    let mut buf = b"RIFF".to_vec();
+    buf.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&body);
+    buf
+}
+
+const CUE: &[u8] = b"cue \x1c\x00\x00\x00\x01\x00\x00\x00\
+    \x01\x00\x00\x00\x02\x00\x00\x00data\x00\x00\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00";
+const DATA: &[u8] = b"data\x03\x00\x00\x00\x01\x02\x03\x00";
+
+#[test]
+fn roundtrip_fmt() {
+    assert_eq!(roundtrip(&wav(false, &[DATA])).unwrap(), None);
+}
+
+#[test]
+fn roundtrip_fmt_extension() {
+    assert_eq!(roundtrip(&wav(true, &[CUE, DATA])).unwrap(), None);
+}
+
+#[test]
+fn roundtrip_cue_after_data() {
+    assert_eq!(roundtrip(&wav(true, &[DATA, CUE])).unwrap(), None);
+}
+
+#[test]
+fn roundtrip_rf64() {
+    let mut wav = read(&wav(true, &[CUE, DATA])).unwrap();
+    wav.container = Container::Rf64;
+    let buf = write(&wav).unwrap();
+    assert_eq!(&buf[..4], b"RF64");
+    assert_eq!(roundtrip(&buf).unwrap(), None);
+}
+
+#[test]
+fn roundtrip_invalid() {
+    let mut buf = wav(false, &[DATA]);
+    buf[0] = b'X';
+    assert!(roundtrip(&buf).is_err());
+}
+
+#[test]
+fn first_mismatch_chunk() {
+    let original = wav(true, &[CUE, DATA]);
+    let header = read_header(Cursor::new(&original)).unwrap();
+
+    let mut written = original.clone();
+    written[30] ^= 1;
+    let mismatch = first_mismatch(&header, &original, &written).unwrap();
+    assert_eq!(mismatch.offset, 30);
+    assert_eq!(mismatch.chunk_id, FMT_CHUNK_ID);
+
+    let mut written = original.clone();
+    written[50] ^= 1;
+    assert_eq!(
+        first_mismatch(&header, &original, &written)
+            .unwrap()
+            .chunk_id,
+        CUE_CHUNK_ID
+    );
+
+    let written = &original[..original.len() - 1];
+    let mismatch = first_mismatch(&header, &original, written).unwrap();
+    assert_eq!(mismatch.offset, original.len() as u64 - 1);
+    assert_eq!(mismatch.chunk_id, DATA_CHUNK_ID);
+    assert_eq!(mismatch.written_size, mismatch.original_size - 1);
+
+    let mut written = original.clone();
+    written.push(0);
+    let mismatch = first_mismatch(&header, &original, &written).unwrap();
+    assert_eq!(mismatch.chunk_id, RIFF_CHUNK_ID);
+}
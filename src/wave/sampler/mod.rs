@@ -0,0 +1,109 @@
+use super::SamplesPerSec;
+use serde::{Deserialize, Serialize};
+
+/// The MIDI note of middle C, the default unity note.
+const MIDDLE_C: u32 = 60;
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+/// The sampler chunk (`smpl`) of a wave file, with the loop points.
+///
+/// All fields are stored as-is, so reading and writing a wave file keeps
+/// the chunk unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Sampler {
+    /// The MIDI manufacturer code, or zero
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub manufacturer: u32,
+    /// The product code of the manufacturer, or zero
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub product: u32,
+    /// The duration of one sample in nanoseconds
+    #[serde(default)]
+    pub sample_period: u32,
+    /// The MIDI note that plays the sample at its original pitch
+    #[serde(default = "middle_c")]
+    pub midi_unity_note: u32,
+    /// The fraction of a semitone above the unity note, where `0x80000000`
+    /// is half a semitone
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub midi_pitch_fraction: u32,
+    /// The SMPTE format (0, 24, 25, 29, or 30 frames per second)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub smpte_format: u32,
+    /// The SMPTE offset, as `0xhhmmssff`
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub smpte_offset: u32,
+    /// The sample loops
+    #[serde(default)]
+    pub loops: Vec<SampleLoop>,
+    /// Manufacturer-specific data after the loops
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sampler_data: Vec<u8>,
+}
+
+fn middle_c() -> u32 {
+    MIDDLE_C
+}
+
+/// A sample loop of the sampler chunk.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SampleLoop {
+    /// The ID of the cue point the loop is associated with, or zero
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub cue_point_id: u32,
+    /// The loop type: 0 is forward, 1 is alternating (ping-pong), and 2 is
+    /// backward
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub loop_type: u32,
+    /// The first sample of the loop
+    pub start: u32,
+    /// The last sample of the loop, which is played
+    pub end: u32,
+    /// The fraction of a sample to fine-tune the loop end
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub fraction: u32,
+    /// How often the loop is played, zero for infinitely
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub play_count: u32,
+}
+
+impl Sampler {
+    /// A sampler chunk without loops for the sample rate, played at pitch
+    /// with middle C.
+    pub fn new(samples_per_sec: SamplesPerSec) -> Self {
+        Self {
+            sample_period: 1_000_000_000 / samples_per_sec.as_u32(),
+            midi_unity_note: MIDDLE_C,
+            ..Self::default()
+        }
+    }
+
+    /// Derive forward loops from pairs of cue points.
+    ///
+    /// The first and second cue point are the start and end of the first
+    /// loop, the third and fourth of the second loop, and so on. The loop
+    /// ends before the second cue point of the pair. If there is an odd
+    /// number of cue points, the last one is ignored.
+    pub fn loops_from_cues(cue: &[u32]) -> Vec<SampleLoop> {
+        if cue.len() % 2 == 1 {
+            tracing::warn!("Odd number of cue points, ignoring the last for loops...");
+        }
+        cue.chunks_exact(2)
+            .zip((1u32..).step_by(2))
+            .map(|(pair, cue_point_id)| SampleLoop {
+                cue_point_id,
+                loop_type: 0,
+                start: pair[0].min(pair[1]),
+                end: pair[0].max(pair[1]).saturating_sub(1),
+                fraction: 0,
+                play_count: 0,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,78 @@
+use super::*;
+use crate::wave::{read, roundtrip, write, BitsPerSample, Channels, Format, WaveFile};
+
+fn wav(sampler: Option<Sampler>) -> WaveFile {
+    let fmt = Format {
+        channels: Channels::One,
+        samples_per_sec: SamplesPerSec::Hz44100,
+        bits_per_sample: BitsPerSample::Sixteen,
+    };
+    WaveFile {
+        cue: Some(vec![10, 20]),
+        sampler,
+        ..WaveFile::new(fmt, vec![0; 100])
+    }
+}
+
+#[test]
+fn loops_from_cue_pairs() {
+    let loops = Sampler::loops_from_cues(&[10, 20, 40, 30, 50]);
+    assert_eq!(
+        loops,
+        vec![
+            SampleLoop {
+                cue_point_id: 1,
+                loop_type: 0,
+                start: 10,
+                end: 19,
+                fraction: 0,
+                play_count: 0,
+            },
+            SampleLoop {
+                cue_point_id: 3,
+                loop_type: 0,
+                start: 30,
+                end: 39,
+                fraction: 0,
+                play_count: 0,
+            },
+        ]
+    );
+}
+
+#[test]
+fn sampler_json_defaults() {
+    let sampler: Sampler = serde_json::from_str(r#"{"loops":[{"start":1,"end":9}]}"#).unwrap();
+    assert_eq!(sampler.midi_unity_note, 60);
+    assert_eq!(sampler.loops[0].start, 1);
+    assert_eq!(sampler.loops[0].end, 9);
+    assert_eq!(sampler.loops[0].loop_type, 0);
+
+    let json = serde_json::to_string(&Sampler::new(SamplesPerSec::Hz44100)).unwrap();
+    assert_eq!(
+        json,
+        r#"{"sample_period":22675,"midi_unity_note":60,"loops":[]}"#
+    );
+}
+
+#[test]
+fn write_and_read_sampler() {
+    let mut sampler = Sampler::new(SamplesPerSec::Hz44100);
+    sampler.loops = Sampler::loops_from_cues(&[10, 20]);
+    sampler.loops[0].play_count = 3;
+    // odd sizes are padded
+    sampler.sampler_data = vec![1, 2, 3];
+
+    let buf = write(&wav(Some(sampler.clone()))).unwrap();
+    let smpl = buf.windows(4).position(|w| w == b"smpl").unwrap();
+    assert_eq!(&buf[smpl + 4..smpl + 8], &(36u32 + 24 + 3).to_le_bytes());
+    assert_eq!(read(&buf).unwrap().sampler, Some(sampler));
+    assert_eq!(roundtrip(&buf).unwrap(), None);
+}
+
+#[test]
+fn read_without_sampler() {
+    let buf = write(&wav(None)).unwrap();
+    assert!(!buf.windows(4).any(|w| w == b"smpl"));
+    assert_eq!(read(&buf).unwrap().sampler, None);
+}
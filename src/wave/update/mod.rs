@@ -0,0 +1,404 @@
+use super::write::{
+    write_bext, write_cue, write_ixml, write_junk, write_list, write_riff_header, write_smpl,
+};
+use super::{
+    chunk_order, ChunkId, Container, CuePosition, WaveHeader, BEXT_CHUNK_ID, CUE_CHUNK_ID,
+    DATA_CHUNK_ID, DS64_CHUNK_MIN_SIZE, FMT_CHUNK_ID, IXML_CHUNK_ID, JUNK_CHUNK_ID, LIST_CHUNK_ID,
+    RIFF_HEADER_SIZE, SMPL_CHUNK_ID,
+};
+use eyre::{eyre, Context as _, OptionExt as _, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// How the chunks were written by [`update_in_place`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateMethod {
+    /// The chunks were the same size, and were overwritten
+    Patched,
+    /// The chunks before the data chunk changed size or were removed, but fit
+    /// in the space of the old chunks and any `JUNK` chunks next to them, so
+    /// they were overwritten and the rest of the space filled with a `JUNK`
+    /// chunk
+    Padded,
+    /// The chunks that changed size were at the end of the file, or are new
+    /// and go at the end, so they were appended and the file size updated
+    Appended,
+    /// The chunks changed size, so the file was rewritten with the audio data
+    /// copied as-is
+    Rewritten,
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".zcue-tmp");
+    path.with_file_name(name)
+}
+
+/// The offset and bytes of the RIFF size, which for RF64 is in the ds64
+/// chunk. Returns `None` if a RIFF file would be greater than 4 GiB.
+fn riff_size_field(container: Container, riff_size: u64) -> Option<(u64, Vec<u8>)> {
+    match container {
+        Container::Riff => {
+            let riff_size: u32 = riff_size.try_into().ok()?;
+            Some((4, riff_size.to_le_bytes().to_vec()))
+        }
+        // the RIFF size is the first field of the ds64 chunk
+        Container::Rf64 | Container::Bw64 => {
+            Some((RIFF_HEADER_SIZE + 8, riff_size.to_le_bytes().to_vec()))
+        }
+    }
+}
+
+/// Overwrite chunks of the same size.
+fn patch(file: &mut File, patches: &[(u64, &[u8])]) -> io::Result<()> {
+    for (offset, chunk) in patches {
+        file.seek(SeekFrom::Start(*offset))?;
+        file.write_all(chunk)?;
+    }
+    Ok(())
+}
+
+/// Overwrite chunks of a copy of the wave file.
+fn patch_copy(path: &Path, temp: &Path, patches: &[(u64, &[u8])]) -> Result<()> {
+    fs::copy(path, temp)?;
+    let mut file = OpenOptions::new().write(true).open(temp)?;
+    patch(&mut file, patches)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Fit the chunks from the first changed one up to the data chunk into the
+/// space of the old chunks, any `JUNK` chunks before them, and any chunks
+/// that were removed, padding the rest with a `JUNK` chunk.
+///
+/// Returns the offset and bytes to write, and the index in the order of the
+/// first chunk after them, or `None` if the chunks don't fit, or chunks after
+/// them would have to move.
+fn pad(
+    header: &WaveHeader,
+    order: &[ChunkId],
+    new_chunks: &[(ChunkId, Vec<u8>)],
+    first_changed: usize,
+) -> Result<Option<(u64, Vec<u8>, usize)>> {
+    let original: Vec<ChunkId> = header.chunks.iter().map(|chunk| chunk.id).collect();
+    let new_chunk = |id| new_chunks.iter().find(|(new_id, _)| *new_id == id);
+
+    // padding before the changed chunks can be used up
+    let mut start = first_changed;
+    while start > 0 && order[start - 1] == JUNK_CHUNK_ID {
+        start -= 1;
+    }
+    if order[..start] != original[..start] {
+        return Ok(None);
+    }
+    // the first chunk after them that isn't rewritten stays in place, along
+    // with everything after it
+    let Some(end) = order[start..]
+        .iter()
+        .position(|&id| id != JUNK_CHUNK_ID && new_chunk(id).is_none())
+        .map(|index| start + index)
+    else {
+        return Ok(None);
+    };
+    // Panic safety: chunks that aren't rewritten are in the file
+    let kept = original.iter().position(|&id| id == order[end]).unwrap();
+    if order[end..] != original[kept..] {
+        return Ok(None);
+    }
+    let rewritten: Vec<ChunkId> = order[start..end]
+        .iter()
+        .copied()
+        .filter(|&id| id != JUNK_CHUNK_ID)
+        .collect();
+    // chunks can't be moved from elsewhere in the file
+    let moved = rewritten.iter().any(|id| {
+        original
+            .iter()
+            .position(|original_id| original_id == id)
+            .is_some_and(|index| index < start || index >= kept)
+    });
+    if moved {
+        return Ok(None);
+    }
+
+    let offset = header.chunks[start].offset;
+    let space = header.chunks[kept].offset - offset;
+    let mut bytes = Vec::new();
+    for &id in &rewritten {
+        // Panic safety: only chunks with new bytes are rewritten
+        bytes.extend_from_slice(&new_chunk(id).unwrap().1);
+    }
+    // Cast safety: u64 >= usize
+    let Some(remaining) = space.checked_sub(bytes.len() as u64) else {
+        return Ok(None);
+    };
+    match remaining {
+        0 => {}
+        // too small for a chunk header
+        1..=7 => return Ok(None),
+        _ => bytes.extend_from_slice(&write_junk(remaining)?),
+    }
+    Ok(Some((offset, bytes, end)))
+}
+
+fn append(
+    path: &Path,
+    temp: &Path,
+    patches: &[(u64, &[u8])],
+    offset: u64,
+    chunks: &[&[u8]],
+    riff_size: (u64, Vec<u8>),
+) -> Result<()> {
+    fs::copy(path, temp)?;
+    let mut file = OpenOptions::new().write(true).open(temp)?;
+    patch(&mut file, patches)?;
+    file.set_len(offset)?;
+    file.seek(SeekFrom::Start(offset))?;
+    for chunk in chunks {
+        file.write_all(chunk)?;
+    }
+    let (riff_size_offset, riff_size) = riff_size;
+    file.seek(SeekFrom::Start(riff_size_offset))?;
+    file.write_all(&riff_size)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+fn rewrite(
+    path: &Path,
+    temp: &Path,
+    header: &WaveHeader,
+    order: &[ChunkId],
+    new_chunks: &[(ChunkId, Vec<u8>)],
+) -> Result<()> {
+    let find_new = |id| new_chunks.iter().find(|(new_id, _)| *new_id == id);
+    let find_existing = |id| {
+        header
+            .chunks
+            .iter()
+            .find(|chunk| chunk.id == id)
+            .ok_or_else(|| eyre!("Missing chunk `{}`", id))
+    };
+
+    let mut riff_size = 4u64;
+    for &chunk_id in order {
+        let size = match find_new(chunk_id) {
+            // Cast safety: u64 >= usize
+            Some((_, chunk)) => chunk.len() as u64,
+            None => {
+                let chunk = find_existing(chunk_id)?;
+                chunk.end() - chunk.offset
+            }
+        };
+        riff_size += size;
+    }
+    let container = match header.container {
+        Container::Riff if riff_size > u32::MAX.into() => {
+            tracing::debug!("file size is greater than 4 GiB, writing RF64");
+            Container::Rf64
+        }
+        container => container,
+    };
+    if container != Container::Riff {
+        riff_size += 8 + u64::from(DS64_CHUNK_MIN_SIZE);
+    }
+
+    let mut read = BufReader::new(File::open(path)?);
+    let file = File::create(temp)?;
+    // keep the permissions of the original, which `fs::copy` does for the
+    // other methods
+    file.set_permissions(fs::metadata(path)?.permissions())?;
+    let mut write = BufWriter::new(file);
+
+    write.write_all(&write_riff_header(
+        container,
+        riff_size,
+        header.data_size,
+        header.sample_count(),
+    )?)?;
+
+    for &chunk_id in order {
+        if let Some((_, chunk)) = find_new(chunk_id) {
+            write.write_all(chunk)?;
+            continue;
+        }
+        let chunk = find_existing(chunk_id)?;
+        tracing::trace!("copying chunk `{}` at {}", chunk.id, chunk.offset);
+        read.seek(SeekFrom::Start(chunk.offset))?;
+        let len = chunk.end() - chunk.offset;
+        let copied = io::copy(&mut (&mut read).take(len), &mut write)?;
+        if copied != len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+    }
+
+    let file = write.into_inner().map_err(|e| e.into_error())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Write the cue points and other metadata chunks (sampler, `INFO` list,
+/// `bext`, and iXML) of the header to the wave file the header was read from,
+/// without reading the audio data into memory. If a metadata chunk was
+/// removed from the header, it is removed from the file.
+///
+/// If the chunks stay in the same place and are the same size, only those
+/// chunks are overwritten. If the chunks that changed size are before the
+/// data chunk and fit in the space of the old chunks and any `JUNK` chunks
+/// next to them, they are overwritten and padded with a `JUNK` chunk. If the
+/// chunks that changed size are the last chunks, the file is truncated and
+/// the new chunks appended. Otherwise, the file is rewritten with the audio
+/// data and other chunks copied as-is.
+///
+/// Every change is made to a copy of the wave file with the same
+/// permissions, which then replaces the wave file, so the wave file is
+/// unchanged if writing fails. If a backup path is given, the original file
+/// is also kept there.
+pub fn update_in_place(
+    path: &Path,
+    header: &WaveHeader,
+    cue_position: CuePosition,
+    backup: Option<&Path>,
+) -> crate::Result<UpdateMethod> {
+    let cue = header.cue.as_deref().ok_or_eyre("No cue points to write")?;
+    let mut new_chunks = vec![(CUE_CHUNK_ID, write_cue(cue, &header.encoding)?)];
+    if let Some(sampler) = &header.sampler {
+        new_chunks.push((SMPL_CHUNK_ID, write_smpl(sampler)?));
+    }
+    if let Some(info) = &header.info {
+        new_chunks.push((LIST_CHUNK_ID, write_list(info)?));
+    }
+    if let Some(bext) = &header.bext {
+        new_chunks.push((BEXT_CHUNK_ID, write_bext(bext)?));
+    }
+    if let Some(ixml) = &header.ixml {
+        new_chunks.push((IXML_CHUNK_ID, write_ixml(ixml)?));
+    }
+    let existing = |id| header.chunks.iter().find(|chunk| chunk.id == id);
+
+    let original: Vec<ChunkId> = header.chunks.iter().map(|chunk| chunk.id).collect();
+    // chunks that were removed from the header are dropped
+    let mut optional: Vec<ChunkId> = original
+        .iter()
+        .copied()
+        .filter(|&id| match id {
+            FMT_CHUNK_ID | DATA_CHUNK_ID => false,
+            SMPL_CHUNK_ID => header.sampler.is_some(),
+            LIST_CHUNK_ID => header.info.is_some(),
+            BEXT_CHUNK_ID => header.bext.is_some(),
+            IXML_CHUNK_ID => header.ixml.is_some(),
+            _ => true,
+        })
+        .collect();
+    for (id, _) in &new_chunks {
+        if !optional.contains(id) {
+            optional.push(*id);
+        }
+    }
+    let order = chunk_order(&original, &optional, cue_position);
+
+    let new_chunk = |id| new_chunks.iter().find(|(new_id, _)| *new_id == id);
+    // Cast safety: u64 >= usize
+    let resized = |id| match (existing(id), new_chunk(id)) {
+        (Some(chunk), Some((_, bytes))) => chunk.end() - chunk.offset != bytes.len() as u64,
+        (None, Some(_)) => true,
+        (_, None) => false,
+    };
+    // every chunk from the first resized one on has to be written
+    let first_resized = order.iter().position(|&id| resized(id));
+    let appendable = first_resized.is_some_and(|index| {
+        order[..index] == original[..index]
+            && order[index..].iter().all(|&id| new_chunk(id).is_some())
+    });
+    // Panic safety: the FMT and DATA chunks are always present
+    let append_at = first_resized.map(|index| {
+        original
+            .get(index)
+            .and_then(|&id| existing(id))
+            .map_or_else(|| header.chunks.last().unwrap().end(), |chunk| chunk.offset)
+    });
+    let append_chunks: Vec<&[u8]> = order[first_resized.unwrap_or(order.len())..]
+        .iter()
+        .filter_map(|&id| new_chunk(id))
+        .map(|(_, bytes)| bytes.as_slice())
+        .collect();
+    // a RIFF file greater than 4 GiB is rewritten as RF64
+    let riff_size = append_at.filter(|_| appendable).and_then(|offset| {
+        // Cast safety: u64 >= usize
+        let size = append_chunks
+            .iter()
+            .map(|chunk| chunk.len() as u64)
+            .sum::<u64>();
+        riff_size_field(header.container, offset - 8 + size)
+    });
+
+    // the new chunks before the first resized one are overwritten
+    let patches: Vec<(u64, &[u8])> = order[..first_resized.unwrap_or(order.len())]
+        .iter()
+        .filter_map(|&id| Some((existing(id)?.offset, new_chunk(id)?.1.as_slice())))
+        .collect();
+
+    // the first chunk that was resized, added, or removed
+    let first_changed = order
+        .iter()
+        .zip(0..)
+        .position(|(&id, index)| original.get(index) != Some(&id) || resized(id));
+    let padded = match first_changed {
+        Some(index) if !appendable => pad(header, &order, &new_chunks, index)?,
+        _ => None,
+    };
+
+    let temp = temp_path(path);
+    let result = match (first_resized, padded) {
+        (None, _) if order == original => {
+            tracing::debug!("patching chunks");
+            patch_copy(path, &temp, &patches).map(|()| UpdateMethod::Patched)
+        }
+        (_, Some((offset, bytes, end))) => {
+            tracing::debug!("padding chunks at {}", offset);
+            // the chunks after the padding are the same size
+            let mut patches = patches;
+            patches.push((offset, &bytes));
+            patches.extend(
+                order[end..]
+                    .iter()
+                    .filter_map(|&id| Some((existing(id)?.offset, new_chunk(id)?.1.as_slice()))),
+            );
+            patch_copy(path, &temp, &patches).map(|()| UpdateMethod::Padded)
+        }
+        (Some(_), None) if riff_size.is_some() => {
+            // Panic safety: the RIFF size is only computed if appending
+            let offset = append_at.unwrap();
+            let riff_size = riff_size.unwrap();
+            tracing::debug!("appending chunks at {}", offset);
+            append(path, &temp, &patches, offset, &append_chunks, riff_size)
+                .map(|()| UpdateMethod::Appended)
+        }
+        _ => {
+            tracing::debug!("rewriting file with new chunks");
+            rewrite(path, &temp, header, &order, &new_chunks).map(|()| UpdateMethod::Rewritten)
+        }
+    };
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    let method = result.wrap_err("Error writing temporary file")?;
+
+    if let Some(backup) = backup {
+        tracing::debug!("backing up `{}` to `{}`", path.display(), backup.display());
+        let _ = fs::remove_file(backup);
+        // a hard link avoids copying the audio data, but isn't always possible
+        let result = fs::hard_link(path, backup).or_else(|_| fs::copy(path, backup).map(|_| ()));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        result.wrap_err_with(|| format!("Error writing backup `{}`", backup.display()))?;
+    }
+
+    fs::rename(&temp, path).wrap_err("Error replacing file")?;
+    Ok(method)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,302 @@
+use super::*;
+use crate::wave::{
+    read, read_header, write, write_with, BitsPerSample, Channels, Format, Info, Sampler,
+    SamplesPerSec,
+};
+use crate::WaveFile;
+
+fn wav(cue: Option<Vec<u32>>) -> Vec<u8> {
+    wav_with(cue, CuePosition::Original)
+}
+
+fn wav_with(cue: Option<Vec<u32>>, cue_position: CuePosition) -> Vec<u8> {
+    let fmt = Format {
+        channels: Channels::One,
+        samples_per_sec: SamplesPerSec::Hz22050,
+        bits_per_sample: BitsPerSample::Eight,
+    };
+    let wav = WaveFile {
+        cue,
+        ..WaveFile::new(fmt, (0..101u8).collect())
+    };
+    write_with(&wav, cue_position).unwrap()
+}
+
+fn update(name: &str, before: Option<Vec<u32>>, after: Vec<u32>) -> (Vec<u8>, UpdateMethod) {
+    update_with(name, wav(before), after, CuePosition::Original)
+}
+
+fn update_with(
+    name: &str,
+    original: Vec<u8>,
+    after: Vec<u32>,
+    cue_position: CuePosition,
+) -> (Vec<u8>, UpdateMethod) {
+    let dir = std::env::temp_dir().join(format!("zcue-update-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("test.wav");
+    let backup = dir.join("test.wav.bak");
+    fs::write(&path, &original).unwrap();
+
+    let mut header = read_header(BufReader::new(File::open(&path).unwrap())).unwrap();
+    header.cue = Some(after);
+    let method = update_in_place(&path, &header, cue_position, Some(&backup)).unwrap();
+
+    let updated = fs::read(&path).unwrap();
+    assert_eq!(fs::read(&backup).unwrap(), original);
+    assert!(!temp_path(&path).exists());
+    fs::remove_dir_all(&dir).unwrap();
+    (updated, method)
+}
+
+#[test]
+fn update_in_place_same_size_patches() {
+    let (updated, method) = update("patch", Some(vec![1, 2]), vec![3, 4]);
+    assert_eq!(method, UpdateMethod::Patched);
+    assert_eq!(updated, wav(Some(vec![3, 4])));
+}
+
+#[test]
+fn update_in_place_grow_rewrites() {
+    let (updated, method) = update("grow", Some(vec![1]), vec![3, 4, 5]);
+    assert_eq!(method, UpdateMethod::Rewritten);
+    assert_eq!(updated, wav(Some(vec![3, 4, 5])));
+    assert_eq!(read(&updated).unwrap().data, (0..101u8).collect::<Vec<_>>());
+}
+
+#[test]
+fn update_in_place_no_cue_rewrites() {
+    let (updated, method) = update("add", None, vec![7]);
+    assert_eq!(method, UpdateMethod::Rewritten);
+    assert_eq!(updated, wav(Some(vec![7])));
+}
+
+#[test]
+fn update_in_place_cue_after_data_appends() {
+    let original = wav_with(Some(vec![1]), CuePosition::AfterData);
+    let (updated, method) = update_with("append", original, vec![3, 4], CuePosition::Original);
+    assert_eq!(method, UpdateMethod::Appended);
+    assert_eq!(updated, wav_with(Some(vec![3, 4]), CuePosition::AfterData));
+}
+
+#[test]
+fn update_in_place_no_cue_after_data_appends() {
+    let (updated, method) = update_with("append-new", wav(None), vec![7], CuePosition::AfterData);
+    assert_eq!(method, UpdateMethod::Appended);
+    assert_eq!(updated, wav_with(Some(vec![7]), CuePosition::AfterData));
+}
+
+#[test]
+fn update_in_place_move_cue_rewrites() {
+    let original = wav_with(Some(vec![1]), CuePosition::AfterData);
+    let (updated, method) = update_with("move", original, vec![1], CuePosition::BeforeData);
+    assert_eq!(method, UpdateMethod::Rewritten);
+    assert_eq!(updated, wav(Some(vec![1])));
+}
+
+#[test]
+fn update_in_place_rf64_appends() {
+    let mut wav = read(&wav_with(Some(vec![1]), CuePosition::AfterData)).unwrap();
+    wav.container = Container::Rf64;
+    let original = write(&wav).unwrap();
+    let (updated, method) = update_with("rf64-append", original, vec![1, 2], CuePosition::Original);
+    assert_eq!(method, UpdateMethod::Appended);
+    wav.cue = Some(vec![1, 2]);
+    assert_eq!(updated, write(&wav).unwrap());
+}
+
+#[test]
+fn update_in_place_rf64_rewrites() {
+    let mut wav = read(&wav(Some(vec![1]))).unwrap();
+    wav.container = Container::Bw64;
+    let original = write(&wav).unwrap();
+    let (updated, method) =
+        update_with("rf64-rewrite", original, vec![1, 2], CuePosition::Original);
+    assert_eq!(method, UpdateMethod::Rewritten);
+    wav.cue = Some(vec![1, 2]);
+    assert_eq!(updated, write(&wav).unwrap());
+}
+
+#[test]
+fn update_in_place_writes_sampler() {
+    let dir = std::env::temp_dir().join(format!("zcue-update-smpl-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("test.wav");
+    fs::write(&path, wav(Some(vec![1, 2]))).unwrap();
+
+    let mut header = read_header(BufReader::new(File::open(&path).unwrap())).unwrap();
+    let mut sampler = Sampler::new(header.fmt.samples_per_sec);
+    sampler.loops = Sampler::loops_from_cues(&[1, 2]);
+    header.sampler = Some(sampler.clone());
+    let method = update_in_place(&path, &header, CuePosition::Original, None).unwrap();
+    assert_eq!(method, UpdateMethod::Rewritten);
+
+    // the same size again only patches the chunks
+    sampler.midi_unity_note = 72;
+    let mut header = read_header(BufReader::new(File::open(&path).unwrap())).unwrap();
+    header.sampler = Some(sampler.clone());
+    let method = update_in_place(&path, &header, CuePosition::Original, None).unwrap();
+    assert_eq!(method, UpdateMethod::Patched);
+
+    let updated = read(&fs::read(&path).unwrap()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(updated.cue, Some(vec![1, 2]));
+    assert_eq!(updated.sampler, Some(sampler));
+    assert_eq!(updated.data, (0..101u8).collect::<Vec<_>>());
+}
+
+#[test]
+fn update_in_place_strips_info() {
+    let mut wav = WaveFile {
+        info: Some(Info::new()),
+        ..read(&wav(Some(vec![1]))).unwrap()
+    };
+    wav.info
+        .as_mut()
+        .unwrap()
+        .set(ChunkId::new(*b"INAM"), "Title");
+    let original = write(&wav).unwrap();
+
+    let dir = std::env::temp_dir().join(format!("zcue-update-info-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("test.wav");
+    fs::write(&path, &original).unwrap();
+
+    let mut header = read_header(BufReader::new(File::open(&path).unwrap())).unwrap();
+    assert_eq!(header.info, wav.info);
+    header.info = None;
+    let method = update_in_place(&path, &header, CuePosition::Original, None).unwrap();
+    assert_eq!(method, UpdateMethod::Padded);
+
+    let updated = fs::read(&path).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    // the list is replaced by padding
+    assert_eq!(updated.len(), original.len());
+    assert!(!updated.windows(5).any(|bytes| bytes == b"Title"));
+    let updated = read(&updated).unwrap();
+    assert_eq!(updated.info, None);
+    assert_eq!(updated.cue, wav.cue);
+    assert_eq!(updated.data, wav.data);
+}
+
+/// Insert a `JUNK` chunk with the given content size before the data chunk.
+fn with_junk(original: &[u8], size: u32) -> Vec<u8> {
+    let header = read_header(std::io::Cursor::new(original)).unwrap();
+    // Cast safety: the test file is small
+    let offset = header.data_offset as usize - 8;
+    let mut buf = original[..offset].to_vec();
+    buf.extend_from_slice(b"JUNK");
+    buf.extend_from_slice(&size.to_le_bytes());
+    buf.resize(buf.len() + size as usize, 0);
+    buf.extend_from_slice(&original[offset..]);
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    buf
+}
+
+#[test]
+fn update_in_place_shrink_pads() {
+    let original = wav(Some(vec![1, 2, 3]));
+    let (updated, method) = update("shrink", Some(vec![1, 2, 3]), vec![4]);
+    assert_eq!(method, UpdateMethod::Padded);
+    // the cue chunk is 48 bytes smaller, which is taken up by padding
+    assert_eq!(updated, with_junk(&wav(Some(vec![4])), 40));
+    assert_eq!(updated.len(), original.len());
+}
+
+#[test]
+fn update_in_place_grow_into_junk_pads() {
+    let original = with_junk(&wav(Some(vec![1])), 56);
+    let (updated, method) = update_with("junk", original, vec![1, 2, 3], CuePosition::Original);
+    assert_eq!(method, UpdateMethod::Padded);
+    assert_eq!(updated, with_junk(&wav(Some(vec![1, 2, 3])), 8));
+}
+
+#[test]
+fn update_in_place_add_cue_into_junk_pads() {
+    let original = with_junk(&wav(None), 100);
+    let (updated, method) = update_with("junk-new", original, vec![7], CuePosition::Original);
+    assert_eq!(method, UpdateMethod::Padded);
+    assert_eq!(updated, with_junk(&wav(Some(vec![7])), 64));
+}
+
+#[test]
+fn update_in_place_too_little_junk_rewrites() {
+    // 4 bytes would be left over, which is too small for a chunk
+    let original = with_junk(&wav(Some(vec![1])), 20);
+    let (updated, method) = update_with("junk-small", original, vec![1, 2], CuePosition::Original);
+    assert_eq!(method, UpdateMethod::Rewritten);
+    assert_eq!(updated, with_junk(&wav(Some(vec![1, 2])), 20));
+}
+
+#[test]
+fn update_in_place_failure_keeps_original() {
+    let dir = std::env::temp_dir().join(format!("zcue-update-fail-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("test.wav");
+    let original = wav(Some(vec![1, 2, 3]));
+    fs::write(&path, &original).unwrap();
+    // a directory where the temporary file goes makes writing it fail
+    fs::create_dir_all(temp_path(&path)).unwrap();
+
+    let mut header = read_header(BufReader::new(File::open(&path).unwrap())).unwrap();
+    let mut results = Vec::new();
+    // patched, then padded
+    for cue in [vec![4, 5, 6], vec![7]] {
+        header.cue = Some(cue);
+        results.push(update_in_place(&path, &header, CuePosition::Original, None).is_err());
+    }
+    let after = fs::read(&path).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(results, vec![true, true]);
+    assert_eq!(after, original);
+}
+
+#[cfg(unix)]
+#[test]
+fn update_in_place_patch_replaces_file() {
+    use std::os::unix::fs::{MetadataExt as _, PermissionsExt as _};
+
+    let dir = std::env::temp_dir().join(format!("zcue-update-inode-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("test.wav");
+    fs::write(&path, wav(Some(vec![1, 2, 3]))).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    let inode = fs::metadata(&path).unwrap().ino();
+
+    let mut header = read_header(BufReader::new(File::open(&path).unwrap())).unwrap();
+    header.cue = Some(vec![4, 5, 6]);
+    let method = update_in_place(&path, &header, CuePosition::Original, None).unwrap();
+    assert_eq!(method, UpdateMethod::Patched);
+
+    // the patched copy replaced the file, keeping its permissions
+    let metadata = fs::metadata(&path).unwrap();
+    let updated = fs::read(&path).unwrap();
+    let temp_exists = temp_path(&path).exists();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_ne!(metadata.ino(), inode);
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o640);
+    assert!(!temp_exists);
+    assert_eq!(read(&updated).unwrap().cue, Some(vec![4, 5, 6]));
+}
+
+#[cfg(unix)]
+#[test]
+fn update_in_place_rewrite_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    let dir = std::env::temp_dir().join(format!("zcue-update-mode-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("test.wav");
+    fs::write(&path, wav(Some(vec![1]))).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+    let mut header = read_header(BufReader::new(File::open(&path).unwrap())).unwrap();
+    header.cue = Some(vec![1, 2, 3]);
+    let method = update_in_place(&path, &header, CuePosition::Original, None).unwrap();
+    assert_eq!(method, UpdateMethod::Rewritten);
+
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(mode & 0o777, 0o640);
+}
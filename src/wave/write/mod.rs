@@ -1,48 +1,164 @@
 mod writer;
 
+use super::bext::BEXT_CHUNK_MIN_SIZE;
 use super::*;
-use color_eyre::eyre::{Context as _, OptionExt as _};
+use eyre::{Context as _, OptionExt as _};
 use std::io::{Result, Write};
 use writer::CountingWriter;
 
-fn size_wav_file(wav: &WaveFile) -> color_eyre::eyre::Result<(u32, u32, u32)> {
-    let riff_size: u32 = 4u32;
-    let header_size: u32 = 8u32;
-    let fmt_size: u32 = header_size + 16u32;
-    let mut chunk_size = riff_size + fmt_size + header_size;
-
-    let cue_size: u32 = match &wav.cue {
-        Some(cue) => {
-            let len: u32 = cue.len().try_into().wrap_err("Way too many cue points")?;
-            let cue_size = len
-                .checked_mul(24)
-                .ok_or_eyre("Way too many cue points")?
-                .checked_add(4u32)
-                .ok_or_eyre("Way too many cue points")?;
-            // ok, because chunk_size is fixed and small here
-            chunk_size += header_size;
-            chunk_size = chunk_size
-                .checked_add(cue_size)
-                .ok_or_eyre("Way too many cue points")?;
-            cue_size
+fn size_cue_chunk(cue: &[u32]) -> eyre::Result<u32> {
+    let len: u32 = cue.len().try_into().wrap_err("Way too many cue points")?;
+    len.checked_mul(24)
+        .ok_or_eyre("Way too many cue points")?
+        .checked_add(4u32)
+        .ok_or_eyre("Way too many cue points")
+}
+
+fn size_smpl_chunk(sampler: &Sampler) -> eyre::Result<u32> {
+    let loop_count: u32 = sampler
+        .loops
+        .len()
+        .try_into()
+        .wrap_err("Way too many sample loops")?;
+    let data_size: u32 = sampler
+        .sampler_data
+        .len()
+        .try_into()
+        .wrap_err("Way too much sampler data")?;
+    loop_count
+        .checked_mul(24)
+        .and_then(|size| size.checked_add(36))
+        .and_then(|size| size.checked_add(data_size))
+        .ok_or_eyre("SMPL chunk is greater than 4 GiB")
+}
+
+fn size_list_chunk(info: &Info) -> eyre::Result<u32> {
+    // the list type
+    let mut size = 4u32;
+    for (id, text) in info.iter_bytes() {
+        if text.contains(&0) {
+            eyre::bail!("INFO text for `{}` contains a NUL character", id);
         }
-        None => 0,
-    };
+        let text_size: u32 = text
+            .len()
+            .checked_add(1)
+            .and_then(|len| len.try_into().ok())
+            .ok_or_eyre("LIST chunk is greater than 4 GiB")?;
+        size = size
+            .checked_add(8)
+            .and_then(|size| size.checked_add(text_size))
+            .and_then(|size| size.checked_add(text_size & 1))
+            .ok_or_eyre("LIST chunk is greater than 4 GiB")?;
+    }
+    Ok(size)
+}
 
-    let data_size: u32 = wav
-        .data
+fn size_bext_chunk(bext: &Bext) -> eyre::Result<u32> {
+    let history_size: u32 = bext
+        .coding_history
         .len()
         .try_into()
-        .wrap_err("DATA chunk is greater than 4 GiB")?;
-    let data_pad = data_size & 1;
+        .wrap_err("BEXT chunk is greater than 4 GiB")?;
+    history_size
+        .checked_add(BEXT_CHUNK_MIN_SIZE)
+        .ok_or_eyre("BEXT chunk is greater than 4 GiB")
+}
 
-    let chunk_size = chunk_size
-        .checked_add(data_size)
-        .ok_or_eyre("File size is greater than 4 GiB")?
-        .checked_add(data_pad)
-        .ok_or_eyre("File size is greater than 4 GiB")?;
+/// The sizes of the chunks of a wave file, excluding the chunk headers.
+struct ChunkSizes {
+    /// The container to write, which is RF64 if the file is too large for
+    /// RIFF
+    container: Container,
+    riff: u64,
+    fmt: u32,
+    cue: u32,
+    smpl: u32,
+    list: u32,
+    bext: u32,
+    ixml: u32,
+    data: u64,
+}
 
-    Ok((chunk_size, cue_size, data_size))
+fn size_wav_file(wav: &WaveFile) -> eyre::Result<ChunkSizes> {
+    let header_size = 8u64;
+    let fmt = fmt_chunk_size(&wav.encoding, wav.fmt_extension)?;
+    let cue = match &wav.cue {
+        Some(cue) => size_cue_chunk(cue)?,
+        None => 0,
+    };
+    let smpl = match &wav.sampler {
+        Some(sampler) => size_smpl_chunk(sampler)?,
+        None => 0,
+    };
+    let list = match &wav.info {
+        Some(info) => size_list_chunk(info)?,
+        None => 0,
+    };
+    let bext = match &wav.bext {
+        Some(bext) => size_bext_chunk(bext)?,
+        None => 0,
+    };
+    let ixml = match &wav.ixml {
+        Some(ixml) => ixml
+            .len()
+            .try_into()
+            .wrap_err("IXML chunk is greater than 4 GiB")?,
+        None => 0,
+    };
+    // Cast safety: u64 >= usize
+    let data = wav.data.len() as u64;
+
+    let mut chunks = vec![fmt.into(), data];
+    if wav.fact.is_some() {
+        chunks.push(4);
+    }
+    if wav.cue.is_some() {
+        chunks.push(cue.into());
+    }
+    if wav.sampler.is_some() {
+        chunks.push(smpl.into());
+    }
+    if wav.info.is_some() {
+        chunks.push(list.into());
+    }
+    if wav.bext.is_some() {
+        chunks.push(bext.into());
+    }
+    if wav.ixml.is_some() {
+        chunks.push(ixml.into());
+    }
+    // the form type
+    let mut riff = 4u64;
+    for size in chunks {
+        riff = riff
+            .checked_add(header_size)
+            .and_then(|riff| riff.checked_add(size))
+            .and_then(|riff| riff.checked_add(size & 1))
+            .ok_or_eyre("File size is way too large")?;
+    }
+
+    let container = match wav.container {
+        Container::Riff if riff > u32::MAX.into() => {
+            tracing::debug!("file size is greater than 4 GiB, writing RF64");
+            Container::Rf64
+        }
+        container => container,
+    };
+    if container != Container::Riff {
+        riff += header_size + u64::from(DS64_CHUNK_MIN_SIZE);
+    }
+
+    Ok(ChunkSizes {
+        container,
+        riff,
+        fmt,
+        cue,
+        smpl,
+        list,
+        bext,
+        ixml,
+        data,
+    })
 }
 
 fn write_chunk_header(
@@ -61,25 +177,111 @@ fn write_chunk_header(
     Ok(())
 }
 
-fn write_riff_chunk(write: &mut CountingWriter<impl Write>, size: u32) -> Result<()> {
-    write_chunk_header(write, RIFF_CHUNK_ID, size)?;
+fn write_riff_chunk(
+    write: &mut CountingWriter<impl Write>,
+    container: Container,
+    size: u64,
+) -> Result<()> {
+    // Cast safety: RIFF files have been checked to be less than 4 GiB, and
+    // the size of RF64 files is in the ds64 chunk
+    let size = match container {
+        Container::Riff => size as u32,
+        Container::Rf64 | Container::Bw64 => u32::MAX,
+    };
+    write_chunk_header(write, container.chunk_id(), size)?;
     write.write_all(FORM_TYPE_WAVE.as_ref())?;
     Ok(())
 }
 
-fn write_fmt_chunk(write: &mut CountingWriter<impl Write>, fmt: &Format) -> Result<()> {
-    write_chunk_header(write, FMT_CHUNK_ID, 16)?;
+fn write_ds64_chunk(
+    write: &mut CountingWriter<impl Write>,
+    riff_size: u64,
+    data_size: u64,
+    sample_count: u64,
+) -> Result<()> {
+    write_chunk_header(write, DS64_CHUNK_ID, DS64_CHUNK_MIN_SIZE)?;
+    write.write_u64(riff_size)?;
+    write.write_u64(data_size)?;
+    write.write_u64(sample_count)?;
+    // no other chunks are greater than 4 GiB
+    write.write_u32(0)?;
+    Ok(())
+}
+
+fn fmt_chunk_size(encoding: &Encoding, extension: bool) -> eyre::Result<u32> {
+    let size = match encoding {
+        Encoding::Pcm if extension => FMT_CHUNK_SIZE_EXTENSION,
+        Encoding::Pcm => FMT_CHUNK_SIZE,
+        Encoding::ImaAdpcm { .. } => FMT_CHUNK_SIZE_EXTENSION + 2,
+        Encoding::MsAdpcm { coefficients, .. } => {
+            let count: u16 = coefficients
+                .len()
+                .try_into()
+                .wrap_err("Way too many coefficients")?;
+            FMT_CHUNK_SIZE_EXTENSION + 4 + count as u32 * 4
+        }
+    };
+    Ok(size)
+}
+
+fn write_fmt_chunk(
+    write: &mut CountingWriter<impl Write>,
+    fmt: &Format,
+    encoding: &Encoding,
+    size: u32,
+) -> Result<()> {
+    write_chunk_header(write, FMT_CHUNK_ID, size)?;
 
-    let block_align = fmt.bits_per_sample.block_align(fmt.channels);
     let samples_per_sec = fmt.samples_per_sec.as_u32();
-    let avg_bytes_per_sec = samples_per_sec * block_align as u32;
+    let layout = match encoding {
+        Encoding::Pcm => {
+            let block_align = fmt.bits_per_sample.block_align(fmt.channels);
+            let avg_bytes_per_sec = samples_per_sec * block_align as u32;
+
+            write.write_u16(WAVE_FORMAT_PCM)?;
+            write.write_u16(fmt.channels.as_u16())?;
+            write.write_u32(samples_per_sec)?;
+            write.write_u32(avg_bytes_per_sec)?;
+            write.write_u16(block_align)?;
+            write.write_u16(fmt.bits_per_sample.as_u16())?;
+            if size == FMT_CHUNK_SIZE_EXTENSION {
+                write.write_u16(0)?; // extension size
+            }
+            return Ok(());
+        }
+        Encoding::ImaAdpcm { layout } => {
+            write.write_u16(WAVE_FORMAT_IMA_ADPCM)?;
+            layout
+        }
+        Encoding::MsAdpcm { layout, .. } => {
+            write.write_u16(WAVE_FORMAT_MS_ADPCM)?;
+            layout
+        }
+    };
 
-    write.write_u16(WAVE_FORMAT_PCM)?;
     write.write_u16(fmt.channels.as_u16())?;
     write.write_u32(samples_per_sec)?;
-    write.write_u32(avg_bytes_per_sec)?;
-    write.write_u16(block_align)?;
-    write.write_u16(fmt.bits_per_sample.as_u16())?;
+    write.write_u32(layout.avg_bytes_per_sec)?;
+    write.write_u16(layout.block_align)?;
+    // Bits per sample
+    write.write_u16(4)?;
+    // Cast safety: this has already been validated by `fmt_chunk_size`
+    write.write_u16((size - FMT_CHUNK_SIZE_EXTENSION) as u16)?; // extension size
+    write.write_u16(layout.samples_per_block)?;
+    if let Encoding::MsAdpcm { coefficients, .. } = encoding {
+        // Cast safety: this has already been validated by `fmt_chunk_size`
+        write.write_u16(coefficients.len() as u16)?;
+        for (coefficient1, coefficient2) in coefficients {
+            write.write_u16(*coefficient1 as u16)?;
+            write.write_u16(*coefficient2 as u16)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_fact_chunk(write: &mut CountingWriter<impl Write>, sample_count: u32) -> Result<()> {
+    write_chunk_header(write, FACT_CHUNK_ID, 4)?;
+    write.write_u32(sample_count)?;
     Ok(())
 }
 
@@ -87,33 +289,133 @@ fn write_cue_point(
     write: &mut CountingWriter<impl Write>,
     index: u32,
     position: u32,
+    encoding: &Encoding,
 ) -> Result<()> {
     tracing::trace!("writing cue point {} at {}", index, write.offset);
+    let (block_start, sample_start) = encoding.cue_block(position);
     write.write_u32(index)?;
     write.write_u32(position)?;
     write.write_all(DATA_CHUNK_ID.as_ref())?;
     write.write_u32(0)?; // chunk_start
-    write.write_u32(0)?; // block_start
-    write.write_u32(position)?; // sample_start
+    write.write_u32(block_start)?;
+    write.write_u32(sample_start)?;
     tracing::trace!("cue point {} is {}", index, position);
     Ok(())
 }
 
-fn write_cue_chunk(write: &mut CountingWriter<impl Write>, cue: &[u32], size: u32) -> Result<()> {
+fn write_cue_chunk(
+    write: &mut CountingWriter<impl Write>,
+    cue: &[u32],
+    size: u32,
+    encoding: &Encoding,
+) -> Result<()> {
     write_chunk_header(write, CUE_CHUNK_ID, size)?;
 
-    // Cast safety: this has already been validated by `size_wav_file`
+    // Cast safety: this has already been validated by `size_cue_chunk`
     let cue_point_count = cue.len() as u32;
     write.write_u32(cue_point_count)?;
 
     for (position, index) in cue.iter().copied().zip(1u32..) {
-        write_cue_point(write, index, position)?
+        write_cue_point(write, index, position, encoding)?
     }
     Ok(())
 }
 
-fn write_data_chunk(write: &mut CountingWriter<impl Write>, data: &[u8], size: u32) -> Result<()> {
-    write_chunk_header(write, DATA_CHUNK_ID, size)?;
+fn write_smpl_chunk(
+    write: &mut CountingWriter<impl Write>,
+    sampler: &Sampler,
+    size: u32,
+) -> Result<()> {
+    write_chunk_header(write, SMPL_CHUNK_ID, size)?;
+    write.write_u32(sampler.manufacturer)?;
+    write.write_u32(sampler.product)?;
+    write.write_u32(sampler.sample_period)?;
+    write.write_u32(sampler.midi_unity_note)?;
+    write.write_u32(sampler.midi_pitch_fraction)?;
+    write.write_u32(sampler.smpte_format)?;
+    write.write_u32(sampler.smpte_offset)?;
+    // Cast safety: this has already been validated by `size_smpl_chunk`
+    write.write_u32(sampler.loops.len() as u32)?;
+    write.write_u32(sampler.sampler_data.len() as u32)?;
+    for sample_loop in &sampler.loops {
+        write.write_u32(sample_loop.cue_point_id)?;
+        write.write_u32(sample_loop.loop_type)?;
+        write.write_u32(sample_loop.start)?;
+        write.write_u32(sample_loop.end)?;
+        write.write_u32(sample_loop.fraction)?;
+        write.write_u32(sample_loop.play_count)?;
+    }
+    write.write_all(&sampler.sampler_data)?;
+    if (size & 1) == 1 {
+        write.write_u8(0)?;
+    }
+    Ok(())
+}
+
+fn write_list_chunk(write: &mut CountingWriter<impl Write>, info: &Info, size: u32) -> Result<()> {
+    write_chunk_header(write, LIST_CHUNK_ID, size)?;
+    write.write_all(LIST_TYPE_INFO.as_ref())?;
+    for (id, text) in info.iter_bytes() {
+        // Cast safety: this has already been validated by `size_list_chunk`
+        let text_size = text.len() as u32 + 1;
+        write_chunk_header(write, id, text_size)?;
+        write.write_all(text)?;
+        write.write_u8(0)?;
+        if (text_size & 1) == 1 {
+            write.write_u8(0)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_bext_chunk(write: &mut CountingWriter<impl Write>, bext: &Bext, size: u32) -> Result<()> {
+    write_chunk_header(write, BEXT_CHUNK_ID, size)?;
+    write.write_all(&bext.description)?;
+    write.write_all(&bext.originator)?;
+    write.write_all(&bext.originator_reference)?;
+    write.write_all(&bext.origination_date)?;
+    write.write_all(&bext.origination_time)?;
+    // Cast safety: splitting the time reference into the low and high parts
+    write.write_u32(bext.time_reference as u32)?;
+    write.write_u32((bext.time_reference >> 32) as u32)?;
+    write.write_u16(bext.version)?;
+    write.write_all(&bext.umid)?;
+    // Cast safety: the loudness fields are signed
+    write.write_u16(bext.loudness_value as u16)?;
+    write.write_u16(bext.loudness_range as u16)?;
+    write.write_u16(bext.max_true_peak_level as u16)?;
+    write.write_u16(bext.max_momentary_loudness as u16)?;
+    write.write_u16(bext.max_short_term_loudness as u16)?;
+    write.write_all(&bext.reserved)?;
+    write.write_all(&bext.coding_history)?;
+    if (size & 1) == 1 {
+        write.write_u8(0)?;
+    }
+    Ok(())
+}
+
+fn write_ixml_chunk(write: &mut CountingWriter<impl Write>, ixml: &[u8], size: u32) -> Result<()> {
+    write_chunk_header(write, IXML_CHUNK_ID, size)?;
+    write.write_all(ixml)?;
+    if (size & 1) == 1 {
+        write.write_u8(0)?;
+    }
+    Ok(())
+}
+
+fn write_data_chunk(
+    write: &mut CountingWriter<impl Write>,
+    container: Container,
+    data: &[u8],
+    size: u64,
+) -> Result<()> {
+    // Cast safety: RIFF files have been checked to be less than 4 GiB, and
+    // the size of RF64 files is in the ds64 chunk
+    let size_field = match container {
+        Container::Riff => size as u32,
+        Container::Rf64 | Container::Bw64 => u32::MAX,
+    };
+    write_chunk_header(write, DATA_CHUNK_ID, size_field)?;
     write.write_all(data)?;
     if (size & 1) == 1 {
         write.write_u8(0)?;
@@ -124,23 +426,160 @@ fn write_data_chunk(write: &mut CountingWriter<impl Write>, data: &[u8], size: u
 fn write_wav_file(
     write: &mut CountingWriter<impl Write>,
     wav: &WaveFile,
-) -> color_eyre::eyre::Result<()> {
-    let (total_size, cue_size, data_size) = size_wav_file(wav)?;
+    cue_position: CuePosition,
+) -> eyre::Result<()> {
+    let sizes = size_wav_file(wav)?;
 
-    write_riff_chunk(write, total_size)?;
-    write_fmt_chunk(write, &wav.fmt)?;
+    let mut optional = Vec::new();
+    if wav.fact.is_some() {
+        optional.push(FACT_CHUNK_ID);
+    }
+    if wav.cue.is_some() {
+        optional.push(CUE_CHUNK_ID);
+    }
+    if wav.sampler.is_some() {
+        optional.push(SMPL_CHUNK_ID);
+    }
+    if wav.info.is_some() {
+        optional.push(LIST_CHUNK_ID);
+    }
+    if wav.bext.is_some() {
+        optional.push(BEXT_CHUNK_ID);
+    }
+    if wav.ixml.is_some() {
+        optional.push(IXML_CHUNK_ID);
+    }
 
-    if let Some(cue) = &wav.cue {
-        write_cue_chunk(write, cue, cue_size)?;
+    write_riff_chunk(write, sizes.container, sizes.riff)?;
+    if sizes.container != Container::Riff {
+        write_ds64_chunk(write, sizes.riff, sizes.data, wav.sample_count())?;
+    }
+    for chunk_id in chunk_order(&wav.chunk_order, &optional, cue_position) {
+        match chunk_id {
+            FMT_CHUNK_ID => write_fmt_chunk(write, &wav.fmt, &wav.encoding, sizes.fmt)?,
+            DATA_CHUNK_ID => write_data_chunk(write, sizes.container, &wav.data, sizes.data)?,
+            // only chunks that are present are in the order
+            FACT_CHUNK_ID => {
+                if let Some(fact) = wav.fact {
+                    write_fact_chunk(write, fact)?;
+                }
+            }
+            CUE_CHUNK_ID => {
+                if let Some(cue) = &wav.cue {
+                    write_cue_chunk(write, cue, sizes.cue, &wav.encoding)?;
+                }
+            }
+            SMPL_CHUNK_ID => {
+                if let Some(sampler) = &wav.sampler {
+                    write_smpl_chunk(write, sampler, sizes.smpl)?;
+                }
+            }
+            LIST_CHUNK_ID => {
+                if let Some(info) = &wav.info {
+                    write_list_chunk(write, info, sizes.list)?;
+                }
+            }
+            BEXT_CHUNK_ID => {
+                if let Some(bext) = &wav.bext {
+                    write_bext_chunk(write, bext, sizes.bext)?;
+                }
+            }
+            IXML_CHUNK_ID => {
+                if let Some(ixml) = &wav.ixml {
+                    write_ixml_chunk(write, ixml, sizes.ixml)?;
+                }
+            }
+            _ => unreachable!("unexpected chunk `{}`", chunk_id),
+        }
     }
 
-    write_data_chunk(write, &wav.data, data_size)?;
     tracing::trace!("wrote {} bytes", write.offset);
     Ok(())
 }
 
-pub(crate) fn write(wav: &WaveFile) -> color_eyre::eyre::Result<Vec<u8>> {
+/// Write a wave file, keeping the original chunk order.
+///
+/// If the file is greater than 4 GiB, it is written as RF64.
+pub fn write(wav: &WaveFile) -> crate::Result<Vec<u8>> {
+    write_with(wav, CuePosition::Original)
+}
+
+/// Write a wave file, with the cue chunk at the given position.
+pub fn write_with(wav: &WaveFile, cue_position: CuePosition) -> crate::Result<Vec<u8>> {
+    let mut write = CountingWriter::new(Vec::new());
+    write_wav_file(&mut write, wav, cue_position)?;
+    Ok(write.into_inner())
+}
+
+/// Write a cue chunk, including the chunk header.
+pub(crate) fn write_cue(cue: &[u32], encoding: &Encoding) -> eyre::Result<Vec<u8>> {
+    let size = size_cue_chunk(cue)?;
+    let mut write = CountingWriter::new(Vec::new());
+    write_cue_chunk(&mut write, cue, size, encoding)?;
+    Ok(write.into_inner())
+}
+
+/// Write the RIFF header, and for RF64 and BW64 the ds64 chunk.
+pub(crate) fn write_riff_header(
+    container: Container,
+    riff_size: u64,
+    data_size: u64,
+    sample_count: u64,
+) -> eyre::Result<Vec<u8>> {
+    let mut write = CountingWriter::new(Vec::new());
+    write_riff_chunk(&mut write, container, riff_size)?;
+    if container != Container::Riff {
+        write_ds64_chunk(&mut write, riff_size, data_size, sample_count)?;
+    }
+    Ok(write.into_inner())
+}
+
+/// Write a sampler chunk, including the chunk header and padding.
+pub(crate) fn write_smpl(sampler: &Sampler) -> eyre::Result<Vec<u8>> {
+    let size = size_smpl_chunk(sampler)?;
+    let mut write = CountingWriter::new(Vec::new());
+    write_smpl_chunk(&mut write, sampler, size)?;
+    Ok(write.into_inner())
+}
+
+/// Write a `LIST` chunk with the `INFO` list, including the chunk header.
+pub(crate) fn write_list(info: &Info) -> eyre::Result<Vec<u8>> {
+    let size = size_list_chunk(info)?;
+    let mut write = CountingWriter::new(Vec::new());
+    write_list_chunk(&mut write, info, size)?;
+    Ok(write.into_inner())
+}
+
+/// Write a Broadcast Wave Format extension chunk, including the chunk header
+/// and padding.
+pub(crate) fn write_bext(bext: &Bext) -> eyre::Result<Vec<u8>> {
+    let size = size_bext_chunk(bext)?;
+    let mut write = CountingWriter::new(Vec::new());
+    write_bext_chunk(&mut write, bext, size)?;
+    Ok(write.into_inner())
+}
+
+/// Write an iXML chunk, including the chunk header and padding.
+pub(crate) fn write_ixml(ixml: &[u8]) -> eyre::Result<Vec<u8>> {
+    let size = ixml
+        .len()
+        .try_into()
+        .wrap_err("IXML chunk is greater than 4 GiB")?;
+    let mut write = CountingWriter::new(Vec::new());
+    write_ixml_chunk(&mut write, ixml, size)?;
+    Ok(write.into_inner())
+}
+
+/// Write a `JUNK` chunk of zeros, with an even total size including the
+/// chunk header of at least 8 bytes.
+pub(crate) fn write_junk(total_size: u64) -> eyre::Result<Vec<u8>> {
+    let size: u32 = total_size
+        .checked_sub(8)
+        .and_then(|size| size.try_into().ok())
+        .ok_or_else(|| eyre::eyre!("Invalid JUNK chunk size {}", total_size))?;
     let mut write = CountingWriter::new(Vec::new());
-    write_wav_file(&mut write, wav)?;
+    write_chunk_header(&mut write, JUNK_CHUNK_ID, size)?;
+    // Cast safety: u32 <= usize on supported platforms
+    write.write_all(&vec![0; size as usize])?;
     Ok(write.into_inner())
 }
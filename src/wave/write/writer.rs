@@ -25,6 +25,12 @@ impl<W: Write> CountingWriter<W> {
         self.inner.write_all(buf)
     }
 
+    #[inline]
+    pub fn write_u64(&mut self, value: u64) -> Result<()> {
+        let buf = value.to_le_bytes();
+        self.write_all(&buf)
+    }
+
     #[inline]
     pub fn write_u32(&mut self, value: u32) -> Result<()> {
         let buf = value.to_le_bytes();